@@ -2,15 +2,19 @@
 //! alias. Included by both `main.rs` files via `#[path]`, so this same
 //! source compiles twice, once per binary target.
 
+use std::io::Write;
+use std::path::Path;
+
 use clap::{CommandFactory, FromArgMatches};
 use color_eyre::eyre::{Result, WrapErr};
 
 use self::commands::Cli;
-use feedyourai::{config, run_git, run_local};
+use feedyourai::scanner::ScanStats;
+use feedyourai::{config, run_git, run_git_to_writer, run_local, run_local_to_writer};
 
 /// System-clipboard access for copying the combined output.
 mod clipboard;
-/// Argument parsing and the `init` subcommand.
+/// Argument parsing and the `init` and `cache` subcommands.
 mod commands;
 
 /// Runs the CLI end to end: installs `color_eyre`'s error/panic hooks, then
@@ -21,8 +25,9 @@ pub(crate) fn run() -> Result<()> {
 }
 
 /// Parses `args`, resolves configuration (merging any `fyai.toml` with CLI
-/// flags), runs the combine, and reports the result to stdout/stderr,
-/// including a best-effort clipboard copy when `--clipboard` was passed.
+/// flags), runs the combine, and reports the result on stderr (leaving
+/// stdout free for `-o -`), including a best-effort clipboard copy when
+/// `--clipboard` was passed.
 ///
 /// Split out from [`run`] so it can be exercised directly, with an explicit
 /// argument list, from tests — `run` itself can't be called more than once
@@ -38,6 +43,9 @@ where
     if commands::init::handle_init_subcommand(&cli)? {
         return Ok(());
     }
+    if commands::cache::handle_cache_subcommand(&cli)? {
+        return Ok(());
+    }
 
     let repo_url = cli.repo.clone();
     let repo_branch = cli.repo_branch.clone();
@@ -48,7 +56,7 @@ where
     let file_config = match config::discover_config_file() {
         Some(path) => match config::PartialConfig::from_path(&path) {
             Ok(cfg) => {
-                println!("Loaded config from: {}", path.display());
+                eprintln!("Loaded config from: {}", path.display());
                 cfg
             }
             Err(e) => {
@@ -65,44 +73,94 @@ where
 
     let config = config::merge_config(file_config, cli_config);
     let output_path = config.output.clone();
+    let to_stdout = config.output_is_stdout();
     let tree_only = config.tree_only;
 
-    let stats = if let Some(repo_url) = repo_url {
-        run_git(
-            &repo_url,
+    // The clipboard gets exactly what was written, so capture it on the way
+    // out rather than reading the output back. Chunked output has no single
+    // text to copy.
+    let capture = cli.clipboard && !tree_only;
+    if capture && config.chunk_size.is_some() {
+        eprintln!("Warning: output is split into chunks; skipping clipboard copy.");
+    }
+    let mut captured = (capture && config.chunk_size.is_none()).then(Vec::new);
+
+    let context = if repo_url.is_some() {
+        "failed to process git repository"
+    } else {
+        "failed to process local directory"
+    };
+    let stats = match (&repo_url, captured.as_mut()) {
+        (Some(repo_url), None) => run_git(
+            repo_url,
             repo_branch.as_deref(),
             repo_commit.as_deref(),
             config,
-        )
-        .wrap_err("failed to process git repository")?
-    } else {
-        run_local(config).wrap_err("failed to process local directory")?
-    };
+        ),
+        (Some(repo_url), Some(buffer)) => run_git_to_writer(
+            repo_url,
+            repo_branch.as_deref(),
+            repo_commit.as_deref(),
+            config,
+            buffer,
+        ),
+        (None, None) => run_local(config),
+        (None, Some(buffer)) => run_local_to_writer(config, buffer),
+    }
+    .wrap_err(context)?;
+
+    if let Some(buffer) = &captured {
+        write_output(&output_path, to_stdout, buffer)?;
+    }
 
     if tree_only {
-        println!("Project tree written to {}", output_path.display());
-        println!("Total size walked: {}", format_size(stats.total_size));
+        print_destination("Project tree written to", &output_path, to_stdout, &stats);
+        eprintln!("Total size walked: {}", format_size(stats.total_size));
+        print_diff_size(&stats);
+        print_redactions(&stats);
+        eprintln!("Tokens ({}): {}", stats.tokenizer, stats.tokens);
         return Ok(());
     }
 
-    let output_contents = std::fs::read_to_string(&output_path)
-        .wrap_err_with(|| format!("failed to read output file {}", output_path.display()))?;
-
-    println!("Files combined successfully into {}", output_path.display());
-    println!("Total size walked: {}", format_size(stats.total_size));
-    println!(
+    print_destination(
+        "Files combined successfully into",
+        &output_path,
+        to_stdout,
+        &stats,
+    );
+    eprintln!("Total size walked: {}", format_size(stats.total_size));
+    eprintln!(
         "  Non-binary (written): {}",
         format_size(stats.written_size)
     );
-    println!("  Binary (skipped): {}", format_size(stats.binary_size));
+    eprintln!("  Binary (skipped): {}", format_size(stats.binary_size));
     let size_filtered = stats.size_filtered();
     if size_filtered > 0 {
-        println!("  Skipped by size filter: {}", format_size(size_filtered));
+        eprintln!("  Skipped by size filter: {}", format_size(size_filtered));
+    }
+    if stats.grep_filtered_size > 0 {
+        eprintln!(
+            "  Skipped by grep filter: {}",
+            format_size(stats.grep_filtered_size)
+        );
+    }
+    if stats.budget_dropped_size > 0 {
+        eprintln!(
+            "  Dropped for token budget: {}",
+            format_size(stats.budget_dropped_size)
+        );
+    }
+    if stats.stripped_size > 0 {
+        eprintln!("  Comments stripped: {}", format_size(stats.stripped_size));
     }
+    print_diff_size(&stats);
+    print_redactions(&stats);
+    eprintln!("Tokens ({}): {}", stats.tokenizer, stats.tokens);
 
-    if cli.clipboard {
+    if let Some(buffer) = captured {
+        let output_contents = String::from_utf8_lossy(&buffer);
         match clipboard::copy_to_clipboard(&output_contents) {
-            Ok(()) => println!("Output copied to clipboard successfully!"),
+            Ok(()) => eprintln!("Output copied to clipboard successfully!"),
             Err(err) if clipboard::should_ignore_clipboard_error() => {
                 eprintln!("Warning: clipboard unavailable; skipping copy. {}", err);
             }
@@ -113,6 +171,69 @@ where
     Ok(())
 }
 
+/// Writes the captured combined `output` to `output_path`, or to stdout.
+fn write_output(output_path: &Path, to_stdout: bool, output: &[u8]) -> Result<()> {
+    if to_stdout {
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(output)
+            .and_then(|()| stdout.flush())
+            .wrap_err("failed to write output to stdout")
+    } else {
+        std::fs::write(output_path, output)
+            .wrap_err_with(|| format!("failed to write output file {}", output_path.display()))
+    }
+}
+
+/// Prints `message` to stderr followed by where the output went: stdout,
+/// `output_path`, or, for chunked output, the number of chunks and one line
+/// per chunk.
+fn print_destination(message: &str, output_path: &Path, to_stdout: bool, stats: &ScanStats) {
+    if to_stdout {
+        eprintln!("{message} stdout");
+        return;
+    }
+    if stats.chunks.is_empty() {
+        eprintln!("{message} {}", output_path.display());
+        return;
+    }
+
+    eprintln!("{message} {} chunks:", stats.chunks.len());
+    for chunk in &stats.chunks {
+        eprintln!(
+            "  {} ({}, {} tokens, {} files)",
+            chunk.path.display(),
+            format_size(chunk.size),
+            chunk.tokens,
+            chunk.files
+        );
+    }
+}
+
+/// Prints the size of the `--diff` section, if there was one.
+fn print_diff_size(stats: &ScanStats) {
+    if stats.diff_size > 0 {
+        eprintln!("Git diff: {}", format_size(stats.diff_size));
+    }
+}
+
+/// Prints how many secrets were redacted, if any, and where: one line per
+/// file, then the diff and commit subjects together.
+fn print_redactions(stats: &ScanStats) {
+    if stats.redactions == 0 {
+        return;
+    }
+    eprintln!("Secrets redacted: {}", stats.redactions);
+    let mut in_files = 0;
+    for file in stats.files.iter().filter(|file| file.redactions > 0) {
+        eprintln!("  {}: {}", file.display_path.display(), file.redactions);
+        in_files += file.redactions;
+    }
+    if stats.redactions > in_files {
+        eprintln!("  Git diff and log: {}", stats.redactions - in_files);
+    }
+}
+
 /// Formats `bytes` as a human-readable size (`"512 B"`, `"1.2 KB"`, `"3.4
 /// MB"`, ...), using 1024 as the unit step.
 ///
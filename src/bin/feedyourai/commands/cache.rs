@@ -0,0 +1,143 @@
+//! Implementation of the `cache` subcommand.
+
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+use super::{CacheAction, Cli, Command};
+use color_eyre::eyre::{OptionExt, Result};
+use feedyourai::cache::CloneCache;
+
+/// If `cli` carries a `cache` subcommand, runs its action against the
+/// system clone cache and returns `Ok(true)`; otherwise returns `Ok(false)`
+/// so the caller proceeds with a normal combine run.
+pub fn handle_cache_subcommand(cli: &Cli) -> Result<bool> {
+    let Some(Command::Cache { action }) = &cli.command else {
+        return Ok(false);
+    };
+    let cache = CloneCache::system().ok_or_eyre("could not determine cache directory")?;
+
+    match action {
+        CacheAction::List => list(&cache, &mut std::io::stdout().lock())?,
+        CacheAction::Clear { url: None } => {
+            let removed = cache.clear()?;
+            eprintln!(
+                "Removed {removed} cached clone{} from {}",
+                if removed == 1 { "" } else { "s" },
+                cache.root().display()
+            );
+            let in_use = cache.entries()?.len();
+            if in_use > 0 {
+                eprintln!(
+                    "Left {in_use} cached clone{} in use by another run",
+                    if in_use == 1 { "" } else { "s" }
+                );
+            }
+        }
+        CacheAction::Clear { url: Some(url) } => {
+            if cache.remove(url)? {
+                eprintln!("Removed the cached clone of {url}");
+            } else {
+                eprintln!("No cached clone of {url}");
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Writes one line per cached clone to `output`, most recently used first:
+/// its URL, size, and how long ago it was last used. The totals go to
+/// stderr, so `output` stays one clone per line.
+fn list<W: Write>(cache: &CloneCache, output: &mut W) -> Result<()> {
+    let entries = cache.entries()?;
+    if entries.is_empty() {
+        eprintln!("No cached clones in {}", cache.root().display());
+        return Ok(());
+    }
+
+    let now = SystemTime::now();
+    for entry in &entries {
+        let age = now.duration_since(entry.last_used).unwrap_or_default();
+        writeln!(
+            output,
+            "{}\t{}\tused {}",
+            entry.url,
+            super::super::format_size(entry.size),
+            format_age(age)
+        )?;
+    }
+    let total = entries.iter().map(|entry| entry.size).sum();
+    eprintln!(
+        "{} cached clone{}, {} in {}",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        super::super::format_size(total),
+        cache.root().display()
+    );
+    Ok(())
+}
+
+/// Formats `age` in its largest whole unit: `"just now"`, `"5 minutes
+/// ago"`, `"1 hour ago"`, `"3 days ago"`.
+fn format_age(age: Duration) -> String {
+    const UNITS: [(&str, u64); 3] = [("day", 86_400), ("hour", 3_600), ("minute", 60)];
+
+    let secs = age.as_secs();
+    for (unit, unit_secs) in UNITS {
+        let count = secs / unit_secs;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural} ago");
+        }
+    }
+    "just now".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    #[test]
+    fn returns_false_without_the_cache_subcommand() {
+        let matches = Cli::command()
+            .try_get_matches_from(["fyai", "init"])
+            .unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+        assert!(!handle_cache_subcommand(&cli).unwrap());
+    }
+
+    #[test]
+    fn list_writes_one_line_per_clone() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("0123456789abcdef");
+        std::fs::create_dir_all(entry.join("repo")).unwrap();
+        std::fs::write(entry.join("url"), "https://example.com/x.git").unwrap();
+        std::fs::write(entry.join("repo/file"), vec![0u8; 2048]).unwrap();
+
+        let mut output = Vec::new();
+        list(&CloneCache::new(dir.path()), &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        // 2048 bytes of content plus the URL file.
+        assert_eq!(text, "https://example.com/x.git\t2.0 KB\tused just now\n");
+    }
+
+    #[test]
+    fn list_writes_nothing_for_an_empty_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output = Vec::new();
+        list(&CloneCache::new(dir.path().join("missing")), &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn format_age_uses_the_largest_whole_unit() {
+        assert_eq!(format_age(Duration::from_secs(59)), "just now");
+        assert_eq!(format_age(Duration::from_secs(60)), "1 minute ago");
+        assert_eq!(
+            format_age(Duration::from_secs(2 * 3_600 + 5)),
+            "2 hours ago"
+        );
+        assert_eq!(format_age(Duration::from_secs(3 * 86_400)), "3 days ago");
+    }
+}
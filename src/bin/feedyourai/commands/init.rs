@@ -39,27 +39,57 @@ pub fn handle_init_subcommand(cli: &Cli) -> Result<bool> {
 # syntax) anywhere under the scanned directory, instead of exclude_dirs/
 # exclude_files below.
 
-directory = "."
-output = "fyai.txt"
+directory = "." # or a .zip / .tar(.gz/.xz/.zst) archive
+# directories = ["backend", "../shared-protos"] # several roots, in place of directory
+output = "fyai.txt" # or "-" for stdout
 include_dirs = ["src", "docs"]
 exclude_dirs = ["node_modules", "dist"]
 include_ext = ["md", "txt"]
 exclude_ext = ["log", "tmp"]
 include_files = ["README.md", "main.rs"]
 exclude_files = ["LICENSE", "config.json"]
+# include_globs = ["src/**/*.rs"] # relative to directory
+# exclude_globs = ["**/generated/**"]
+# grep = ["PaymentIntent"] # only files whose contents match one of these regexes
+grep_invert = false
+grep_lines = false # list the matching line numbers in each file's heading
+# files_from = "files.txt" # or "-"; reads just these files instead of walking
 min_size = 10240
 max_size = 512000
+# changed_since = "main" # only files changed since the merge base with main
+staged = false
+unstaged = false
+full_tree = false
+# diff = "main" # or "main..HEAD", "main...HEAD"; appends a Git Diff section
+# git_log = 10 # appends the last 10 commits as a Recent Commits section
+# rev = "v2.3" # scans the directory as of this branch, tag, or commit
+# repo_path = "packages/api" # with --repo, scans only this subdirectory
+submodules = false
+cache = false # with --repo, reuse a cached clone instead of cloning again
+# cache_max_size = 2147483648 # evict older cached clones beyond this many bytes
 hidden = true
 gitignore = true
 ignore_files = true
 git_global = true
 follow_links = false
 tree_only = false
+strip_comments = false # remove comments from file contents, by language
+compact = false # collapse blank-line runs and trailing whitespace
+# compact_tabs = 4 # with compact, turn each 4 leading spaces into a tab
+line_numbers = false # number each line inside the code fences
+redact = true # replace credentials with [REDACTED:<kind>]
+# redact_rules = { internal_token = "itk_[0-9a-f]{32}" } # extra rules: kind = regex
 human = false
+output_format = "markdown" # or "xml", "json", "jsonl"
+tokenizer = "chars" # or "cl100k", "o200k"
+heading_tokens = false
+# max_tokens = 100000
+budget_order = "walk" # or "smallest", "recent"
+# chunk_size = "100000tokens" # or a number of bytes, e.g. 500000
 "#;
 
         std::fs::write(&path, template)?;
-        println!("Template config file written to {}", display_path);
+        eprintln!("Template config file written to {}", display_path);
         return Ok(true);
     }
     Ok(false)
@@ -1,12 +1,15 @@
-//! Argument parsing: the [`Cli`] struct, its `init` subcommand, and
+//! Argument parsing: the [`Cli`] struct, its `init` and `cache` subcommands, and
 //! conversion of parsed `clap` matches into a library
 //! [`PartialConfig`](feedyourai::config::PartialConfig).
 
 use clap::{ArgAction, Parser, Subcommand, parser::ValueSource};
 
 use color_eyre::eyre::{Result, eyre};
-use feedyourai::config::PartialConfig;
+use feedyourai::config::{BudgetOrder, ChunkSize, OutputFormat, PartialConfig};
+use feedyourai::tokens::Tokenizer;
 
+/// The `cache` subcommand: lists or clears cached clones.
+pub mod cache;
 /// The `init` subcommand: writes a starter `fyai.toml`.
 pub mod init;
 
@@ -18,26 +21,60 @@ pub mod init;
     about = "A tool to combine text files for LLM processing with flexible filtering options.\n\nCONFIG FILE SUPPORT:\n  - You can specify options in a config file (TOML format).\n  - Local config: ./fyai.toml (used if present in current directory)\n  - Global config: system config directory (used if no local config found).\n    Honors $XDG_CONFIG_HOME (any platform, if set to an absolute path),\n    else the platform default. Run `fyai init --global` to see the exact path.\n  - CLI options override config file values.\n  - You can also drop a .fyaiignore file (gitignore syntax) to exclude paths.\n  - See README for details and examples."
 )]
 pub struct Cli {
-    /// Sets the input directory.
+    /// Sets the input directory, or a `.zip` or `.tar(.gz/.xz/.zst)` archive
+    /// to scan without extracting it. Repeat it to scan several roots in one
+    /// run, each with its own tree.
     #[arg(
         short = 'i',
         long = "input",
         value_name = "DIR",
         default_value = ".",
-        help = "Sets the input directory"
+        action = ArgAction::Append,
+        help = "Sets the input directory, or a .zip or .tar(.gz/.xz/.zst) archive; repeat for several roots"
     )]
-    pub input: String,
+    pub input: Vec<String>,
 
-    /// Sets the output file.
+    /// Sets a `.zip` or `.tar(.gz/.xz/.zst)` archive to scan in place of the
+    /// input directory, without extracting it.
+    #[arg(
+        long = "archive",
+        value_name = "FILE",
+        conflicts_with_all = ["input", "repo"],
+        help = "Scans a .zip or .tar(.gz/.xz/.zst) archive instead of the input directory"
+    )]
+    pub archive: Option<String>,
+
+    /// Reads exactly the files listed in this file, or on stdin for `-`,
+    /// instead of walking the input directory. Paths are newline- or
+    /// NUL-separated and relative to the input directory.
+    #[arg(
+        long = "files-from",
+        value_name = "FILE",
+        help = "Reads exactly the files listed in FILE (or stdin for -), one per line or NUL-separated, instead of walking the input directory"
+    )]
+    pub files_from: Option<String>,
+
+    /// Sets the output file, or `-` for stdout.
     #[arg(
         short = 'o',
         long = "output",
         value_name = "FILE",
         default_value = "fyai.txt",
-        help = "Sets the output file"
+        help = "Sets the output file, or - for stdout"
     )]
     pub output: String,
 
+    /// Sets the output format: Markdown headings and fences, one XML
+    /// `<documents>` element, a JSON object, or JSON Lines \[default:
+    /// markdown\].
+    #[arg(
+        long = "format",
+        value_name = "FORMAT",
+        value_parser = ["markdown", "xml", "json", "jsonl"],
+        help = "Sets the output format: markdown, xml, json, or jsonl [default: markdown]"
+    )]
+    pub format: Option<String>,
+
     /// Sets the git repository URL to clone and scan instead of a local
     /// directory.
     #[arg(
@@ -66,6 +103,46 @@ pub struct Cli {
     )]
     pub repo_commit: Option<String>,
 
+    /// Scans only this subdirectory of the repository, sparse-checking-out
+    /// just that subtree. Requires `--repo`.
+    #[arg(
+        long = "repo-path",
+        value_name = "SUBDIR",
+        requires = "repo",
+        help = "Scan only this subdirectory of the git repository"
+    )]
+    pub repo_path: Option<String>,
+
+    /// Checks out submodules when cloning, and marks each submodule root
+    /// in the tree.
+    #[arg(
+        long = "submodules",
+        action = ArgAction::SetTrue,
+        help = "Check out submodules recursively with --repo, and mark submodule roots in the tree"
+    )]
+    pub submodules: bool,
+
+    /// Keeps the clone in the system cache directory and, on later runs of
+    /// the same URL, fetches into it instead of cloning again. Requires
+    /// `--repo`.
+    #[arg(
+        long = "cache",
+        action = ArgAction::SetTrue,
+        requires = "repo",
+        help = "Reuse a cached clone of --repo, fetching instead of cloning (see `fyai cache`)"
+    )]
+    pub cache: bool,
+
+    /// Evicts the least recently used cached clones once the cache exceeds
+    /// this size in bytes. Requires `--repo`.
+    #[arg(
+        long = "cache-max-size",
+        value_name = "BYTES",
+        requires = "repo",
+        help = "Evict the least recently used cached clones beyond this many bytes [default: 2 GiB]"
+    )]
+    pub cache_max_size: Option<u64>,
+
     /// Sets the directories to include (e.g., `src,tests`).
     #[arg(
         long = "include-dirs",
@@ -114,6 +191,53 @@ pub struct Cli {
     )]
     pub exclude_files: Option<String>,
 
+    /// Only includes files whose path relative to the input directory
+    /// matches this glob (e.g., `src/**/*.rs`). Repeatable.
+    #[arg(
+        long = "include",
+        value_name = "GLOB",
+        action = ArgAction::Append,
+        help = "Only include files whose relative path matches this glob (e.g., 'src/**/*.rs'); repeatable"
+    )]
+    pub include_globs: Option<Vec<String>>,
+
+    /// Excludes paths relative to the input directory that match this glob
+    /// (e.g., `**/generated/**`). Repeatable.
+    #[arg(
+        long = "exclude",
+        value_name = "GLOB",
+        action = ArgAction::Append,
+        help = "Exclude paths whose relative path matches this glob (e.g., '**/generated/**'); repeatable"
+    )]
+    pub exclude_globs: Option<Vec<String>>,
+
+    /// Only includes files whose contents match this regex (e.g.,
+    /// `PaymentIntent`). Repeatable; a file matching any of them is kept.
+    #[arg(
+        long = "grep",
+        value_name = "REGEX",
+        action = ArgAction::Append,
+        help = "Only include files whose contents match this regex (e.g., 'PaymentIntent'); repeatable"
+    )]
+    pub grep: Option<Vec<String>>,
+
+    /// With `--grep`, only includes files whose contents match none of the
+    /// regexes instead.
+    #[arg(
+        long = "grep-invert",
+        action = ArgAction::SetTrue,
+        help = "With --grep, only include files that match none of the regexes"
+    )]
+    pub grep_invert: bool,
+
+    /// With `--grep`, lists the lines each file matched on in its heading.
+    #[arg(
+        long = "grep-lines",
+        action = ArgAction::SetTrue,
+        help = "With --grep, list the matching line numbers in each file's heading"
+    )]
+    pub grep_lines: bool,
+
     /// Excludes files smaller than this size in bytes.
     #[arg(
         short = 'n',
@@ -132,6 +256,68 @@ pub struct Cli {
     )]
     pub max_size: Option<u64>,
 
+    /// Only includes files git reports as changed since the merge base of
+    /// this ref and `HEAD`, committed or not.
+    #[arg(
+        long = "changed-since",
+        value_name = "REF",
+        help = "Only include files changed since the merge base of REF and HEAD (committed or not)"
+    )]
+    pub changed_since: Option<String>,
+
+    /// Only includes files with staged changes.
+    #[arg(
+        long = "staged",
+        action = ArgAction::SetTrue,
+        help = "Only include files with staged changes"
+    )]
+    pub staged: bool,
+
+    /// Only includes files with unstaged changes, plus untracked files.
+    #[arg(
+        long = "unstaged",
+        action = ArgAction::SetTrue,
+        help = "Only include files with unstaged changes, plus untracked files"
+    )]
+    pub unstaged: bool,
+
+    /// With `--changed-since`/`--staged`/`--unstaged`, keeps every file in
+    /// the tree, marking the changed ones.
+    #[arg(
+        long = "full-tree",
+        action = ArgAction::SetTrue,
+        help = "With --changed-since/--staged/--unstaged, show the full tree and mark changed files"
+    )]
+    pub full_tree: bool,
+
+    /// Appends the unified diff for `<base>` (against the working tree),
+    /// `<base>..<head>`, or `<base>...<head>` (from their merge base),
+    /// limited to the files the filters allow.
+    #[arg(
+        long = "diff",
+        value_name = "BASE[..HEAD]",
+        help = "Append a Git Diff section for BASE (against the working tree), BASE..HEAD, or BASE...HEAD (from their merge base), limited to filtered paths"
+    )]
+    pub diff: Option<String>,
+
+    /// Appends the last `N` commits touching the input directory, with
+    /// their changed paths.
+    #[arg(
+        long = "git-log",
+        value_name = "N",
+        help = "Append a Recent Commits section with the last N commits touching the input directory"
+    )]
+    pub git_log: Option<u64>,
+
+    /// Scans the input directory as it was at a git revision (branch, tag,
+    /// or commit), without touching the working copy.
+    #[arg(
+        long = "rev",
+        value_name = "REF",
+        help = "Scan the input directory as of a git branch, tag, or commit"
+    )]
+    pub rev: Option<String>,
+
     /// Sets whether to skip hidden files/directories (dot-files) \[default:
     /// true\].
     #[arg(
@@ -180,11 +366,100 @@ pub struct Cli {
     #[arg(long = "tree-only", action = ArgAction::SetTrue, help = "Only output the project directory tree, no file contents")]
     pub tree_only: bool,
 
+    /// Removes line and block comments from file contents, by language.
+    #[arg(
+        long = "strip-comments",
+        action = ArgAction::SetTrue,
+        help = "Remove line and block comments from file contents, by language"
+    )]
+    pub strip_comments: bool,
+
+    /// Collapses blank-line runs and trims trailing whitespace in file
+    /// contents, except in indentation-sensitive languages.
+    #[arg(
+        long = "compact",
+        action = ArgAction::SetTrue,
+        help = "Collapse blank-line runs and trim trailing whitespace in file contents (not Python, YAML, Makefiles, Haskell, Sass, or Markdown)"
+    )]
+    pub compact: bool,
+
+    /// With `--compact`, turns each run of `WIDTH` leading spaces into a
+    /// tab.
+    #[arg(
+        long = "compact-tabs",
+        value_name = "WIDTH",
+        requires = "compact",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "With --compact, turn each run of WIDTH leading spaces into a tab"
+    )]
+    pub compact_tabs: Option<u64>,
+
+    /// Numbers each line of file contents with its line in the file.
+    #[arg(
+        long = "line-numbers",
+        action = ArgAction::SetTrue,
+        help = "Number each line of file contents with its line in the file"
+    )]
+    pub line_numbers: bool,
+
+    /// Sets whether to replace credentials in file contents and the diff
+    /// with `[REDACTED:<kind>]` \[default: true\].
+    #[arg(
+        long = "no-redact",
+        action = ArgAction::SetTrue,
+        help = "Sets whether to replace credentials (private keys, tokens, high-entropy secrets) with [REDACTED:<kind>] [default: true]"
+    )]
+    pub no_redact: bool,
+
     /// Renders the directory tree with `tree`-style connector glyphs instead
     /// of the minimal two-space indent.
     #[arg(long = "human", action = ArgAction::SetTrue, help = "Render the directory tree with tree-style connector glyphs")]
     pub human: bool,
 
+    /// Sets how tokens are counted for the run summary: a chars/4 estimate,
+    /// or the `cl100k_base`/`o200k_base` BPE vocabularies \[default: chars\].
+    #[arg(
+        long = "tokenizer",
+        value_name = "TOKENIZER",
+        value_parser = ["chars", "cl100k", "o200k"],
+        help = "Sets how tokens are counted: chars (chars/4 estimate), cl100k, or o200k [default: chars]"
+    )]
+    pub tokenizer: Option<String>,
+
+    /// Shows each file's token count next to its size in its heading.
+    #[arg(long = "heading-tokens", action = ArgAction::SetTrue, help = "Show each file's token count next to its size in its heading")]
+    pub heading_tokens: bool,
+
+    /// Caps the output at this many tokens: files are admitted in
+    /// `--budget-order` until the budget is spent, and the rest are marked
+    /// omitted in the tree.
+    #[arg(
+        long = "budget",
+        value_name = "TOKENS",
+        help = "Cap the output at this many tokens; files past the budget are marked omitted in the tree"
+    )]
+    pub budget: Option<u64>,
+
+    /// Sets the order files are admitted in under `--budget` \[default:
+    /// walk\].
+    #[arg(
+        long = "budget-order",
+        value_name = "ORDER",
+        value_parser = ["walk", "smallest", "recent"],
+        help = "Order files are admitted in under --budget: walk, smallest, or recent [default: walk]"
+    )]
+    pub budget_order: Option<String>,
+
+    /// Splits the output into numbered chunks (`fyai.001.txt`, ...) of at
+    /// most this size: a number of bytes, or of tokens with a `tokens`
+    /// suffix (e.g. `100000tokens`).
+    #[arg(
+        long = "chunk-size",
+        value_name = "SIZE",
+        help = "Split the output into numbered chunks of at most SIZE bytes, or tokens with a `tokens` suffix (e.g. 500000, 100000tokens)"
+    )]
+    pub chunk_size: Option<String>,
+
     /// Copies the combined output to the system clipboard \[default: false\].
     #[arg(
         short = 'c',
@@ -198,7 +473,7 @@ pub struct Cli {
     #[arg(short = 't', long = "test", action = ArgAction::SetTrue, help = "Run in test mode")]
     pub test: bool,
 
-    /// Optional subcommand (currently only `init`).
+    /// Optional subcommand (`init` or `cache`).
     #[command(subcommand)]
     pub command: Option<Command>,
 }
@@ -221,6 +496,30 @@ pub enum Command {
         #[arg(long = "force", action = ArgAction::SetTrue, help = "Overwrite existing config file if present")]
         force: bool,
     },
+
+    /// Lists or clears the repository clones `--cache` keeps.
+    Cache {
+        /// What to do with the cache.
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+/// Actions of the `cache` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Lists the cached clones, most recently used first.
+    List,
+
+    /// Removes every cached clone, or just one repository's.
+    Clear {
+        /// Removes only the clone of this repository URL.
+        #[arg(
+            value_name = "URL",
+            help = "Only remove the clone of this repository URL"
+        )]
+        url: Option<String>,
+    },
 }
 
 /// Converts parsed `clap` matches into a [`PartialConfig`], leaving a field
@@ -233,7 +532,17 @@ pub enum Command {
 /// Comma-separated list options (`include_dirs`, `exclude_ext`, ...) are
 /// split, trimmed, lower-cased, and emptied entries dropped.
 pub fn config_from_matches(matches: clap::ArgMatches) -> Result<PartialConfig> {
-    let directory = explicit_string(&matches, "input");
+    // One `-i` sets `directory`, several set `directories`.
+    let mut inputs = match matches.value_source("input") {
+        Some(ValueSource::CommandLine) => explicit_strings(&matches, "input").unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let (directory, directories) = match inputs.len() {
+        0 => (explicit_string(&matches, "archive"), None),
+        1 => (inputs.pop(), None),
+        _ => (None, Some(inputs)),
+    };
+    let files_from = explicit_string(&matches, "files_from");
     let output = explicit_string(&matches, "output");
 
     let include_dirs = match matches.try_get_one::<String>("include_dirs") {
@@ -314,6 +623,24 @@ pub fn config_from_matches(matches: clap::ArgMatches) -> Result<PartialConfig> {
         },
     };
 
+    let changed_since = explicit_string(&matches, "changed_since");
+    let staged = explicit_flag(&matches, "staged");
+    let unstaged = explicit_flag(&matches, "unstaged");
+    let full_tree = explicit_flag(&matches, "full_tree");
+    let diff = explicit_string(&matches, "diff");
+    let git_log = match matches.try_get_one::<u64>("git_log") {
+        Ok(value) => value.copied(),
+        Err(_) => None,
+    };
+    let rev = explicit_string(&matches, "rev");
+    let repo_path = explicit_string(&matches, "repo_path");
+    let submodules = explicit_flag(&matches, "submodules");
+    let cache = explicit_flag(&matches, "cache");
+    let cache_max_size = match matches.try_get_one::<u64>("cache_max_size") {
+        Ok(value) => value.copied(),
+        Err(_) => None,
+    };
+
     // The `--no-*` flags are negated: the `Config` field is the opposite of
     // whatever was passed.
     let hidden = explicit_flag(&matches, "no_hidden").map(|no_hidden| !no_hidden);
@@ -323,27 +650,93 @@ pub fn config_from_matches(matches: clap::ArgMatches) -> Result<PartialConfig> {
     let git_global = explicit_flag(&matches, "no_git_global").map(|no_git_global| !no_git_global);
     let follow_links = explicit_flag(&matches, "follow_links");
     let tree_only = explicit_flag(&matches, "tree_only");
+    let strip_comments = explicit_flag(&matches, "strip_comments");
+    let compact = explicit_flag(&matches, "compact");
+    let compact_tabs = matches.get_one::<u64>("compact_tabs").copied();
+    let line_numbers = explicit_flag(&matches, "line_numbers");
+    let redact = explicit_flag(&matches, "no_redact").map(|no_redact| !no_redact);
     let human = explicit_flag(&matches, "human");
 
-    Ok(PartialConfig {
-        directory,
-        output,
-        include_dirs,
-        exclude_dirs,
-        include_ext,
-        exclude_ext,
-        include_files,
-        exclude_files,
-        min_size,
-        max_size,
-        hidden,
-        gitignore,
-        ignore_files,
-        git_global,
-        follow_links,
-        tree_only,
-        human,
-    })
+    let include_globs = explicit_strings(&matches, "include_globs");
+    let exclude_globs = explicit_strings(&matches, "exclude_globs");
+    let grep = explicit_strings(&matches, "grep");
+    let grep_invert = explicit_flag(&matches, "grep_invert");
+    let grep_lines = explicit_flag(&matches, "grep_lines");
+
+    let output_format = match matches.try_get_one::<String>("format") {
+        Ok(Some(format)) => Some(format.parse::<OutputFormat>()?),
+        Ok(None) | Err(_) => None,
+    };
+
+    let tokenizer = match matches.try_get_one::<String>("tokenizer") {
+        Ok(Some(tokenizer)) => Some(tokenizer.parse::<Tokenizer>()?),
+        Ok(None) | Err(_) => None,
+    };
+    let heading_tokens = explicit_flag(&matches, "heading_tokens");
+
+    let max_tokens = match matches.try_get_one::<u64>("budget") {
+        Ok(value) => value.copied(),
+        Err(_) => None,
+    };
+    let budget_order = match matches.try_get_one::<String>("budget_order") {
+        Ok(Some(order)) => Some(order.parse::<BudgetOrder>()?),
+        Ok(None) | Err(_) => None,
+    };
+    let chunk_size = match matches.try_get_one::<String>("chunk_size") {
+        Ok(Some(size)) => Some(size.parse::<ChunkSize>()?),
+        Ok(None) | Err(_) => None,
+    };
+
+    // Anything not set here stays `None`, like `redact_rules`, which only
+    // the config file can add.
+    let mut config = PartialConfig::default();
+    config.directory = directory;
+    config.directories = directories;
+    config.files_from = files_from;
+    config.output = output;
+    config.include_dirs = include_dirs;
+    config.exclude_dirs = exclude_dirs;
+    config.include_ext = include_ext;
+    config.exclude_ext = exclude_ext;
+    config.include_files = include_files;
+    config.exclude_files = exclude_files;
+    config.include_globs = include_globs;
+    config.exclude_globs = exclude_globs;
+    config.grep = grep;
+    config.grep_invert = grep_invert;
+    config.grep_lines = grep_lines;
+    config.min_size = min_size;
+    config.max_size = max_size;
+    config.changed_since = changed_since;
+    config.staged = staged;
+    config.unstaged = unstaged;
+    config.full_tree = full_tree;
+    config.diff = diff;
+    config.git_log = git_log;
+    config.rev = rev;
+    config.repo_path = repo_path;
+    config.submodules = submodules;
+    config.cache = cache;
+    config.cache_max_size = cache_max_size;
+    config.hidden = hidden;
+    config.gitignore = gitignore;
+    config.ignore_files = ignore_files;
+    config.git_global = git_global;
+    config.follow_links = follow_links;
+    config.tree_only = tree_only;
+    config.strip_comments = strip_comments;
+    config.compact = compact;
+    config.compact_tabs = compact_tabs;
+    config.line_numbers = line_numbers;
+    config.redact = redact;
+    config.human = human;
+    config.output_format = output_format;
+    config.tokenizer = tokenizer;
+    config.heading_tokens = heading_tokens;
+    config.max_tokens = max_tokens;
+    config.budget_order = budget_order;
+    config.chunk_size = chunk_size;
+    Ok(config)
 }
 
 /// Returns `matches`' string value for `id`, but only if it was passed
@@ -355,6 +748,16 @@ fn explicit_string(matches: &clap::ArgMatches, id: &str) -> Option<String> {
     matches.get_one::<String>(id).cloned()
 }
 
+/// Returns every value of the repeatable option `id`, or `None` if it
+/// wasn't passed. Globs are kept verbatim: commas may be part of a
+/// `{a,b}` alternation, so they aren't split like the list options.
+fn explicit_strings(matches: &clap::ArgMatches, id: &str) -> Option<Vec<String>> {
+    match matches.try_get_many::<String>(id) {
+        Ok(values) => values.map(|values| values.cloned().collect()),
+        Err(_) => None,
+    }
+}
+
 /// Returns `matches`' `SetTrue` flag value for `id`, but only if it was
 /// passed explicitly on the command line.
 fn explicit_flag(matches: &clap::ArgMatches, id: &str) -> Option<bool> {
@@ -400,6 +803,18 @@ mod tests {
         let matches = parse_ok(&["fyai"]);
         let config = config_from_matches(matches).unwrap();
         assert_eq!(config.directory, None);
+        assert_eq!(config.directories, None);
+    }
+
+    #[test]
+    fn repeated_input_sets_directories() {
+        let matches = parse_ok(&["fyai", "-i", "backend", "--input", "../protos"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.directory, None);
+        assert_eq!(
+            config.directories,
+            Some(vec!["backend".to_string(), "../protos".to_string()])
+        );
     }
 
     #[test]
@@ -527,6 +942,68 @@ mod tests {
         assert_eq!(config.exclude_files, None);
     }
 
+    #[test]
+    fn include_globs_are_repeatable_and_kept_verbatim() {
+        let matches = parse_ok(&[
+            "fyai",
+            "--include",
+            "src/**/*.{rs,toml}",
+            "--include",
+            "Cargo.toml",
+        ]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(
+            config.include_globs,
+            Some(vec![
+                "src/**/*.{rs,toml}".to_string(),
+                "Cargo.toml".to_string()
+            ])
+        );
+
+        let matches = parse_ok(&["fyai"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.include_globs, None);
+    }
+
+    #[test]
+    fn exclude_globs_parse_and_default_to_none() {
+        let matches = parse_ok(&["fyai", "--exclude", "**/generated/**"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(
+            config.exclude_globs,
+            Some(vec!["**/generated/**".to_string()])
+        );
+
+        let matches = parse_ok(&["fyai"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.exclude_globs, None);
+    }
+
+    #[test]
+    fn grep_is_repeatable_and_its_flags_are_explicit_only() {
+        let matches = parse_ok(&[
+            "fyai",
+            "--grep",
+            "PaymentIntent",
+            "--grep",
+            "refund\\(",
+            "--grep-invert",
+            "--grep-lines",
+        ]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(
+            config.grep,
+            Some(vec!["PaymentIntent".to_string(), "refund\\(".to_string()])
+        );
+        assert_eq!(config.grep_invert, Some(true));
+        assert_eq!(config.grep_lines, Some(true));
+
+        let config = config_from_matches(parse_ok(&["fyai"])).unwrap();
+        assert_eq!(config.grep, None);
+        assert_eq!(config.grep_invert, None);
+        assert_eq!(config.grep_lines, None);
+    }
+
     // ---- min-size / max-size ------------------------------------------
 
     #[test]
@@ -668,6 +1145,38 @@ mod tests {
         assert_eq!(config.tree_only, None);
     }
 
+    #[test]
+    fn strip_comments_flag_is_some_true() {
+        let matches = parse_ok(&["fyai", "--strip-comments"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.strip_comments, Some(true));
+    }
+
+    #[test]
+    fn compact_tabs_sets_the_tab_width_and_needs_compact() {
+        let matches = parse_ok(&["fyai", "--compact", "--compact-tabs", "4"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.compact, Some(true));
+        assert_eq!(config.compact_tabs, Some(4));
+
+        assert!(parse(&["fyai", "--compact-tabs", "4"]).is_err());
+        assert!(parse(&["fyai", "--compact", "--compact-tabs", "0"]).is_err());
+    }
+
+    #[test]
+    fn line_numbers_flag_is_some_true() {
+        let matches = parse_ok(&["fyai", "--line-numbers"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.line_numbers, Some(true));
+    }
+
+    #[test]
+    fn no_redact_flag_negates_to_some_false() {
+        let matches = parse_ok(&["fyai", "--no-redact"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.redact, Some(false));
+    }
+
     #[test]
     fn human_flag_is_some_true() {
         let matches = parse_ok(&["fyai", "--human"]);
@@ -682,6 +1191,116 @@ mod tests {
         assert_eq!(config.human, None);
     }
 
+    // ---- --format -------------------------------------------------------
+
+    #[test]
+    fn format_xml_is_some_xml() {
+        let matches = parse_ok(&["fyai", "--format", "xml"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.output_format, Some(OutputFormat::Xml));
+    }
+
+    #[test]
+    fn format_json_and_jsonl_are_accepted() {
+        let matches = parse_ok(&["fyai", "--format", "json"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.output_format, Some(OutputFormat::Json));
+
+        let matches = parse_ok(&["fyai", "--format", "jsonl"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.output_format, Some(OutputFormat::Jsonl));
+    }
+
+    #[test]
+    fn format_not_passed_is_none() {
+        let matches = parse_ok(&["fyai"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.output_format, None);
+    }
+
+    #[test]
+    fn format_unknown_value_is_rejected_by_clap() {
+        let result = parse(&["fyai", "--format", "yaml"]);
+        assert!(result.is_err());
+    }
+
+    // ---- --tokenizer / --heading-tokens ---------------------------------
+
+    #[test]
+    fn tokenizer_chars_is_some_chars() {
+        let matches = parse_ok(&["fyai", "--tokenizer", "chars"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.tokenizer, Some(Tokenizer::Chars));
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn tokenizer_bpe_names_are_accepted() {
+        let matches = parse_ok(&["fyai", "--tokenizer", "o200k"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.tokenizer, Some(Tokenizer::O200k));
+    }
+
+    #[test]
+    fn tokenizer_unknown_value_is_rejected_by_clap() {
+        let result = parse(&["fyai", "--tokenizer", "gpt2"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn heading_tokens_flag_is_some_true() {
+        let matches = parse_ok(&["fyai", "--heading-tokens"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.heading_tokens, Some(true));
+    }
+
+    // ---- --budget / --budget-order --------------------------------------
+
+    #[test]
+    fn budget_sets_max_tokens() {
+        let matches = parse_ok(&["fyai", "--budget", "100000"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.max_tokens, Some(100_000));
+    }
+
+    #[test]
+    fn budget_rejects_non_numeric_values() {
+        let result = parse(&["fyai", "--budget", "lots"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn budget_order_smallest_is_some_smallest() {
+        let matches = parse_ok(&["fyai", "--budget-order", "smallest"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.budget_order, Some(BudgetOrder::Smallest));
+    }
+
+    #[test]
+    fn budget_order_unknown_value_is_rejected_by_clap() {
+        let result = parse(&["fyai", "--budget-order", "largest"]);
+        assert!(result.is_err());
+    }
+
+    // ---- --chunk-size ---------------------------------------------------
+
+    #[test]
+    fn chunk_size_accepts_bytes_and_tokens() {
+        let matches = parse_ok(&["fyai", "--chunk-size", "500000"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.chunk_size, Some(ChunkSize::Bytes(500_000)));
+
+        let matches = parse_ok(&["fyai", "--chunk-size", "100000tokens"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.chunk_size, Some(ChunkSize::Tokens(100_000)));
+    }
+
+    #[test]
+    fn chunk_size_invalid_value_is_an_error() {
+        let matches = parse_ok(&["fyai", "--chunk-size", "lots"]);
+        assert!(config_from_matches(matches).is_err());
+    }
+
     // ---- --repo conflicts / requires ------------------------------------
 
     #[test]
@@ -696,6 +1315,38 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn archive_conflicts_with_input_and_repo() {
+        assert!(parse(&["fyai", "--archive", "src.zip", "--input", "y"]).is_err());
+        assert!(
+            parse(&[
+                "fyai",
+                "--archive",
+                "src.zip",
+                "--repo",
+                "https://example.com/x.git"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn archive_sets_the_directory() {
+        let matches = parse_ok(&["fyai", "--archive", "src.tar.gz"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.directory, Some("src.tar.gz".to_string()));
+    }
+
+    #[test]
+    fn files_from_reaches_the_partial_config() {
+        let matches = parse_ok(&["fyai", "--files-from", "-"]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.files_from, Some("-".to_string()));
+
+        let config = config_from_matches(parse_ok(&["fyai"])).unwrap();
+        assert_eq!(config.files_from, None);
+    }
+
     #[test]
     fn repo_alone_parses_fine() {
         let matches = parse_ok(&["fyai", "--repo", "https://example.com/x.git"]);
@@ -741,6 +1392,86 @@ mod tests {
         assert_eq!(cli.repo_commit, Some("deadbeef".to_string()));
     }
 
+    #[test]
+    fn repo_path_requires_repo() {
+        let result = parse(&["fyai", "--repo-path", "packages/api"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn repo_path_with_repo_reaches_the_partial_config() {
+        let matches = parse_ok(&[
+            "fyai",
+            "--repo",
+            "https://example.com/x.git",
+            "--repo-path",
+            "packages/api",
+        ]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.repo_path, Some("packages/api".to_string()));
+    }
+
+    #[test]
+    fn cache_flags_require_repo() {
+        assert!(parse(&["fyai", "--cache"]).is_err());
+        assert!(parse(&["fyai", "--cache-max-size", "1000"]).is_err());
+    }
+
+    #[test]
+    fn cache_flags_with_repo_reach_the_partial_config() {
+        let matches = parse_ok(&[
+            "fyai",
+            "--repo",
+            "https://example.com/x.git",
+            "--cache",
+            "--cache-max-size",
+            "1000",
+        ]);
+        let config = config_from_matches(matches).unwrap();
+        assert_eq!(config.cache, Some(true));
+        assert_eq!(config.cache_max_size, Some(1000));
+    }
+
+    // ---- cache subcommand ------------------------------------------------
+
+    #[test]
+    fn cache_subcommand_parses_list_and_clear() {
+        let cli = Cli::from_arg_matches(&parse_ok(&["fyai", "cache", "list"])).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Cache {
+                action: CacheAction::List
+            })
+        ));
+
+        let cli = Cli::from_arg_matches(&parse_ok(&["fyai", "cache", "clear"])).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Cache {
+                action: CacheAction::Clear { url: None }
+            })
+        ));
+
+        let cli = Cli::from_arg_matches(&parse_ok(&[
+            "fyai",
+            "cache",
+            "clear",
+            "https://example.com/x.git",
+        ]))
+        .unwrap();
+        match cli.command {
+            Some(Command::Cache {
+                action: CacheAction::Clear { url },
+            }) => assert_eq!(url, Some("https://example.com/x.git".to_string())),
+            other => panic!("expected Some(Command::Cache {{ .. }}), got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn cache_subcommand_requires_an_action() {
+        assert!(parse(&["fyai", "cache"]).is_err());
+    }
+
     // ---- init subcommand -------------------------------------------------
 
     #[test]
@@ -854,17 +1585,50 @@ mod tests {
             "main.rs",
             "--exclude-files",
             "LICENSE",
+            "--include",
+            "src/**",
+            "--exclude",
+            "*.lock",
             "--min-size",
             "10",
             "--max-size",
             "20",
+            "--changed-since",
+            "main",
+            "--staged",
+            "--unstaged",
+            "--full-tree",
+            "--diff",
+            "main..HEAD",
+            "--git-log",
+            "5",
+            "--rev",
+            "v2.3",
+            "--submodules",
             "--no-hidden",
             "--no-gitignore",
             "--no-ignore-files",
             "--no-git-global",
             "--follow-links",
             "--tree-only",
+            "--strip-comments",
+            "--compact",
+            "--compact-tabs",
+            "2",
+            "--line-numbers",
+            "--no-redact",
             "--human",
+            "--format",
+            "xml",
+            "--tokenizer",
+            "chars",
+            "--heading-tokens",
+            "--budget",
+            "5000",
+            "--budget-order",
+            "recent",
+            "--chunk-size",
+            "1000t",
             "-t",
         ]);
         let config = config_from_matches(matches).unwrap();
@@ -886,15 +1650,36 @@ mod tests {
         assert_eq!(config.exclude_ext, Some(vec![".lock".to_string()]));
         assert_eq!(config.include_files, Some(vec!["main.rs".to_string()]));
         assert_eq!(config.exclude_files, Some(vec!["license".to_string()]));
+        assert_eq!(config.include_globs, Some(vec!["src/**".to_string()]));
+        assert_eq!(config.exclude_globs, Some(vec!["*.lock".to_string()]));
         assert_eq!(config.min_size, Some(10));
         assert_eq!(config.max_size, Some(20));
+        assert_eq!(config.changed_since, Some("main".to_string()));
+        assert_eq!(config.staged, Some(true));
+        assert_eq!(config.unstaged, Some(true));
+        assert_eq!(config.full_tree, Some(true));
+        assert_eq!(config.diff, Some("main..HEAD".to_string()));
+        assert_eq!(config.git_log, Some(5));
+        assert_eq!(config.rev, Some("v2.3".to_string()));
+        assert_eq!(config.submodules, Some(true));
         assert_eq!(config.hidden, Some(false));
         assert_eq!(config.gitignore, Some(false));
         assert_eq!(config.ignore_files, Some(false));
         assert_eq!(config.git_global, Some(false));
         assert_eq!(config.follow_links, Some(true));
         assert_eq!(config.tree_only, Some(true));
+        assert_eq!(config.strip_comments, Some(true));
+        assert_eq!(config.compact, Some(true));
+        assert_eq!(config.compact_tabs, Some(2));
+        assert_eq!(config.line_numbers, Some(true));
+        assert_eq!(config.redact, Some(false));
         assert_eq!(config.human, Some(true));
+        assert_eq!(config.output_format, Some(OutputFormat::Xml));
+        assert_eq!(config.tokenizer, Some(Tokenizer::Chars));
+        assert_eq!(config.heading_tokens, Some(true));
+        assert_eq!(config.max_tokens, Some(5000));
+        assert_eq!(config.budget_order, Some(BudgetOrder::Recent));
+        assert_eq!(config.chunk_size, Some(ChunkSize::Tokens(1000)));
     }
 
     #[test]
@@ -910,14 +1695,39 @@ mod tests {
         assert_eq!(config.exclude_ext, None);
         assert_eq!(config.include_files, None);
         assert_eq!(config.exclude_files, None);
+        assert_eq!(config.include_globs, None);
+        assert_eq!(config.exclude_globs, None);
         assert_eq!(config.min_size, None);
         assert_eq!(config.max_size, None);
+        assert_eq!(config.changed_since, None);
+        assert_eq!(config.staged, None);
+        assert_eq!(config.unstaged, None);
+        assert_eq!(config.full_tree, None);
+        assert_eq!(config.diff, None);
+        assert_eq!(config.git_log, None);
+        assert_eq!(config.rev, None);
+        assert_eq!(config.repo_path, None);
+        assert_eq!(config.submodules, None);
+        assert_eq!(config.cache, None);
+        assert_eq!(config.cache_max_size, None);
         assert_eq!(config.hidden, None);
         assert_eq!(config.gitignore, None);
         assert_eq!(config.ignore_files, None);
         assert_eq!(config.git_global, None);
         assert_eq!(config.follow_links, None);
         assert_eq!(config.tree_only, None);
+        assert_eq!(config.strip_comments, None);
+        assert_eq!(config.compact, None);
+        assert_eq!(config.compact_tabs, None);
+        assert_eq!(config.line_numbers, None);
+        assert_eq!(config.redact, None);
+        assert_eq!(config.redact_rules, None);
         assert_eq!(config.human, None);
+        assert_eq!(config.output_format, None);
+        assert_eq!(config.tokenizer, None);
+        assert_eq!(config.heading_tokens, None);
+        assert_eq!(config.max_tokens, None);
+        assert_eq!(config.budget_order, None);
+        assert_eq!(config.chunk_size, None);
     }
 }
@@ -0,0 +1,722 @@
+//! An opt-in cache of `--repo` clones (see [`Config::cache`]), so repeated
+//! runs against the same repository fetch into an existing clone instead of
+//! cloning from scratch.
+//!
+//! Each repository gets its own entry directory under the cache root, named
+//! after a hash of its URL, holding the URL itself (whose modification time
+//! records when the entry was last used) and the clone. Cached clones keep
+//! their full history and a full checkout, whatever `repo_path` a run
+//! scans, and always go through the `git` command line, with or without
+//! the `native-git` feature.
+//!
+//! Next to each entry directory, a `.lock` file of the same name serializes
+//! runs of the same repository: a run holds an exclusive lock on it from
+//! checkout until it's done scanning, and eviction and removal skip entries
+//! another run holds. Removing an entry removes its lock file too.
+//!
+//! [`Config::cache`]: crate::config::Config::cache
+
+use std::cmp::Reverse;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use same_file::Handle;
+
+use crate::error::{FyaiError, Result};
+use crate::git::{command_error_details, git_output};
+
+/// How large the cache may grow, in bytes, before [`CloneCache::evict`]
+/// removes the least recently used clones, unless
+/// [`Config::cache_max_size`](crate::config::Config::cache_max_size) says
+/// otherwise: 2 GiB.
+pub const DEFAULT_MAX_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// File in each entry directory holding the repository URL.
+const URL_FILE: &str = "url";
+/// Directory in each entry directory holding the clone.
+const REPO_DIR: &str = "repo";
+
+/// A directory of cached clones, one per repository URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloneCache {
+    root: PathBuf,
+}
+
+/// One cached clone, as listed by [`CloneCache::entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The repository URL the clone was made from.
+    pub url: String,
+    /// The entry directory, holding the clone.
+    pub path: PathBuf,
+    /// Total size of the entry on disk, in bytes.
+    pub size: u64,
+    /// When a run last used the clone.
+    pub last_used: SystemTime,
+}
+
+impl CloneCache {
+    /// Returns a cache rooted at `root`, which is created on first use.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the cache `--cache` runs use: `feedyourai/repos` under the
+    /// system cache directory (honoring `$XDG_CACHE_HOME` on Linux), or
+    /// `None` if the platform has none.
+    pub fn system() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("feedyourai").join("repos")))
+    }
+
+    /// Returns the directory the cache lives in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns every cached clone, most recently used first. A missing cache
+    /// root is an empty cache.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let read_dir = match fs::read_dir(&self.root) {
+            Ok(read_dir) => read_dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entries = Vec::new();
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+            let url_file = path.join(URL_FILE);
+            // An entry whose clone was interrupted before its URL was
+            // recorded isn't listed; the next run of that URL replaces it.
+            let Ok(url) = fs::read_to_string(&url_file) else {
+                continue;
+            };
+            entries.push(CacheEntry {
+                url,
+                size: dir_size(&path)?,
+                last_used: fs::metadata(&url_file)?.modified()?,
+                path,
+            });
+        }
+        entries.sort_by_key(|entry| Reverse(entry.last_used));
+        Ok(entries)
+    }
+
+    /// Removes the clone of `url`, returning whether there was one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FyaiError::Io`] with [`io::ErrorKind::ResourceBusy`] if
+    /// another run has the clone checked out.
+    pub fn remove(&self, url: &str) -> Result<bool> {
+        let path = self.entry_path(url);
+        if !path.exists() {
+            return Ok(false);
+        }
+        let Some(_lock) = lock_entry(&path, false)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!("the cached clone of {url} is in use by another run"),
+            )
+            .into());
+        };
+        remove_entry(&path)?;
+        Ok(true)
+    }
+
+    /// Removes every cached clone no other run has checked out, returning
+    /// how many it removed, along with any lock files left without an entry.
+    pub fn clear(&self) -> Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            let Some(_lock) = lock_entry(&entry.path, false)? else {
+                continue;
+            };
+            remove_entry(&entry.path)?;
+            removed += 1;
+        }
+
+        let read_dir = match fs::read_dir(&self.root) {
+            Ok(read_dir) => read_dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(removed),
+            Err(err) => return Err(err.into()),
+        };
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+            if path.extension().is_none_or(|ext| ext != "lock") {
+                continue;
+            }
+            let entry = path.with_extension("");
+            if entry.exists() {
+                continue;
+            }
+            if let Some(_lock) = lock_entry(&entry, false)? {
+                remove_entry(&entry)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes the least recently used clones, other than `keep`'s and any
+    /// another run has checked out, until the cache takes at most
+    /// `max_size` bytes (or only those are left), returning the removed
+    /// entries.
+    pub fn evict(&self, max_size: u64, keep: &str) -> Result<Vec<CacheEntry>> {
+        let keep = self.entry_path(keep);
+        let entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+
+        let mut removed = Vec::new();
+        for entry in entries.into_iter().rev() {
+            if total <= max_size {
+                break;
+            }
+            if entry.path == keep {
+                continue;
+            }
+            let Some(_lock) = lock_entry(&entry.path, false)? else {
+                continue;
+            };
+            remove_entry(&entry.path)?;
+            total -= entry.size;
+            removed.push(entry);
+        }
+        Ok(removed)
+    }
+
+    /// Brings the clone of `url` up to date, cloning it first if it isn't
+    /// cached yet, and checks out `commit`, else `branch` (a branch or tag),
+    /// else the remote's default branch, as a detached `HEAD`. Anything a
+    /// previous run left in the working tree is cleaned away.
+    ///
+    /// With `submodules`, the submodules (under `subdir`, if set) are
+    /// checked out recursively at the commits the checkout pins; otherwise
+    /// any a previous run checked out are removed again.
+    ///
+    /// Waits for any other run holding the entry first, then returns its
+    /// lock, which keeps other runs out until it's dropped, and the path to
+    /// the checked-out repository.
+    pub(crate) fn check_out(
+        &self,
+        url: &str,
+        branch: Option<&str>,
+        commit: Option<&str>,
+        subdir: Option<&Path>,
+        submodules: bool,
+    ) -> Result<(File, PathBuf)> {
+        let url = &normalize_url(url);
+        let entry = self.entry_path(url);
+        let repo = entry.join(REPO_DIR);
+
+        fs::create_dir_all(&self.root)?;
+        let lock = lock_entry(&entry, true)?.expect("a blocking lock is always taken");
+
+        if repo.join(".git").exists() {
+            git_output(
+                &repo,
+                ["fetch", "--prune", "--tags", "--force", "origin"],
+                "git fetch",
+            )?;
+            if branch.is_none() && commit.is_none() {
+                git_output(
+                    &repo,
+                    ["remote", "set-head", "origin", "--auto"],
+                    "git remote",
+                )?;
+            }
+        } else {
+            if entry.exists() {
+                fs::remove_dir_all(&entry)?;
+            }
+            fs::create_dir_all(&entry)?;
+            if let Err(err) = clone(url, &repo) {
+                let _ = remove_entry(&entry);
+                return Err(err);
+            }
+        }
+
+        let target = match (commit, branch) {
+            (Some(commit), _) => commit.to_string(),
+            (None, Some(branch)) => {
+                let remote_branch = format!("refs/remotes/origin/{branch}");
+                let is_branch = git_output(
+                    &repo,
+                    ["rev-parse", "--verify", "--quiet", &remote_branch],
+                    "git rev-parse",
+                )
+                .is_ok();
+                if is_branch {
+                    remote_branch
+                } else {
+                    branch.to_string()
+                }
+            }
+            (None, None) => "refs/remotes/origin/HEAD".to_string(),
+        };
+        git_output(
+            &repo,
+            ["checkout", "--force", "--detach", &target],
+            "git checkout",
+        )?;
+        git_output(&repo, ["clean", "-ffdx"], "git clean")?;
+        delete_local_branches(&repo)?;
+
+        git_output(
+            &repo,
+            ["submodule", "deinit", "--all", "--force"],
+            "git submodule deinit",
+        )?;
+        if submodules {
+            let mut args = vec!["submodule", "update", "--init", "--recursive", "--force"];
+            let subdir = subdir.map(|subdir| subdir.to_string_lossy());
+            if let Some(subdir) = &subdir {
+                args.extend(["--", subdir]);
+            }
+            git_output(&repo, args, "git submodule update")?;
+        }
+
+        fs::write(entry.join(URL_FILE), url)?;
+        Ok((lock, repo))
+    }
+
+    /// Returns the entry directory for `url`.
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let url = normalize_url(url);
+        self.root.join(format!("{:016x}", fnv1a(url.as_bytes())))
+    }
+}
+
+/// Returns `url`, or its absolute form if it's a local directory, so the
+/// same relative path run from elsewhere doesn't share its entry.
+fn normalize_url(url: &str) -> String {
+    match fs::canonicalize(url) {
+        Ok(path) if path.is_dir() => path.to_string_lossy().into_owned(),
+        _ => url.to_string(),
+    }
+}
+
+/// Takes the lock of the entry directory `entry`, creating its lock file if
+/// needed. With `wait`, waits for any other run holding it; otherwise
+/// returns `None` if one does.
+fn lock_entry(entry: &Path, wait: bool) -> Result<Option<File>> {
+    let path = entry.with_extension("lock");
+    loop {
+        let lock = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        if wait {
+            lock.lock()?;
+        } else {
+            match lock.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => return Ok(None),
+                Err(TryLockError::Error(err)) => return Err(err.into()),
+            }
+        }
+        // The run we waited on may have removed the entry, lock file and
+        // all; a lock on the removed file keeps no one out, so take the
+        // lock on whatever file is there now instead.
+        let locked = Handle::from_file(lock.try_clone()?)?;
+        if Handle::from_path(&path).is_ok_and(|current| current == locked) {
+            return Ok(Some(lock));
+        }
+    }
+}
+
+/// Removes the entry directory `entry`, if there is one, and then its lock
+/// file, which the caller must hold.
+fn remove_entry(entry: &Path) -> Result<()> {
+    if entry.exists() {
+        fs::remove_dir_all(entry)?;
+    }
+    fs::remove_file(entry.with_extension("lock"))?;
+    Ok(())
+}
+
+/// Clones `url` into `repo` without checking anything out.
+fn clone(url: &str, repo: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .args(["clone", "--no-checkout", url])
+        .arg(repo)
+        .output()
+        .map_err(|e| FyaiError::Git(format!("failed to run git clone: {e}")))?;
+    if !output.status.success() {
+        return Err(FyaiError::Git(format!(
+            "git clone failed: {}",
+            command_error_details(&output)
+        )));
+    }
+    Ok(())
+}
+
+/// Deletes the local branches of `repo`, which only ever has a detached
+/// `HEAD` checked out: a branch left over from the first clone would go
+/// stale as later fetches move `origin/<branch>` on, and `changed_since`,
+/// `diff`, and `rev` would resolve the branch name to it.
+fn delete_local_branches(repo: &Path) -> Result<()> {
+    let refs = git_output(
+        repo,
+        ["for-each-ref", "--format=%(refname)", "refs/heads/"],
+        "git for-each-ref",
+    )?;
+    for name in String::from_utf8_lossy(&refs).lines() {
+        git_output(repo, ["update-ref", "-d", name], "git update-ref")?;
+    }
+    Ok(())
+}
+
+/// Returns the total size of the files under `path`, not following
+/// symbolic links.
+fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for dir_entry in fs::read_dir(path)? {
+        let dir_entry = dir_entry?;
+        let metadata = dir_entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&dir_entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// The 64-bit FNV-1a hash of `bytes`: stable across releases and platforms,
+/// unlike [`std::hash::DefaultHasher`], so entry names survive upgrades.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.email=test@example.com", "-c", "user.name=Test"])
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .expect("failed to run git");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit_file(dir: &Path, name: &str, content: &str) -> String {
+        fs::write(dir.join(name), content).unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", name]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    /// A repository with `main` (tagged `v1` at its first commit) and a
+    /// `feature` branch with one more file.
+    fn source_repo() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q", "-b", "main"]);
+        commit_file(dir.path(), "version.txt", "one");
+        git(dir.path(), &["tag", "v1"]);
+        git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+        commit_file(dir.path(), "feature.txt", "feature");
+        git(dir.path(), &["checkout", "-q", "main"]);
+        dir
+    }
+
+    fn url(source: &TempDir) -> String {
+        source.path().to_string_lossy().into_owned()
+    }
+
+    /// Writes a fake cache entry for `url` with `size` bytes of content,
+    /// last used `age` ago.
+    fn fake_entry(cache: &CloneCache, url: &str, size: usize, age: Duration) {
+        let path = cache.entry_path(url);
+        fs::create_dir_all(path.join(REPO_DIR)).unwrap();
+        fs::write(path.join(REPO_DIR).join("blob"), vec![0u8; size]).unwrap();
+        let url_file = path.join(URL_FILE);
+        fs::write(&url_file, url).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(url_file)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[test]
+    fn check_out_clones_once_then_fetches_new_commits() {
+        let source = source_repo();
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+
+        let (_, repo) = cache
+            .check_out(&url(&source), None, None, None, false)
+            .unwrap();
+        assert_eq!(fs::read_to_string(repo.join("version.txt")).unwrap(), "one");
+
+        commit_file(source.path(), "version.txt", "two");
+        let (_, again) = cache
+            .check_out(&url(&source), None, None, None, false)
+            .unwrap();
+        assert_eq!(again, repo);
+        assert_eq!(fs::read_to_string(repo.join("version.txt")).unwrap(), "two");
+
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].url,
+            fs::canonicalize(source.path()).unwrap().to_string_lossy()
+        );
+        assert!(entries[0].size > 0);
+    }
+
+    #[test]
+    fn check_out_switches_between_branches_tags_and_commits() {
+        let source = source_repo();
+        let first = git(source.path(), &["rev-parse", "v1"]);
+        commit_file(source.path(), "version.txt", "two");
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+
+        let (_, repo) = cache
+            .check_out(&url(&source), Some("feature"), None, None, false)
+            .unwrap();
+        assert!(repo.join("feature.txt").exists());
+        fs::write(repo.join("leftover.txt"), "from an earlier run").unwrap();
+
+        cache
+            .check_out(&url(&source), Some("v1"), None, None, false)
+            .unwrap();
+        assert!(!repo.join("feature.txt").exists());
+        assert!(!repo.join("leftover.txt").exists());
+        assert_eq!(fs::read_to_string(repo.join("version.txt")).unwrap(), "one");
+
+        cache
+            .check_out(&url(&source), Some("main"), None, None, false)
+            .unwrap();
+        assert_eq!(fs::read_to_string(repo.join("version.txt")).unwrap(), "two");
+
+        cache
+            .check_out(&url(&source), None, Some(&first), None, false)
+            .unwrap();
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), first);
+    }
+
+    #[test]
+    fn check_out_leaves_no_local_branch_to_go_stale() {
+        let source = source_repo();
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+
+        let (_, repo) = cache
+            .check_out(&url(&source), None, None, None, false)
+            .unwrap();
+        assert_eq!(
+            git(
+                &repo,
+                &["for-each-ref", "--format=%(refname)", "refs/heads/"]
+            ),
+            ""
+        );
+        assert_eq!(
+            git(&repo, &["rev-parse", "origin/main"]),
+            git(source.path(), &["rev-parse", "main"])
+        );
+    }
+
+    #[test]
+    fn a_failed_clone_leaves_no_entry_behind() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+
+        let missing = root.path().join("missing-repo");
+        let err = cache
+            .check_out(&missing.to_string_lossy(), None, None, None, false)
+            .unwrap_err();
+        assert!(err.to_string().contains("git clone failed"), "{err}");
+        let entry = cache.entry_path(&missing.to_string_lossy());
+        assert!(!entry.exists());
+        assert!(!entry.with_extension("lock").exists());
+        assert!(cache.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_checkout_holds_its_entry_until_dropped() {
+        let source = source_repo();
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+
+        let (lock, _) = cache
+            .check_out(&url(&source), None, None, None, false)
+            .unwrap();
+        let entry = cache.entry_path(&url(&source));
+        assert!(lock_entry(&entry, false).unwrap().is_none());
+
+        let removed = cache.evict(0, "https://example.com/other.git").unwrap();
+        assert!(removed.is_empty());
+        assert!(entry.exists());
+
+        drop(lock);
+        assert!(lock_entry(&entry, false).unwrap().is_some());
+        let removed = cache.evict(0, "https://example.com/other.git").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!entry.exists());
+        assert!(!entry.with_extension("lock").exists());
+    }
+
+    #[test]
+    fn remove_and_clear_leave_checked_out_entries_alone() {
+        let source = source_repo();
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+        fake_entry(&cache, "https://example.com/idle.git", 10, Duration::ZERO);
+
+        let (lock, _) = cache
+            .check_out(&url(&source), None, None, None, false)
+            .unwrap();
+        let entry = cache.entry_path(&url(&source));
+
+        let err = cache.remove(&url(&source)).unwrap_err();
+        assert!(
+            matches!(&err, FyaiError::Io(err) if err.kind() == io::ErrorKind::ResourceBusy),
+            "{err}"
+        );
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(entry.exists());
+        assert_eq!(cache.entries().unwrap().len(), 1);
+
+        drop(lock);
+        assert!(cache.remove(&url(&source)).unwrap());
+        assert!(!entry.exists());
+        assert!(!entry.with_extension("lock").exists());
+    }
+
+    #[test]
+    fn a_run_waiting_on_a_removed_entry_locks_the_new_lock_file() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+        fake_entry(&cache, "https://example.com/a.git", 10, Duration::ZERO);
+        let entry = cache.entry_path("https://example.com/a.git");
+
+        let held = lock_entry(&entry, false).unwrap().unwrap();
+        let waiter = {
+            let entry = entry.clone();
+            std::thread::spawn(move || lock_entry(&entry, true).unwrap().unwrap())
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        remove_entry(&entry).unwrap();
+        drop(held);
+
+        let lock = waiter.join().unwrap();
+        assert_eq!(
+            Handle::from_file(lock).unwrap(),
+            Handle::from_path(entry.with_extension("lock")).unwrap()
+        );
+    }
+
+    #[test]
+    fn entries_are_listed_most_recently_used_first() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+        fake_entry(
+            &cache,
+            "https://example.com/old.git",
+            10,
+            Duration::from_secs(7200),
+        );
+        fake_entry(
+            &cache,
+            "https://example.com/new.git",
+            10,
+            Duration::from_secs(60),
+        );
+
+        let urls: Vec<String> = cache
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.url)
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://example.com/new.git", "https://example.com/old.git"]
+        );
+    }
+
+    #[test]
+    fn a_missing_root_is_an_empty_cache() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path().join("missing"));
+        assert!(cache.entries().unwrap().is_empty());
+        assert_eq!(cache.clear().unwrap(), 0);
+    }
+
+    #[test]
+    fn remove_and_clear_delete_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+        fake_entry(&cache, "https://example.com/a.git", 10, Duration::ZERO);
+        fake_entry(&cache, "https://example.com/b.git", 10, Duration::ZERO);
+        fake_entry(&cache, "https://example.com/c.git", 10, Duration::ZERO);
+
+        assert!(cache.remove("https://example.com/a.git").unwrap());
+        assert!(!cache.remove("https://example.com/a.git").unwrap());
+        assert_eq!(cache.entries().unwrap().len(), 2);
+
+        fs::write(root.path().join("0123456789abcdef.lock"), "").unwrap();
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert!(cache.entries().unwrap().is_empty());
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn evict_removes_least_recently_used_entries_but_keeps_the_current_one() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CloneCache::new(root.path());
+        let hour = Duration::from_secs(3600);
+        fake_entry(&cache, "https://example.com/current.git", 1000, hour * 3);
+        fake_entry(&cache, "https://example.com/older.git", 1000, hour * 2);
+        fake_entry(&cache, "https://example.com/newer.git", 1000, hour);
+
+        let removed = cache
+            .evict(2500, "https://example.com/current.git")
+            .unwrap();
+        let removed: Vec<&str> = removed.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(removed, ["https://example.com/older.git"]);
+
+        let removed = cache.evict(0, "https://example.com/current.git").unwrap();
+        assert_eq!(removed.len(), 1);
+        let left = cache.entries().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].url, "https://example.com/current.git");
+    }
+
+    #[test]
+    fn entry_names_are_stable_hashes_of_the_url() {
+        let cache = CloneCache::new("/cache");
+        assert_eq!(
+            cache.entry_path("https://example.com/x.git"),
+            cache.entry_path("https://example.com/x.git")
+        );
+        assert_ne!(
+            cache.entry_path("https://example.com/x.git"),
+            cache.entry_path("https://example.com/y.git")
+        );
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
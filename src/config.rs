@@ -10,17 +10,37 @@
 //! winning over file, file winning over the built-in default.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use crate::error::{FyaiError, Result};
+use crate::tokens::Tokenizer;
 
 /// Fully-resolved configuration for a single combine run.
+///
+/// Marked `#[non_exhaustive]` so new options aren't breaking changes: outside
+/// this crate, build one with [`Config::builder`] (or [`merge_config`]), then
+/// adjust fields directly if needed.
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub struct Config {
-    /// Directory to scan.
+    /// Directory to scan, or a `.zip` or `.tar(.gz/.xz/.zst)` archive whose
+    /// members are scanned in place (see
+    /// [`collect_entries`](crate::collect_entries)).
     pub directory: PathBuf,
-    /// File the combined output is written to.
+    /// Further roots scanned after `directory` in the same run. Each gets a
+    /// tree of its own, and their files share one content section, under
+    /// headings prefixed with each root's label. Empty for a single root.
+    pub extra_directories: Vec<PathBuf>,
+    /// If set, the walk is skipped and exactly the files listed in this
+    /// file (or on stdin, for `-`) are read, one path per line or
+    /// NUL-separated, relative to `directory`. The name, extension, glob,
+    /// and ignore rules don't apply to them; size bounds and binary
+    /// detection still do.
+    pub files_from: Option<PathBuf>,
+    /// File the combined output is written to, or `-` for stdout.
     pub output: PathBuf,
     /// If set, only directories whose name matches one of these are walked.
     pub include_dirs: Option<Vec<String>>,
@@ -34,10 +54,72 @@ pub struct Config {
     pub include_files: Option<Vec<String>>,
     /// File names to skip.
     pub exclude_files: Option<Vec<String>>,
+    /// If set, only files whose path relative to `directory` matches one of
+    /// these globs (`src/**/*.rs`) are included. Composes with the lists
+    /// above: a file must pass both.
+    pub include_globs: Option<Vec<String>>,
+    /// Globs for paths relative to `directory` to skip (`**/generated/**`).
+    /// A directory that matches is skipped along with everything under it.
+    pub exclude_globs: Option<Vec<String>>,
+    /// If set, only files whose contents match at least one of these
+    /// regexes are written, and the tree only shows them. Checked while the
+    /// files are read, so [`collect_entries`](crate::collect_entries)
+    /// doesn't apply it.
+    pub grep: Option<Vec<String>>,
+    /// With `grep`, keeps the files that match none of its regexes instead.
+    pub grep_invert: bool,
+    /// With `grep` (and not `grep_invert`), each file's heading lists the
+    /// numbers of the lines a regex matched on.
+    pub grep_lines: bool,
     /// Files smaller than this many bytes are skipped.
     pub min_size: Option<u64>,
     /// Files larger than this many bytes are skipped.
     pub max_size: Option<u64>,
+    /// If set, only files git reports as changed since the merge base of
+    /// this ref and `HEAD` (committed or not) are included.
+    pub changed_since: Option<String>,
+    /// If true, only files with staged changes are included. Combines with
+    /// `changed_since` and `unstaged` as a union.
+    pub staged: bool,
+    /// If true, only files with unstaged changes, or untracked files git
+    /// doesn't ignore, are included.
+    pub unstaged: bool,
+    /// With `changed_since`/`staged`/`unstaged`, whether the tree still
+    /// shows every file the filters allow, marking the changed ones
+    /// `[changed]`, rather than just the changed files.
+    pub full_tree: bool,
+    /// If set, a `- Git Diff` section with the unified diff for this range
+    /// (`<base>` against the working tree, `<base>..<head>`, or
+    /// `<base>...<head>` from their merge base), limited to
+    /// paths the include/exclude filters allow, is added after the files.
+    pub diff: Option<String>,
+    /// If set, a `- Recent Commits` section lists this many of the latest
+    /// commits touching `directory`, with their changed paths.
+    pub git_log: Option<u64>,
+    /// If set, `directory` is scanned as it was at this git revision (a
+    /// branch, tag, or commit), checked out into a temporary worktree, so
+    /// ignore files and filters apply as they would have at that commit.
+    /// Only [`crate::run_local`]/[`crate::run_git`] and their `_to_writer`
+    /// variants honor it.
+    pub rev: Option<String>,
+    /// If set, [`crate::run_git`] scans only this subdirectory of the clone
+    /// (relative to the repository root), sparse-checking-out just that
+    /// subtree. Ignored for local runs.
+    pub repo_path: Option<PathBuf>,
+    /// If true, [`crate::run_git`] checks out submodules (recursively, and
+    /// shallowly when the clone is), and the tree marks every submodule
+    /// root `[submodule]`. Local scans walk submodules as they're checked
+    /// out either way, and outside a git work tree have nothing to mark.
+    pub submodules: bool,
+    /// If true, [`crate::run_git`] keeps its clone in the
+    /// [system clone cache](crate::cache::CloneCache::system) and, on later
+    /// runs of the same URL, fetches into it instead of cloning again.
+    pub cache: bool,
+    /// After a cached run, the least recently used clones are evicted
+    /// until the cache takes at most this many bytes (default
+    /// [`DEFAULT_MAX_SIZE`](crate::cache::DEFAULT_MAX_SIZE)). The clone
+    /// just used is always kept.
+    pub cache_max_size: Option<u64>,
     /// Whether to skip hidden files and directories (dot-files). Independent
     /// of `gitignore`/`ignore_files`/`git_global`.
     pub hidden: bool,
@@ -55,19 +137,620 @@ pub struct Config {
     pub follow_links: bool,
     /// If true, only the directory tree is written; file contents are skipped.
     pub tree_only: bool,
+    /// If true, line and block comments are removed from each file's
+    /// contents, by the language its extension maps to. Files in languages
+    /// without a known comment syntax are written unchanged.
+    pub strip_comments: bool,
+    /// If true, runs of blank lines in each file's contents are collapsed
+    /// into one and trailing whitespace is trimmed, except in languages
+    /// whose whitespace is significant (Python, YAML, Makefiles, Haskell,
+    /// Sass, Markdown).
+    pub compact: bool,
+    /// With `compact`, each run of this many spaces at the start of a line
+    /// becomes a tab. `0` leaves indentation alone, same as `None`.
+    pub compact_tabs: Option<u64>,
+    /// If true, each line of a file's contents is numbered with the line it
+    /// came from in the file, so numbers can skip lines removed by
+    /// `strip_comments`, `compact` or `grep`.
+    pub line_numbers: bool,
+    /// If true, credentials in file contents and the `diff` text (private
+    /// keys, cloud and chat tokens, high-entropy values assigned to
+    /// credential names like `API_KEY` or `PASSWORD`) are replaced with
+    /// `[REDACTED:<kind>]`.
+    pub redact: bool,
+    /// With `redact`, extra rules: each name is the kind its regex's matches
+    /// are reported as. A regex with capture groups only replaces the first
+    /// group that matched. A regex that can match the empty string is
+    /// rejected.
+    pub redact_rules: BTreeMap<String, String>,
     /// If true, renders the directory tree with `tree`-style connector
     /// glyphs (`├──`, `└──`, `│`) instead of the minimal two-space indent.
     pub human: bool,
+    /// Layout of the combined output (Markdown headings and fences, an XML
+    /// document, or JSON/JSON Lines for tooling).
+    pub output_format: OutputFormat,
+    /// How tokens are counted for the run's [`ScanStats`](crate::scanner::ScanStats)
+    /// and, with `heading_tokens`, each file's heading.
+    pub tokenizer: Tokenizer,
+    /// If true, each file's heading shows its token count next to its size
+    /// (`### src/main.rs (1.2 KB, 310 tokens)`).
+    pub heading_tokens: bool,
+    /// If set, files are admitted in `budget_order` until their contents
+    /// (plus the tree) would exceed this many tokens; every later file is
+    /// left out and marked `[omitted]` in the tree.
+    pub max_tokens: Option<u64>,
+    /// Priority order files are admitted in under `max_tokens`.
+    pub budget_order: BudgetOrder,
+    /// If set, the output is split into numbered chunks (`fyai.001.txt`,
+    /// `fyai.002.txt`, ...) of at most this size each, instead of one
+    /// `output` file.
+    pub chunk_size: Option<ChunkSize>,
+}
+
+impl Config {
+    /// Returns a [`ConfigBuilder`] starting from the defaults, the same ones
+    /// [`merge_config`] falls back to.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Returns whether `output` is `-`, meaning the combined output goes to
+    /// stdout rather than a file.
+    pub fn output_is_stdout(&self) -> bool {
+        self.output.as_os_str() == "-"
+    }
+
+    /// Returns every root the run scans: `directory`, then
+    /// `extra_directories`.
+    pub fn directories(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.directory.as_path())
+            .chain(self.extra_directories.iter().map(PathBuf::as_path))
+    }
+}
+
+/// The built-in defaults: scan `.` into `fyai.txt`, skipping hidden and
+/// ignored files, with no other filters, budget, or chunking.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("."),
+            extra_directories: Vec::new(),
+            files_from: None,
+            output: PathBuf::from("fyai.txt"),
+            include_dirs: None,
+            exclude_dirs: None,
+            include_ext: None,
+            exclude_ext: None,
+            include_files: None,
+            exclude_files: None,
+            include_globs: None,
+            exclude_globs: None,
+            grep: None,
+            grep_invert: false,
+            grep_lines: false,
+            min_size: None,
+            max_size: None,
+            changed_since: None,
+            staged: false,
+            unstaged: false,
+            full_tree: false,
+            diff: None,
+            git_log: None,
+            rev: None,
+            repo_path: None,
+            submodules: false,
+            cache: false,
+            cache_max_size: None,
+            hidden: true,
+            gitignore: true,
+            ignore_files: true,
+            git_global: true,
+            follow_links: false,
+            tree_only: false,
+            strip_comments: false,
+            compact: false,
+            compact_tabs: None,
+            line_numbers: false,
+            redact: true,
+            redact_rules: BTreeMap::new(),
+            human: false,
+            output_format: OutputFormat::default(),
+            tokenizer: Tokenizer::default(),
+            heading_tokens: false,
+            max_tokens: None,
+            budget_order: BudgetOrder::default(),
+            chunk_size: None,
+        }
+    }
+}
+
+/// Builds a [`Config`] from the defaults up, one chainable setter per
+/// option, so callers only name what they change.
+///
+/// ```
+/// use feedyourai::config::Config;
+///
+/// let config = Config::builder()
+///     .directory("src")
+///     .include_ext(["rs", "toml"])
+///     .hidden(false)
+///     .build();
+/// assert_eq!(config.include_ext, Some(vec!["rs".to_string(), "toml".to_string()]));
+/// assert!(config.gitignore);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Sets [`Config::directory`].
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.config.directory = directory.into();
+        self
+    }
+
+    /// Sets [`Config::directory`] to the first of `directories` and
+    /// [`Config::extra_directories`] to the rest. Leaves both alone when
+    /// `directories` is empty.
+    pub fn directories<P: Into<PathBuf>>(
+        mut self,
+        directories: impl IntoIterator<Item = P>,
+    ) -> Self {
+        let mut directories = directories.into_iter().map(Into::into);
+        if let Some(directory) = directories.next() {
+            self.config.directory = directory;
+            self.config.extra_directories = directories.collect();
+        }
+        self
+    }
+
+    /// Sets [`Config::files_from`].
+    pub fn files_from(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.files_from = Some(path.into());
+        self
+    }
+
+    /// Sets [`Config::output`].
+    pub fn output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.config.output = output.into();
+        self
+    }
+
+    /// Sets [`Config::include_dirs`].
+    pub fn include_dirs<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.include_dirs = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::exclude_dirs`].
+    pub fn exclude_dirs<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.exclude_dirs = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::include_ext`].
+    pub fn include_ext<S: Into<String>>(mut self, exts: impl IntoIterator<Item = S>) -> Self {
+        self.config.include_ext = Some(exts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::exclude_ext`].
+    pub fn exclude_ext<S: Into<String>>(mut self, exts: impl IntoIterator<Item = S>) -> Self {
+        self.config.exclude_ext = Some(exts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::include_files`].
+    pub fn include_files<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.include_files = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::exclude_files`].
+    pub fn exclude_files<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.exclude_files = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::include_globs`].
+    pub fn include_globs<S: Into<String>>(mut self, globs: impl IntoIterator<Item = S>) -> Self {
+        self.config.include_globs = Some(globs.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::exclude_globs`].
+    pub fn exclude_globs<S: Into<String>>(mut self, globs: impl IntoIterator<Item = S>) -> Self {
+        self.config.exclude_globs = Some(globs.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::grep`].
+    pub fn grep<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.config.grep = Some(patterns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets [`Config::grep_invert`].
+    pub fn grep_invert(mut self, grep_invert: bool) -> Self {
+        self.config.grep_invert = grep_invert;
+        self
+    }
+
+    /// Sets [`Config::grep_lines`].
+    pub fn grep_lines(mut self, grep_lines: bool) -> Self {
+        self.config.grep_lines = grep_lines;
+        self
+    }
+
+    /// Sets [`Config::min_size`].
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.config.min_size = Some(bytes);
+        self
+    }
+
+    /// Sets [`Config::max_size`].
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.config.max_size = Some(bytes);
+        self
+    }
+
+    /// Sets [`Config::changed_since`].
+    pub fn changed_since(mut self, git_ref: impl Into<String>) -> Self {
+        self.config.changed_since = Some(git_ref.into());
+        self
+    }
+
+    /// Sets [`Config::staged`].
+    pub fn staged(mut self, staged: bool) -> Self {
+        self.config.staged = staged;
+        self
+    }
+
+    /// Sets [`Config::unstaged`].
+    pub fn unstaged(mut self, unstaged: bool) -> Self {
+        self.config.unstaged = unstaged;
+        self
+    }
+
+    /// Sets [`Config::full_tree`].
+    pub fn full_tree(mut self, full_tree: bool) -> Self {
+        self.config.full_tree = full_tree;
+        self
+    }
+
+    /// Sets [`Config::diff`].
+    pub fn diff(mut self, range: impl Into<String>) -> Self {
+        self.config.diff = Some(range.into());
+        self
+    }
+
+    /// Sets [`Config::git_log`].
+    pub fn git_log(mut self, commits: u64) -> Self {
+        self.config.git_log = Some(commits);
+        self
+    }
+
+    /// Sets [`Config::rev`].
+    pub fn rev(mut self, rev: impl Into<String>) -> Self {
+        self.config.rev = Some(rev.into());
+        self
+    }
+
+    /// Sets [`Config::repo_path`].
+    pub fn repo_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.repo_path = Some(path.into());
+        self
+    }
+
+    /// Sets [`Config::submodules`].
+    pub fn submodules(mut self, submodules: bool) -> Self {
+        self.config.submodules = submodules;
+        self
+    }
+
+    /// Sets [`Config::cache`].
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.config.cache = cache;
+        self
+    }
+
+    /// Sets [`Config::cache_max_size`].
+    pub fn cache_max_size(mut self, bytes: u64) -> Self {
+        self.config.cache_max_size = Some(bytes);
+        self
+    }
+
+    /// Sets [`Config::hidden`].
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.config.hidden = hidden;
+        self
+    }
+
+    /// Sets [`Config::gitignore`].
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.config.gitignore = gitignore;
+        self
+    }
+
+    /// Sets [`Config::ignore_files`].
+    pub fn ignore_files(mut self, ignore_files: bool) -> Self {
+        self.config.ignore_files = ignore_files;
+        self
+    }
+
+    /// Sets [`Config::git_global`].
+    pub fn git_global(mut self, git_global: bool) -> Self {
+        self.config.git_global = git_global;
+        self
+    }
+
+    /// Sets [`Config::follow_links`].
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.config.follow_links = follow_links;
+        self
+    }
+
+    /// Sets [`Config::tree_only`].
+    pub fn tree_only(mut self, tree_only: bool) -> Self {
+        self.config.tree_only = tree_only;
+        self
+    }
+
+    /// Sets [`Config::strip_comments`].
+    pub fn strip_comments(mut self, strip_comments: bool) -> Self {
+        self.config.strip_comments = strip_comments;
+        self
+    }
+
+    /// Sets [`Config::compact`].
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.config.compact = compact;
+        self
+    }
+
+    /// Sets [`Config::compact_tabs`].
+    pub fn compact_tabs(mut self, tab_width: u64) -> Self {
+        self.config.compact_tabs = Some(tab_width);
+        self
+    }
+
+    /// Sets [`Config::line_numbers`].
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.config.line_numbers = line_numbers;
+        self
+    }
+
+    /// Sets [`Config::redact`].
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.config.redact = redact;
+        self
+    }
+
+    /// Sets [`Config::redact_rules`].
+    pub fn redact_rules<K: Into<String>, V: Into<String>>(
+        mut self,
+        rules: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.config.redact_rules = rules
+            .into_iter()
+            .map(|(kind, pattern)| (kind.into(), pattern.into()))
+            .collect();
+        self
+    }
+
+    /// Sets [`Config::human`].
+    pub fn human(mut self, human: bool) -> Self {
+        self.config.human = human;
+        self
+    }
+
+    /// Sets [`Config::output_format`].
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.config.output_format = output_format;
+        self
+    }
+
+    /// Sets [`Config::tokenizer`].
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.config.tokenizer = tokenizer;
+        self
+    }
+
+    /// Sets [`Config::heading_tokens`].
+    pub fn heading_tokens(mut self, heading_tokens: bool) -> Self {
+        self.config.heading_tokens = heading_tokens;
+        self
+    }
+
+    /// Sets [`Config::max_tokens`].
+    pub fn max_tokens(mut self, max_tokens: u64) -> Self {
+        self.config.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Sets [`Config::budget_order`].
+    pub fn budget_order(mut self, budget_order: BudgetOrder) -> Self {
+        self.config.budget_order = budget_order;
+        self
+    }
+
+    /// Sets [`Config::chunk_size`].
+    pub fn chunk_size(mut self, chunk_size: ChunkSize) -> Self {
+        self.config.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Returns the built [`Config`].
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
+
+/// Layout of the combined output file.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A `- Tree Structure` section, then one `### path (size)` heading and
+    /// language-tagged, fenced code block per file.
+    #[default]
+    Markdown,
+    /// A single `<documents>` element holding a `<tree>`, one
+    /// `<document index="N">` (with `<source>` and `<document_content>`)
+    /// per file, and a closing `<stats>` element, with all text escaped.
+    Xml,
+    /// A single JSON object: `tree`, a `files` array (each with `path`,
+    /// `size`, `language`, `content`), and `stats`.
+    Json,
+    /// JSON Lines: a `tree` record, one `file` record per file, then a
+    /// `stats` record, each tagged with a `"type"` field.
+    Jsonl,
+}
+
+/// Priority order files are admitted in when `max_tokens` is set.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetOrder {
+    /// Walk order (the order files appear in the tree).
+    #[default]
+    Walk,
+    /// Fewest tokens first, fitting as many files as possible.
+    Smallest,
+    /// Most recently modified first. Archive members keep walk order.
+    Recent,
+}
+
+impl FromStr for BudgetOrder {
+    type Err = FyaiError;
+
+    /// Parses the lower-case name used by the `budget_order` config key and
+    /// the `--budget-order` flag.
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "walk" => Ok(Self::Walk),
+            "smallest" => Ok(Self::Smallest),
+            "recent" => Ok(Self::Recent),
+            _ => Err(FyaiError::InvalidValue {
+                key: "budget_order",
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// Maximum size of one output chunk, in bytes or in tokens (as counted by
+/// `config.tokenizer`).
+///
+/// Parsed from a plain number of bytes (`500000`), or a number with a
+/// `bytes`/`b` or `tokens`/`t` suffix (`100000tokens`, `100000t`). In
+/// `fyai.toml`, a bare integer is also accepted, as bytes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "ChunkSizeValue", into = "String")]
+pub enum ChunkSize {
+    /// At most this many bytes of tree and file contents per chunk.
+    Bytes(u64),
+    /// At most this many tokens of tree and file contents per chunk.
+    Tokens(u64),
+}
+
+/// What a `chunk_size` config value may be written as.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChunkSizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<ChunkSizeValue> for ChunkSize {
+    type Error = FyaiError;
+
+    fn try_from(value: ChunkSizeValue) -> Result<Self> {
+        match value {
+            ChunkSizeValue::Bytes(0) => Err(FyaiError::InvalidValue {
+                key: "chunk_size",
+                value: "0".to_string(),
+            }),
+            ChunkSizeValue::Bytes(bytes) => Ok(Self::Bytes(bytes)),
+            ChunkSizeValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<ChunkSize> for String {
+    fn from(size: ChunkSize) -> Self {
+        match size {
+            ChunkSize::Bytes(bytes) => format!("{bytes}bytes"),
+            ChunkSize::Tokens(tokens) => format!("{tokens}tokens"),
+        }
+    }
+}
+
+impl FromStr for ChunkSize {
+    type Err = FyaiError;
+
+    /// Parses `<N>`, `<N>bytes`/`<N>b`, or `<N>tokens`/`<N>t`, where `N` is
+    /// a positive integer.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || FyaiError::InvalidValue {
+            key: "chunk_size",
+            value: value.to_string(),
+        };
+
+        let trimmed = value.trim();
+        let digits_end = trimmed
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let (number, unit) = trimmed.split_at(digits_end);
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        if number == 0 {
+            return Err(invalid());
+        }
+
+        match unit.trim() {
+            "" | "b" | "bytes" => Ok(Self::Bytes(number)),
+            "t" | "tokens" => Ok(Self::Tokens(number)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = FyaiError;
+
+    /// Parses the lower-case name used by the `output_format` config key and
+    /// the `--format` flag.
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "markdown" => Ok(Self::Markdown),
+            "xml" => Ok(Self::Xml),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => Err(FyaiError::InvalidValue {
+                key: "output_format",
+                value: value.to_string(),
+            }),
+        }
+    }
 }
 
 /// Partially-specified configuration, either loaded from a `fyai.toml` file
 /// or built from CLI flags. Every field is optional; unset fields fall back
 /// to the other source, then to a built-in default, when merged via
 /// [`merge_config`].
+///
+/// Marked `#[non_exhaustive]` like [`Config`]: outside this crate, start
+/// from [`PartialConfig::default`] and set the fields you need.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[non_exhaustive]
 pub struct PartialConfig {
     /// See [`Config::directory`].
     pub directory: Option<String>,
+    /// Every root to scan, in order, in place of `directory`: the first
+    /// becomes [`Config::directory`] and the rest
+    /// [`Config::extra_directories`].
+    pub directories: Option<Vec<String>>,
+    /// See [`Config::files_from`].
+    pub files_from: Option<String>,
     /// See [`Config::output`].
     pub output: Option<String>,
     /// See [`Config::include_dirs`].
@@ -82,10 +765,42 @@ pub struct PartialConfig {
     pub include_files: Option<Vec<String>>,
     /// See [`Config::exclude_files`].
     pub exclude_files: Option<Vec<String>>,
+    /// See [`Config::include_globs`].
+    pub include_globs: Option<Vec<String>>,
+    /// See [`Config::exclude_globs`].
+    pub exclude_globs: Option<Vec<String>>,
+    /// See [`Config::grep`].
+    pub grep: Option<Vec<String>>,
+    /// See [`Config::grep_invert`].
+    pub grep_invert: Option<bool>,
+    /// See [`Config::grep_lines`].
+    pub grep_lines: Option<bool>,
     /// See [`Config::min_size`].
     pub min_size: Option<u64>,
     /// See [`Config::max_size`].
     pub max_size: Option<u64>,
+    /// See [`Config::changed_since`].
+    pub changed_since: Option<String>,
+    /// See [`Config::staged`].
+    pub staged: Option<bool>,
+    /// See [`Config::unstaged`].
+    pub unstaged: Option<bool>,
+    /// See [`Config::full_tree`].
+    pub full_tree: Option<bool>,
+    /// See [`Config::diff`].
+    pub diff: Option<String>,
+    /// See [`Config::git_log`].
+    pub git_log: Option<u64>,
+    /// See [`Config::rev`].
+    pub rev: Option<String>,
+    /// See [`Config::repo_path`].
+    pub repo_path: Option<String>,
+    /// See [`Config::submodules`].
+    pub submodules: Option<bool>,
+    /// See [`Config::cache`].
+    pub cache: Option<bool>,
+    /// See [`Config::cache_max_size`].
+    pub cache_max_size: Option<u64>,
     /// See [`Config::hidden`].
     pub hidden: Option<bool>,
     /// See [`Config::gitignore`].
@@ -98,8 +813,32 @@ pub struct PartialConfig {
     pub follow_links: Option<bool>,
     /// See [`Config::tree_only`].
     pub tree_only: Option<bool>,
+    /// See [`Config::strip_comments`].
+    pub strip_comments: Option<bool>,
+    /// See [`Config::compact`].
+    pub compact: Option<bool>,
+    /// See [`Config::compact_tabs`].
+    pub compact_tabs: Option<u64>,
+    /// See [`Config::line_numbers`].
+    pub line_numbers: Option<bool>,
+    /// See [`Config::redact`].
+    pub redact: Option<bool>,
+    /// See [`Config::redact_rules`].
+    pub redact_rules: Option<BTreeMap<String, String>>,
     /// See [`Config::human`].
     pub human: Option<bool>,
+    /// See [`Config::output_format`].
+    pub output_format: Option<OutputFormat>,
+    /// See [`Config::tokenizer`].
+    pub tokenizer: Option<Tokenizer>,
+    /// See [`Config::heading_tokens`].
+    pub heading_tokens: Option<bool>,
+    /// See [`Config::max_tokens`].
+    pub max_tokens: Option<u64>,
+    /// See [`Config::budget_order`].
+    pub budget_order: Option<BudgetOrder>,
+    /// See [`Config::chunk_size`].
+    pub chunk_size: Option<ChunkSize>,
 }
 
 impl PartialConfig {
@@ -161,28 +900,101 @@ pub fn system_config_dir() -> Option<PathBuf> {
 /// Merges two [`PartialConfig`]s into a final [`Config`]: `cli`'s value wins
 /// wherever set, otherwise `file`'s, otherwise the built-in default.
 pub fn merge_config(file: PartialConfig, cli: PartialConfig) -> Config {
-    let directory = cli
-        .directory
-        .or(file.directory)
+    let defaults = Config::default();
+
+    // `directories` lists every root, so it replaces `directory` rather
+    // than adding to it, and the CLI's roots replace the file's as a whole.
+    let (directory, directories) = if cli.directory.is_some() || cli.directories.is_some() {
+        (cli.directory, cli.directories)
+    } else {
+        (file.directory, file.directories)
+    };
+    let mut directories = directories.unwrap_or_default().into_iter();
+    let directory = directories
+        .next()
+        .or(directory)
         .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
+        .unwrap_or(defaults.directory);
+    let extra_directories = directories.map(PathBuf::from).collect();
 
     let output = cli
         .output
         .or(file.output)
         .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("fyai.txt"));
-
-    let hidden = cli.hidden.or(file.hidden).unwrap_or(true);
-    let gitignore = cli.gitignore.or(file.gitignore).unwrap_or(true);
-    let ignore_files = cli.ignore_files.or(file.ignore_files).unwrap_or(true);
-    let git_global = cli.git_global.or(file.git_global).unwrap_or(true);
-    let follow_links = cli.follow_links.or(file.follow_links).unwrap_or(false);
-    let tree_only = cli.tree_only.or(file.tree_only).unwrap_or(false);
-    let human = cli.human.or(file.human).unwrap_or(false);
+        .unwrap_or(defaults.output);
+
+    let staged = cli.staged.or(file.staged).unwrap_or(defaults.staged);
+    let unstaged = cli.unstaged.or(file.unstaged).unwrap_or(defaults.unstaged);
+    let grep_invert = cli
+        .grep_invert
+        .or(file.grep_invert)
+        .unwrap_or(defaults.grep_invert);
+    let grep_lines = cli
+        .grep_lines
+        .or(file.grep_lines)
+        .unwrap_or(defaults.grep_lines);
+    let full_tree = cli
+        .full_tree
+        .or(file.full_tree)
+        .unwrap_or(defaults.full_tree);
+    let submodules = cli
+        .submodules
+        .or(file.submodules)
+        .unwrap_or(defaults.submodules);
+    let cache = cli.cache.or(file.cache).unwrap_or(defaults.cache);
+    let hidden = cli.hidden.or(file.hidden).unwrap_or(defaults.hidden);
+    let gitignore = cli
+        .gitignore
+        .or(file.gitignore)
+        .unwrap_or(defaults.gitignore);
+    let ignore_files = cli
+        .ignore_files
+        .or(file.ignore_files)
+        .unwrap_or(defaults.ignore_files);
+    let git_global = cli
+        .git_global
+        .or(file.git_global)
+        .unwrap_or(defaults.git_global);
+    let follow_links = cli
+        .follow_links
+        .or(file.follow_links)
+        .unwrap_or(defaults.follow_links);
+    let tree_only = cli
+        .tree_only
+        .or(file.tree_only)
+        .unwrap_or(defaults.tree_only);
+    let strip_comments = cli
+        .strip_comments
+        .or(file.strip_comments)
+        .unwrap_or(defaults.strip_comments);
+    let compact = cli.compact.or(file.compact).unwrap_or(defaults.compact);
+    let line_numbers = cli
+        .line_numbers
+        .or(file.line_numbers)
+        .unwrap_or(defaults.line_numbers);
+    let redact = cli.redact.or(file.redact).unwrap_or(defaults.redact);
+    let human = cli.human.or(file.human).unwrap_or(defaults.human);
+    let output_format = cli
+        .output_format
+        .or(file.output_format)
+        .unwrap_or(defaults.output_format);
+    let tokenizer = cli
+        .tokenizer
+        .or(file.tokenizer)
+        .unwrap_or(defaults.tokenizer);
+    let heading_tokens = cli
+        .heading_tokens
+        .or(file.heading_tokens)
+        .unwrap_or(defaults.heading_tokens);
+    let budget_order = cli
+        .budget_order
+        .or(file.budget_order)
+        .unwrap_or(defaults.budget_order);
 
     Config {
         directory,
+        extra_directories,
+        files_from: cli.files_from.or(file.files_from).map(PathBuf::from),
         output,
         include_dirs: cli.include_dirs.or(file.include_dirs),
         exclude_dirs: cli.exclude_dirs.or(file.exclude_dirs),
@@ -190,15 +1002,43 @@ pub fn merge_config(file: PartialConfig, cli: PartialConfig) -> Config {
         exclude_ext: cli.exclude_ext.or(file.exclude_ext),
         include_files: cli.include_files.or(file.include_files),
         exclude_files: cli.exclude_files.or(file.exclude_files),
+        include_globs: cli.include_globs.or(file.include_globs),
+        exclude_globs: cli.exclude_globs.or(file.exclude_globs),
+        grep: cli.grep.or(file.grep),
+        grep_invert,
+        grep_lines,
         min_size: cli.min_size.or(file.min_size),
         max_size: cli.max_size.or(file.max_size),
+        changed_since: cli.changed_since.or(file.changed_since),
+        staged,
+        unstaged,
+        full_tree,
+        diff: cli.diff.or(file.diff),
+        git_log: cli.git_log.or(file.git_log),
+        rev: cli.rev.or(file.rev),
+        repo_path: cli.repo_path.or(file.repo_path).map(PathBuf::from),
+        submodules,
+        cache,
+        cache_max_size: cli.cache_max_size.or(file.cache_max_size),
         hidden,
         gitignore,
         ignore_files,
         git_global,
         follow_links,
         tree_only,
+        strip_comments,
+        compact,
+        compact_tabs: cli.compact_tabs.or(file.compact_tabs),
+        line_numbers,
+        redact,
+        redact_rules: cli.redact_rules.or(file.redact_rules).unwrap_or_default(),
         human,
+        output_format,
+        tokenizer,
+        heading_tokens,
+        max_tokens: cli.max_tokens.or(file.max_tokens),
+        budget_order,
+        chunk_size: cli.chunk_size.or(file.chunk_size),
     }
 }
 
@@ -414,6 +1254,8 @@ mod tests {
     fn merge_config_all_defaults_when_nothing_set() {
         let config = merge_config(empty_partial(), empty_partial());
         assert_eq!(config.directory, PathBuf::from("."));
+        assert!(config.extra_directories.is_empty());
+        assert_eq!(config.files_from, None);
         assert_eq!(config.output, PathBuf::from("fyai.txt"));
         assert_eq!(config.include_dirs, None);
         assert_eq!(config.exclude_dirs, None);
@@ -421,15 +1263,328 @@ mod tests {
         assert_eq!(config.exclude_ext, None);
         assert_eq!(config.include_files, None);
         assert_eq!(config.exclude_files, None);
+        assert_eq!(config.include_globs, None);
+        assert_eq!(config.exclude_globs, None);
+        assert_eq!(config.grep, None);
+        assert!(!config.grep_invert);
+        assert!(!config.grep_lines);
         assert_eq!(config.min_size, None);
         assert_eq!(config.max_size, None);
+        assert_eq!(config.changed_since, None);
+        assert!(!config.staged);
+        assert!(!config.unstaged);
+        assert!(!config.full_tree);
+        assert_eq!(config.diff, None);
+        assert_eq!(config.git_log, None);
+        assert_eq!(config.rev, None);
+        assert_eq!(config.repo_path, None);
+        assert!(!config.submodules);
+        assert!(!config.cache);
+        assert_eq!(config.cache_max_size, None);
         assert!(config.hidden);
         assert!(config.gitignore);
         assert!(config.ignore_files);
         assert!(config.git_global);
         assert!(!config.follow_links);
         assert!(!config.tree_only);
+        assert!(!config.strip_comments);
+        assert!(!config.compact);
+        assert_eq!(config.compact_tabs, None);
+        assert!(!config.line_numbers);
+        assert!(config.redact);
+        assert!(config.redact_rules.is_empty());
         assert!(!config.human);
+        assert_eq!(config.output_format, OutputFormat::Markdown);
+        assert_eq!(config.tokenizer, Tokenizer::Chars);
+        assert!(!config.heading_tokens);
+        assert_eq!(config.max_tokens, None);
+        assert_eq!(config.budget_order, BudgetOrder::Walk);
+        assert_eq!(config.chunk_size, None);
+    }
+
+    #[test]
+    fn merge_config_output_format_cli_wins_over_file() {
+        let file = PartialConfig {
+            output_format: Some(OutputFormat::Markdown),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            output_format: Some(OutputFormat::Xml),
+            ..empty_partial()
+        };
+        let config = merge_config(file, cli);
+        assert_eq!(config.output_format, OutputFormat::Xml);
+    }
+
+    #[test]
+    fn merge_config_output_format_file_wins_when_cli_unset() {
+        let file = PartialConfig {
+            output_format: Some(OutputFormat::Xml),
+            ..empty_partial()
+        };
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.output_format, OutputFormat::Xml);
+    }
+
+    // ---- OutputFormat ----
+
+    #[test]
+    fn output_format_parses_lower_case_names() {
+        assert_eq!(
+            "markdown".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Markdown
+        );
+        assert_eq!("xml".parse::<OutputFormat>().unwrap(), OutputFormat::Xml);
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!(
+            "jsonl".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Jsonl
+        );
+    }
+
+    #[test]
+    fn output_format_rejects_unknown_names() {
+        let err = "yaml".parse::<OutputFormat>().unwrap_err();
+        assert!(matches!(
+            err,
+            FyaiError::InvalidValue {
+                key: "output_format",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn output_format_deserializes_from_toml() {
+        let config: PartialConfig = toml::from_str("output_format = \"xml\"").unwrap();
+        assert_eq!(config.output_format, Some(OutputFormat::Xml));
+    }
+
+    // ---- tokenizer ----
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn merge_config_tokenizer_cli_wins_over_file() {
+        let file = PartialConfig {
+            tokenizer: Some(Tokenizer::Chars),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            tokenizer: Some(Tokenizer::Cl100k),
+            ..empty_partial()
+        };
+        let config = merge_config(file, cli);
+        assert_eq!(config.tokenizer, Tokenizer::Cl100k);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn tokenizer_deserializes_from_toml() {
+        let config: PartialConfig = toml::from_str("tokenizer = \"o200k\"").unwrap();
+        assert_eq!(config.tokenizer, Some(Tokenizer::O200k));
+    }
+
+    // ---- changed_since ----
+
+    #[test]
+    fn merge_config_changed_since_cli_wins_over_file() {
+        let file = PartialConfig {
+            changed_since: Some("main".to_string()),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            changed_since: Some("v1.0".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file.clone(), cli);
+        assert_eq!(config.changed_since.as_deref(), Some("v1.0"));
+
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.changed_since.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn merge_config_diff_cli_wins_over_file() {
+        let file = PartialConfig {
+            diff: Some("main".to_string()),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            diff: Some("v1.0..v2.0".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file.clone(), cli);
+        assert_eq!(config.diff.as_deref(), Some("v1.0..v2.0"));
+
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.diff.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn merge_config_rev_cli_wins_over_file() {
+        let file = PartialConfig {
+            rev: Some("v1.0".to_string()),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            rev: Some("v2.3".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file.clone(), cli);
+        assert_eq!(config.rev.as_deref(), Some("v2.3"));
+
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.rev.as_deref(), Some("v1.0"));
+    }
+
+    #[test]
+    fn merge_config_repo_path_cli_wins_over_file() {
+        let file = PartialConfig {
+            repo_path: Some("packages/web".to_string()),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            repo_path: Some("packages/api".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file.clone(), cli);
+        assert_eq!(config.repo_path, Some(PathBuf::from("packages/api")));
+
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.repo_path, Some(PathBuf::from("packages/web")));
+    }
+
+    // ---- budget_order ----
+
+    #[test]
+    fn merge_config_budget_order_cli_wins_over_file() {
+        let file = PartialConfig {
+            budget_order: Some(BudgetOrder::Recent),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            budget_order: Some(BudgetOrder::Smallest),
+            ..empty_partial()
+        };
+        let config = merge_config(file, cli);
+        assert_eq!(config.budget_order, BudgetOrder::Smallest);
+    }
+
+    #[test]
+    fn budget_order_parses_lower_case_names_and_rejects_others() {
+        assert_eq!("walk".parse::<BudgetOrder>().unwrap(), BudgetOrder::Walk);
+        assert_eq!(
+            "smallest".parse::<BudgetOrder>().unwrap(),
+            BudgetOrder::Smallest
+        );
+        assert_eq!(
+            "recent".parse::<BudgetOrder>().unwrap(),
+            BudgetOrder::Recent
+        );
+        assert!(matches!(
+            "largest".parse::<BudgetOrder>().unwrap_err(),
+            FyaiError::InvalidValue {
+                key: "budget_order",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn budget_keys_deserialize_from_toml() {
+        let config: PartialConfig =
+            toml::from_str("max_tokens = 1000\nbudget_order = \"smallest\"").unwrap();
+        assert_eq!(config.max_tokens, Some(1000));
+        assert_eq!(config.budget_order, Some(BudgetOrder::Smallest));
+    }
+
+    #[test]
+    fn redact_rules_deserialize_from_a_toml_table() {
+        let config: PartialConfig =
+            toml::from_str("redact = true\n[redact_rules]\ninternal = 'itk_[a-z0-9]{32}'\n")
+                .unwrap();
+        assert_eq!(config.redact, Some(true));
+        assert_eq!(
+            config.redact_rules,
+            Some(BTreeMap::from([(
+                "internal".to_string(),
+                "itk_[a-z0-9]{32}".to_string()
+            )]))
+        );
+    }
+
+    #[test]
+    fn merge_config_redact_rules_file_used_unless_cli_sets_them() {
+        let rules =
+            |pattern: &str| Some(BTreeMap::from([("kind".to_string(), pattern.to_string())]));
+        let file = PartialConfig {
+            redact_rules: rules("file"),
+            ..empty_partial()
+        };
+        assert_eq!(
+            merge_config(file.clone(), empty_partial()).redact_rules,
+            rules("file").unwrap()
+        );
+
+        let cli = PartialConfig {
+            redact_rules: rules("cli"),
+            ..empty_partial()
+        };
+        assert_eq!(merge_config(file, cli).redact_rules, rules("cli").unwrap());
+    }
+
+    // ---- chunk_size ----
+
+    #[test]
+    fn chunk_size_parses_bytes_and_tokens() {
+        assert_eq!(
+            "500000".parse::<ChunkSize>().unwrap(),
+            ChunkSize::Bytes(500_000)
+        );
+        assert_eq!("64b".parse::<ChunkSize>().unwrap(), ChunkSize::Bytes(64));
+        assert_eq!(
+            "64bytes".parse::<ChunkSize>().unwrap(),
+            ChunkSize::Bytes(64)
+        );
+        assert_eq!(
+            "100000tokens".parse::<ChunkSize>().unwrap(),
+            ChunkSize::Tokens(100_000)
+        );
+        assert_eq!("10 t".parse::<ChunkSize>().unwrap(), ChunkSize::Tokens(10));
+    }
+
+    #[test]
+    fn chunk_size_rejects_zero_missing_numbers_and_unknown_units() {
+        for value in ["0", "0tokens", "tokens", "", "10kb", "-5", "1.5t"] {
+            let err = value.parse::<ChunkSize>().unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    FyaiError::InvalidValue {
+                        key: "chunk_size",
+                        ..
+                    }
+                ),
+                "value = {value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn chunk_size_deserializes_from_toml_integer_or_string() {
+        let config: PartialConfig = toml::from_str("chunk_size = 4096").unwrap();
+        assert_eq!(config.chunk_size, Some(ChunkSize::Bytes(4096)));
+
+        let config: PartialConfig = toml::from_str("chunk_size = \"8000tokens\"").unwrap();
+        assert_eq!(config.chunk_size, Some(ChunkSize::Tokens(8000)));
+
+        assert!(toml::from_str::<PartialConfig>("chunk_size = \"lots\"").is_err());
+    }
+
+    #[test]
+    fn chunk_size_serializes_with_its_unit() {
+        assert_eq!(String::from(ChunkSize::Bytes(10)), "10bytes");
+        assert_eq!(String::from(ChunkSize::Tokens(10)), "10tokens");
     }
 
     #[test]
@@ -456,6 +1611,49 @@ mod tests {
         assert_eq!(config.directory, PathBuf::from("from-file"));
     }
 
+    #[test]
+    fn merge_config_directories_replace_directory() {
+        let file = PartialConfig {
+            directory: Some("from-file".to_string()),
+            directories: Some(vec!["backend".to_string(), "../protos".to_string()]),
+            ..empty_partial()
+        };
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.directory, PathBuf::from("backend"));
+        assert_eq!(config.extra_directories, [PathBuf::from("../protos")]);
+        assert_eq!(
+            config.directories().collect::<Vec<_>>(),
+            [Path::new("backend"), Path::new("../protos")]
+        );
+    }
+
+    #[test]
+    fn merge_config_cli_directory_replaces_file_directories() {
+        let file = PartialConfig {
+            directories: Some(vec!["backend".to_string(), "../protos".to_string()]),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            directory: Some("from-cli".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file, cli);
+        assert_eq!(config.directory, PathBuf::from("from-cli"));
+        assert!(config.extra_directories.is_empty());
+    }
+
+    #[test]
+    fn merge_config_empty_directories_fall_back_to_directory() {
+        let file = PartialConfig {
+            directory: Some("from-file".to_string()),
+            directories: Some(Vec::new()),
+            ..empty_partial()
+        };
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.directory, PathBuf::from("from-file"));
+        assert!(config.extra_directories.is_empty());
+    }
+
     #[test]
     fn merge_config_output_cli_wins_over_file() {
         let file = PartialConfig {
@@ -480,6 +1678,157 @@ mod tests {
         assert_eq!(config.output, PathBuf::from("file-out.txt"));
     }
 
+    #[test]
+    fn merge_config_files_from_cli_wins_over_file() {
+        let file = PartialConfig {
+            files_from: Some("files.txt".to_string()),
+            ..empty_partial()
+        };
+        let cli = PartialConfig {
+            files_from: Some("-".to_string()),
+            ..empty_partial()
+        };
+        let config = merge_config(file.clone(), cli);
+        assert_eq!(config.files_from, Some(PathBuf::from("-")));
+
+        let config = merge_config(file, empty_partial());
+        assert_eq!(config.files_from, Some(PathBuf::from("files.txt")));
+    }
+
+    // ---- ConfigBuilder ----
+
+    #[test]
+    fn builder_defaults_match_merge_config_defaults() {
+        assert_eq!(
+            Config::builder().build(),
+            merge_config(empty_partial(), empty_partial())
+        );
+        assert_eq!(Config::builder().build(), Config::default());
+    }
+
+    #[test]
+    fn builder_setters_set_every_field() {
+        let config = Config::builder()
+            .directories(["src", "../shared"])
+            .files_from("-")
+            .output("-")
+            .include_dirs(["src"])
+            .exclude_dirs(vec!["target".to_string()])
+            .include_ext(["rs"])
+            .exclude_ext(["lock"])
+            .include_files(["main.rs"])
+            .exclude_files(["LICENSE"])
+            .include_globs(["src/**/*.rs"])
+            .exclude_globs(["**/generated/**"])
+            .grep(["PaymentIntent"])
+            .grep_invert(true)
+            .grep_lines(true)
+            .min_size(1)
+            .max_size(2)
+            .changed_since("main")
+            .staged(true)
+            .unstaged(true)
+            .full_tree(true)
+            .diff("main..HEAD")
+            .git_log(5)
+            .rev("v2.3")
+            .repo_path("packages/api")
+            .submodules(true)
+            .cache(true)
+            .cache_max_size(1 << 20)
+            .hidden(false)
+            .gitignore(false)
+            .ignore_files(false)
+            .git_global(false)
+            .follow_links(true)
+            .tree_only(true)
+            .strip_comments(true)
+            .compact(true)
+            .compact_tabs(4)
+            .line_numbers(true)
+            .redact(false)
+            .redact_rules([("internal", "itk_\\w+")])
+            .human(true)
+            .output_format(OutputFormat::Json)
+            .tokenizer(Tokenizer::Chars)
+            .heading_tokens(true)
+            .max_tokens(1000)
+            .budget_order(BudgetOrder::Recent)
+            .chunk_size(ChunkSize::Tokens(500))
+            .build();
+
+        let expected = merge_config(
+            empty_partial(),
+            PartialConfig {
+                directory: None,
+                directories: Some(vec!["src".to_string(), "../shared".to_string()]),
+                files_from: Some("-".to_string()),
+                output: Some("-".to_string()),
+                include_dirs: Some(vec!["src".to_string()]),
+                exclude_dirs: Some(vec!["target".to_string()]),
+                include_ext: Some(vec!["rs".to_string()]),
+                exclude_ext: Some(vec!["lock".to_string()]),
+                include_files: Some(vec!["main.rs".to_string()]),
+                exclude_files: Some(vec!["LICENSE".to_string()]),
+                include_globs: Some(vec!["src/**/*.rs".to_string()]),
+                exclude_globs: Some(vec!["**/generated/**".to_string()]),
+                grep: Some(vec!["PaymentIntent".to_string()]),
+                grep_invert: Some(true),
+                grep_lines: Some(true),
+                min_size: Some(1),
+                max_size: Some(2),
+                changed_since: Some("main".to_string()),
+                staged: Some(true),
+                unstaged: Some(true),
+                full_tree: Some(true),
+                diff: Some("main..HEAD".to_string()),
+                git_log: Some(5),
+                rev: Some("v2.3".to_string()),
+                repo_path: Some("packages/api".to_string()),
+                submodules: Some(true),
+                cache: Some(true),
+                cache_max_size: Some(1 << 20),
+                hidden: Some(false),
+                gitignore: Some(false),
+                ignore_files: Some(false),
+                git_global: Some(false),
+                follow_links: Some(true),
+                tree_only: Some(true),
+                strip_comments: Some(true),
+                compact: Some(true),
+                compact_tabs: Some(4),
+                line_numbers: Some(true),
+                redact: Some(false),
+                redact_rules: Some(BTreeMap::from([(
+                    "internal".to_string(),
+                    "itk_\\w+".to_string(),
+                )])),
+                human: Some(true),
+                output_format: Some(OutputFormat::Json),
+                tokenizer: Some(Tokenizer::Chars),
+                heading_tokens: Some(true),
+                max_tokens: Some(1000),
+                budget_order: Some(BudgetOrder::Recent),
+                chunk_size: Some(ChunkSize::Tokens(500)),
+            },
+        );
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn output_is_stdout_only_for_a_lone_dash() {
+        let config_for = |output: &str| {
+            let cli = PartialConfig {
+                output: Some(output.to_string()),
+                ..empty_partial()
+            };
+            merge_config(empty_partial(), cli)
+        };
+        assert!(config_for("-").output_is_stdout());
+        assert!(!config_for("fyai.txt").output_is_stdout());
+        assert!(!config_for("./-").output_is_stdout());
+    }
+
     macro_rules! bool_field_tests {
         ($field:ident, $default:expr, $cli_wins:ident, $file_wins:ident, $default_test:ident) => {
             #[test]
@@ -556,6 +1905,34 @@ mod tests {
         merge_config_tree_only_file_wins,
         merge_config_tree_only_default
     );
+    bool_field_tests!(
+        strip_comments,
+        false,
+        merge_config_strip_comments_cli_wins,
+        merge_config_strip_comments_file_wins,
+        merge_config_strip_comments_default
+    );
+    bool_field_tests!(
+        compact,
+        false,
+        merge_config_compact_cli_wins,
+        merge_config_compact_file_wins,
+        merge_config_compact_default
+    );
+    bool_field_tests!(
+        line_numbers,
+        false,
+        merge_config_line_numbers_cli_wins,
+        merge_config_line_numbers_file_wins,
+        merge_config_line_numbers_default
+    );
+    bool_field_tests!(
+        redact,
+        true,
+        merge_config_redact_cli_wins,
+        merge_config_redact_file_wins,
+        merge_config_redact_default
+    );
     bool_field_tests!(
         human,
         false,
@@ -563,6 +1940,62 @@ mod tests {
         merge_config_human_file_wins,
         merge_config_human_default
     );
+    bool_field_tests!(
+        heading_tokens,
+        false,
+        merge_config_heading_tokens_cli_wins,
+        merge_config_heading_tokens_file_wins,
+        merge_config_heading_tokens_default
+    );
+    bool_field_tests!(
+        staged,
+        false,
+        merge_config_staged_cli_wins,
+        merge_config_staged_file_wins,
+        merge_config_staged_default
+    );
+    bool_field_tests!(
+        unstaged,
+        false,
+        merge_config_unstaged_cli_wins,
+        merge_config_unstaged_file_wins,
+        merge_config_unstaged_default
+    );
+    bool_field_tests!(
+        full_tree,
+        false,
+        merge_config_full_tree_cli_wins,
+        merge_config_full_tree_file_wins,
+        merge_config_full_tree_default
+    );
+    bool_field_tests!(
+        grep_invert,
+        false,
+        merge_config_grep_invert_cli_wins,
+        merge_config_grep_invert_file_wins,
+        merge_config_grep_invert_default
+    );
+    bool_field_tests!(
+        grep_lines,
+        false,
+        merge_config_grep_lines_cli_wins,
+        merge_config_grep_lines_file_wins,
+        merge_config_grep_lines_default
+    );
+    bool_field_tests!(
+        submodules,
+        false,
+        merge_config_submodules_cli_wins,
+        merge_config_submodules_file_wins,
+        merge_config_submodules_default
+    );
+    bool_field_tests!(
+        cache,
+        false,
+        merge_config_cache_cli_wins,
+        merge_config_cache_file_wins,
+        merge_config_cache_default
+    );
 
     macro_rules! vec_field_tests {
         ($field:ident, $cli_wins:ident, $file_wins:ident, $default_test:ident) => {
@@ -634,6 +2067,24 @@ mod tests {
         merge_config_exclude_files_file_wins,
         merge_config_exclude_files_default
     );
+    vec_field_tests!(
+        include_globs,
+        merge_config_include_globs_cli_wins,
+        merge_config_include_globs_file_wins,
+        merge_config_include_globs_default
+    );
+    vec_field_tests!(
+        exclude_globs,
+        merge_config_exclude_globs_cli_wins,
+        merge_config_exclude_globs_file_wins,
+        merge_config_exclude_globs_default
+    );
+    vec_field_tests!(
+        grep,
+        merge_config_grep_cli_wins,
+        merge_config_grep_file_wins,
+        merge_config_grep_default
+    );
 
     macro_rules! u64_field_tests {
         ($field:ident, $cli_wins:ident, $file_wins:ident, $default_test:ident) => {
@@ -681,4 +2132,28 @@ mod tests {
         merge_config_max_size_file_wins,
         merge_config_max_size_default
     );
+    u64_field_tests!(
+        git_log,
+        merge_config_git_log_cli_wins,
+        merge_config_git_log_file_wins,
+        merge_config_git_log_default
+    );
+    u64_field_tests!(
+        cache_max_size,
+        merge_config_cache_max_size_cli_wins,
+        merge_config_cache_max_size_file_wins,
+        merge_config_cache_max_size_default
+    );
+    u64_field_tests!(
+        compact_tabs,
+        merge_config_compact_tabs_cli_wins,
+        merge_config_compact_tabs_file_wins,
+        merge_config_compact_tabs_default
+    );
+    u64_field_tests!(
+        max_tokens,
+        merge_config_max_tokens_cli_wins,
+        merge_config_max_tokens_file_wins,
+        merge_config_max_tokens_default
+    );
 }
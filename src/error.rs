@@ -31,9 +31,58 @@ pub enum FyaiError {
         source: toml::de::Error,
     },
 
+    /// A config or CLI value isn't one `key` accepts.
+    #[error("invalid value {value:?} for {key}")]
+    InvalidValue {
+        /// Name of the config key the value was given for.
+        key: &'static str,
+        /// The rejected value, as written.
+        value: String,
+    },
+
+    /// A pattern in the `key` config list isn't a valid glob.
+    #[error("invalid glob in {key}: {source}")]
+    InvalidGlob {
+        /// Name of the config key the glob was given for.
+        key: &'static str,
+        /// Underlying glob parse error, naming the pattern.
+        source: globset::Error,
+    },
+
+    /// A pattern in the `key` config list isn't a valid regex.
+    #[error("invalid regex in {key}: {source}")]
+    InvalidRegex {
+        /// Name of the config key the regex was given for.
+        key: &'static str,
+        /// Underlying regex parse error, naming the pattern.
+        source: regex::Error,
+    },
+
+    /// The archive scanned in place of a directory couldn't be read: it
+    /// isn't a supported archive, it's corrupt, or it was combined with an
+    /// option that needs a git working copy.
+    #[error("failed to read archive {path}: {source}")]
+    Archive {
+        /// Path of the archive.
+        path: PathBuf,
+        /// Underlying I/O or decoding error.
+        source: io::Error,
+    },
+
     /// Spawning `git`, or the `git` command itself, failed.
     #[error("{0}")]
     Git(String),
+
+    /// The native git backend failed to `operation` (`clone` or
+    /// `checkout`) a `--repo` source.
+    #[cfg(feature = "native-git")]
+    #[error("git {operation} failed: {source}")]
+    NativeGit {
+        /// The step that failed.
+        operation: &'static str,
+        /// Underlying libgit2 error, with its class and code.
+        source: git2::Error,
+    },
 }
 
 /// A [`Result`](std::result::Result) whose error type is [`FyaiError`].
@@ -106,12 +155,68 @@ mod tests {
         assert!(msg.contains(&toml_err_string));
     }
 
+    #[test]
+    fn invalid_value_display_includes_key_and_value() {
+        let err = FyaiError::InvalidValue {
+            key: "output_format",
+            value: "yaml".to_string(),
+        };
+        assert_eq!(err.to_string(), "invalid value \"yaml\" for output_format");
+    }
+
+    #[test]
+    fn invalid_glob_display_includes_key_and_pattern() {
+        let source = globset::Glob::new("src/[").expect_err("unclosed class");
+        let err = FyaiError::InvalidGlob {
+            key: "include_globs",
+            source,
+        };
+        let msg = err.to_string();
+        assert!(msg.starts_with("invalid glob in include_globs: "));
+        assert!(msg.contains("src/["));
+    }
+
+    #[test]
+    fn invalid_regex_display_includes_key_and_pattern() {
+        let pattern = String::from("Payment(");
+        let source = regex::Regex::new(&pattern).expect_err("unclosed group");
+        let err = FyaiError::InvalidRegex {
+            key: "grep",
+            source,
+        };
+        let msg = err.to_string();
+        assert!(msg.starts_with("invalid regex in grep: "));
+        assert!(msg.contains("Payment("));
+    }
+
+    #[test]
+    fn archive_display_includes_path_and_source() {
+        let err = FyaiError::Archive {
+            path: PathBuf::from("src.tar.gz"),
+            source: io::Error::other("unexpected end of file"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "failed to read archive src.tar.gz: unexpected end of file"
+        );
+    }
+
     #[test]
     fn git_variant_display_prints_inner_string() {
         let err = FyaiError::Git("git executable not found".to_string());
         assert_eq!(err.to_string(), "git executable not found");
     }
 
+    #[cfg(feature = "native-git")]
+    #[test]
+    fn native_git_display_includes_operation_and_source() {
+        let err = FyaiError::NativeGit {
+            operation: "clone",
+            source: git2::Error::from_str("repository not found"),
+        };
+        assert_eq!(err.to_string(), "git clone failed: repository not found");
+    }
+
     #[test]
     fn debug_impl_is_available() {
         let err = FyaiError::Git("boom".to_string());
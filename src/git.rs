@@ -0,0 +1,824 @@
+//! Thin wrappers around the `git` command line, for the run options that
+//! ask git about the scanned directory (which files changed, and since
+//! when, the diff itself, recent history, and where its submodules are),
+//! or scan it at another revision.
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use serde::Serialize;
+use tempfile::TempDir;
+
+use crate::config::Config;
+use crate::error::{FyaiError, Result};
+
+/// Returns the files under `config.directory` that `config.changed_since`,
+/// `config.staged`, and `config.unstaged` select, as absolute paths joined
+/// onto `config.directory`, or `None` when none of them is set.
+///
+/// Each option contributes its own set of paths and the result is their
+/// union:
+///
+/// - `changed_since`: files that differ between the merge base of that ref
+///   and `HEAD`, and the working tree, so both the branch's commits and
+///   uncommitted edits count, but commits that landed on the ref after the
+///   branch point don't. In a fresh clone, where only the checked-out
+///   branch exists locally, `origin/<ref>` is tried too.
+/// - `staged`: files with changes in the index.
+/// - `unstaged`: files with changes not yet added to the index, plus
+///   untracked files git doesn't ignore.
+///
+/// Deleted files are left out, since there's nothing left to read.
+pub(crate) fn changed_paths(config: &Config) -> Result<Option<HashSet<PathBuf>>> {
+    if config.changed_since.is_none() && !config.staged && !config.unstaged {
+        return Ok(None);
+    }
+    let dir = &config.directory;
+
+    let mut relative = Vec::new();
+    if let Some(since) = &config.changed_since {
+        let base = merge_base(dir, since, "HEAD")?;
+        relative.extend(diff_names(dir, &[base.as_str()])?);
+    }
+    if config.staged {
+        relative.extend(diff_names(dir, &["--cached"])?);
+    }
+    if config.unstaged {
+        relative.extend(diff_names(dir, &[])?);
+        relative.extend(split_nul(&git_output(
+            dir,
+            ["ls-files", "--others", "--exclude-standard", "-z"],
+            "git ls-files",
+        )?));
+    }
+
+    Ok(Some(
+        relative.into_iter().map(|path| dir.join(path)).collect(),
+    ))
+}
+
+/// Returns the merge base of `since` and `head`, a revision git resolves
+/// as is (`HEAD`, or a SHA).
+fn merge_base(dir: &Path, since: &str, head: &str) -> Result<String> {
+    let since = resolve_ref(dir, since)?;
+    let base = git_output(dir, ["merge-base", since.as_str(), head], "git merge-base")?;
+    Ok(String::from_utf8_lossy(&base).trim().to_string())
+}
+
+/// Resolves `name` to a commit SHA, trying `origin/<name>` when `name`
+/// itself doesn't resolve: in a fresh clone, only the checked-out branch
+/// exists locally.
+fn resolve_ref(dir: &Path, name: &str) -> Result<String> {
+    let resolve = |name: &str| -> Result<Option<String>> {
+        let spec = format!("{name}^{{commit}}");
+        let output = run_git(dir, ["rev-parse", "--verify", "--quiet", spec.as_str()])
+            .map_err(|e| FyaiError::Git(format!("failed to run git rev-parse: {e}")))?;
+        if output.status.success() {
+            return Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            ));
+        }
+        // `--quiet` makes an unknown name fail silently; anything on stderr
+        // is a real problem, like not being in a repository.
+        if output.stderr.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        Err(FyaiError::Git(format!(
+            "git rev-parse failed: {}",
+            command_error_details(&output)
+        )))
+    };
+    match resolve(name)? {
+        Some(sha) => Ok(sha),
+        None => resolve(&format!("origin/{name}"))?
+            .ok_or_else(|| FyaiError::Git(format!("unknown git revision {name:?}"))),
+    }
+}
+
+/// How many bytes of pathspecs one `git diff` run in [`diff`] gets, well
+/// under the command-line limits of every platform (Windows allows 32 KiB
+/// in all).
+const MAX_PATHSPEC_BYTES: usize = 16 * 1024;
+
+/// Returns the unified diff for `range` (`<base>`, diffed against the
+/// working tree, `<base>..<head>`, or `<base>...<head>`, which diffs `head`
+/// against its merge base with `base`, as `git diff` does) in `dir`,
+/// limited to the changed
+/// paths (relative to `dir`) that `keep` accepts. A path counts as changed
+/// on either side, so deletions and both ends of a rename are offered to
+/// `keep` too. As in git, an empty side of a range means `HEAD`.
+///
+/// Returns an empty string when nothing changed, or `keep` rejects every
+/// changed path.
+pub(crate) fn diff(dir: &Path, range: &str, keep: impl Fn(&Path) -> bool) -> Result<String> {
+    let or_head = |rev: &str| if rev.is_empty() { "HEAD" } else { rev }.to_string();
+    let mut revs = Vec::with_capacity(2);
+    if let Some((base, head)) = range.split_once("...") {
+        let head = resolve_ref(dir, &or_head(head))?;
+        revs.push(merge_base(dir, &or_head(base), &head)?);
+        revs.push(head);
+    } else if let Some((base, head)) = range.split_once("..") {
+        revs.push(resolve_ref(dir, &or_head(base))?);
+        revs.push(resolve_ref(dir, &or_head(head))?);
+    } else {
+        revs.push(resolve_ref(dir, range)?);
+    }
+
+    let mut args = vec!["diff", "--name-only", "--relative", "--no-renames", "-z"];
+    args.extend(revs.iter().map(String::as_str));
+    let paths: Vec<PathBuf> = split_nul(&git_output(dir, args, "git diff")?)
+        .into_iter()
+        .filter(|path| keep(path))
+        .collect();
+    if paths.is_empty() {
+        return Ok(String::new());
+    }
+
+    // `git diff` can't read pathspecs from stdin, so a large change set
+    // goes through several runs, each with a bounded command line. The
+    // paths come in the order `git diff` writes them, so the pieces join up
+    // into the diff a single run would have written.
+    let mut diff = Vec::new();
+    let mut paths = paths.iter().peekable();
+    while paths.peek().is_some() {
+        let mut args: Vec<OsString> = ["diff", "--relative", "--no-color", "--no-ext-diff"]
+            .into_iter()
+            .chain(revs.iter().map(String::as_str))
+            .chain(["--"])
+            .map(OsString::from)
+            .collect();
+        let mut batch_len = 0;
+        while let Some(path) = paths.peek() {
+            // `:(literal)` keeps names with glob characters from matching
+            // others.
+            let mut pathspec = OsString::from(":(literal)");
+            pathspec.push(path);
+            if batch_len > 0 && batch_len + pathspec.len() > MAX_PATHSPEC_BYTES {
+                break;
+            }
+            batch_len += pathspec.len() + 1;
+            args.push(pathspec);
+            paths.next();
+        }
+        diff.extend(git_output(dir, args, "git diff")?);
+    }
+    Ok(String::from_utf8_lossy(&diff).into_owned())
+}
+
+/// Runs `git diff --name-only` with `extra` arguments, returning the
+/// changed, non-deleted paths relative to `dir`.
+fn diff_names(dir: &Path, extra: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut args = vec!["diff", "--name-only", "--relative", "--diff-filter=d", "-z"];
+    args.extend_from_slice(extra);
+    Ok(split_nul(&git_output(dir, args, "git diff")?))
+}
+
+/// Splits `git ... -z` output into paths.
+fn split_nul(output: &[u8]) -> Vec<PathBuf> {
+    output
+        .split(|&byte| byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+        .collect()
+}
+
+/// One commit in a [`recent_commits`] listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Commit {
+    /// Abbreviated commit hash.
+    pub hash: String,
+    /// Author date, as `YYYY-MM-DD`.
+    pub date: String,
+    /// First line of the commit message.
+    pub subject: String,
+    /// Paths the commit changed under the listed directory, relative to
+    /// it. Empty for merge commits.
+    pub paths: Vec<String>,
+}
+
+/// Returns the latest `count` commits reachable from `HEAD` that touch
+/// `dir`, newest first.
+pub(crate) fn recent_commits(dir: &Path, count: u64) -> Result<Vec<Commit>> {
+    let count = format!("--max-count={count}");
+    let log = git_output(
+        dir,
+        [
+            "-c",
+            "core.quotePath=false",
+            "log",
+            count.as_str(),
+            "--date=short",
+            "--format=%x1e%h%x1f%ad%x1f%s",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ],
+        "git log",
+    )?;
+    let log = String::from_utf8_lossy(&log);
+
+    Ok(log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.splitn(3, '\x1f');
+            Some(Commit {
+                hash: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or_default().to_string(),
+                paths: lines
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect())
+}
+
+/// Returns the root of every submodule under `dir`, as absolute paths
+/// joined onto `dir`, including the ones nested in checked-out submodules.
+/// A `dir` outside any git work tree has none.
+pub(crate) fn submodule_roots(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !is_work_tree(dir)? {
+        return Ok(Vec::new());
+    }
+    let index = git_output(dir, ["ls-files", "--stage", "-z"], "git ls-files")?;
+    let mut roots = Vec::new();
+    for record in index.split(|&byte| byte == 0) {
+        let record = String::from_utf8_lossy(record);
+        // `<mode> <object> <stage>\t<path>`; submodules are gitlinks.
+        let Some((meta, path)) = record.split_once('\t') else {
+            continue;
+        };
+        if !meta.starts_with("160000 ") {
+            continue;
+        }
+        let root = dir.join(path);
+        if root.join(".git").exists() {
+            roots.extend(submodule_roots(&root)?);
+        }
+        roots.push(root);
+    }
+    Ok(roots)
+}
+
+/// A detached, temporary worktree of a repository, checked out at one
+/// revision. Dropping it removes the worktree and its directory.
+#[derive(Debug)]
+pub(crate) struct Worktree {
+    repo: PathBuf,
+    root: PathBuf,
+    _temp_dir: TempDir,
+}
+
+impl Worktree {
+    /// Checks out `rev` of the repository containing `dir` into a new
+    /// temporary worktree. `rev` resolves like `--changed-since` does, so
+    /// `origin/<rev>` is tried too.
+    ///
+    /// Returns the worktree alongside `dir`'s counterpart inside it, which
+    /// must exist at `rev`.
+    pub(crate) fn at_revision(dir: &Path, rev: &str) -> Result<(Self, PathBuf)> {
+        let sha = resolve_ref(dir, rev)?;
+        let repo = git_line(dir, ["rev-parse", "--show-toplevel"])?;
+        let prefix = git_line(dir, ["rev-parse", "--show-prefix"])?;
+
+        // Named after the repository, so the tree's root label stays the
+        // same as for the working copy.
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir
+            .path()
+            .join(Path::new(&repo).file_name().unwrap_or(OsStr::new("repo")));
+        git_output(
+            dir,
+            [
+                OsStr::new("worktree"),
+                OsStr::new("add"),
+                OsStr::new("--quiet"),
+                OsStr::new("--detach"),
+                root.as_os_str(),
+                OsStr::new(&sha),
+            ],
+            "git worktree add",
+        )?;
+        let worktree = Worktree {
+            repo: PathBuf::from(repo),
+            root,
+            _temp_dir: temp_dir,
+        };
+
+        let directory = if prefix.is_empty() {
+            worktree.root.clone()
+        } else {
+            worktree.root.join(prefix)
+        };
+        if !directory.is_dir() {
+            return Err(FyaiError::Git(format!(
+                "{} does not exist at revision {rev:?}",
+                dir.display()
+            )));
+        }
+        Ok((worktree, directory))
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        // Best effort: if this fails, `git worktree prune` cleans up the
+        // registration once the directory is gone.
+        let _ = run_git(
+            &self.repo,
+            [
+                OsStr::new("worktree"),
+                OsStr::new("remove"),
+                OsStr::new("--force"),
+                self.root.as_os_str(),
+            ],
+        );
+    }
+}
+
+/// Runs `git -C dir <args>` and returns its stdout's first line.
+fn git_line<const N: usize>(dir: &Path, args: [&str; N]) -> Result<String> {
+    let what = format!("git {}", args[0]);
+    let stdout = git_output(dir, args, &what)?;
+    Ok(String::from_utf8_lossy(&stdout).trim_end().to_string())
+}
+
+/// Returns whether `dir` is inside a git work tree. Only failing to run
+/// git at all is an error.
+fn is_work_tree(dir: &Path) -> Result<bool> {
+    let output = run_git(dir, ["rev-parse", "--is-inside-work-tree"])
+        .map_err(|e| FyaiError::Git(format!("failed to run git rev-parse: {e}")))?;
+    Ok(output.status.success() && output.stdout.trim_ascii() == b"true")
+}
+
+/// Runs `git -C dir <args>` and returns its stdout, or a
+/// [`FyaiError::Git`] naming `what` if git can't be run or fails.
+pub(crate) fn git_output<I, S>(dir: &Path, args: I, what: &str) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output =
+        run_git(dir, args).map_err(|e| FyaiError::Git(format!("failed to run {what}: {e}")))?;
+    if !output.status.success() {
+        return Err(FyaiError::Git(format!(
+            "{what} failed: {}",
+            command_error_details(&output)
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Runs `git -C dir <args>`, capturing its output whether or not it
+/// succeeds.
+fn run_git<I, S>(dir: &Path, args: I) -> io::Result<process::Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new("git").arg("-C").arg(dir).args(args).output()
+}
+
+/// Extracts a human-readable error message from a failed command's output,
+/// preferring stderr over stdout, and falling back to a generic message if
+/// both are empty.
+pub(crate) fn command_error_details(output: &process::Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let details = if !stderr.trim().is_empty() {
+        stderr.trim()
+    } else {
+        stdout.trim()
+    };
+
+    if details.is_empty() {
+        "unknown error".to_string()
+    } else {
+        details.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Runs `git <args>` in `dir`, asserting it succeeds.
+    fn git(dir: &Path, args: &[&str]) {
+        git_output(dir, args, "test git").unwrap_or_else(|e| panic!("git {args:?}: {e}"));
+    }
+
+    /// Initializes a repo in a new tempdir with `main` holding `base.txt`
+    /// and `other.txt`, then a `feature` branch that edits `base.txt` and
+    /// adds `new.txt` in a commit.
+    fn feature_branch_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path();
+        git(path, &["init", "-q", "-b", "main"]);
+        git(path, &["config", "user.email", "test@example.com"]);
+        git(path, &["config", "user.name", "Test"]);
+        fs::write(path.join("base.txt"), "base").unwrap();
+        fs::write(path.join("other.txt"), "other").unwrap();
+        git(path, &["add", "."]);
+        git(path, &["commit", "-q", "-m", "initial"]);
+        git(path, &["checkout", "-q", "-b", "feature"]);
+        fs::write(path.join("base.txt"), "edited on feature").unwrap();
+        fs::write(path.join("new.txt"), "new").unwrap();
+        git(path, &["add", "."]);
+        git(path, &["commit", "-q", "-m", "feature work"]);
+        dir
+    }
+
+    fn names(paths: Option<HashSet<PathBuf>>, root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = paths
+            .expect("changed paths")
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn changed_paths_is_none_without_any_change_option() {
+        let config = Config::builder().directory("/nonexistent").build();
+        assert_eq!(changed_paths(&config).unwrap(), None);
+    }
+
+    #[test]
+    fn changed_since_lists_files_touched_since_the_merge_base() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        // A later commit on main must not show up as a change.
+        git(root, &["checkout", "-q", "main"]);
+        fs::write(root.join("other.txt"), "moved on").unwrap();
+        git(root, &["commit", "-q", "-am", "main moves on"]);
+        git(root, &["checkout", "-q", "feature"]);
+
+        let mut config = Config::builder().directory(root).build();
+        config.changed_since = Some("main".to_string());
+
+        assert_eq!(
+            names(changed_paths(&config).unwrap(), root),
+            ["base.txt", "new.txt"]
+        );
+    }
+
+    #[test]
+    fn staged_and_unstaged_split_the_working_tree_changes() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::write(root.join("base.txt"), "staged edit").unwrap();
+        git(root, &["add", "base.txt"]);
+        fs::write(root.join("other.txt"), "unstaged edit").unwrap();
+        fs::write(root.join("untracked.txt"), "untracked").unwrap();
+        fs::remove_file(root.join("new.txt")).unwrap();
+
+        let mut config = Config::builder().directory(root).build();
+        config.staged = true;
+        assert_eq!(names(changed_paths(&config).unwrap(), root), ["base.txt"]);
+
+        config.staged = false;
+        config.unstaged = true;
+        assert_eq!(
+            names(changed_paths(&config).unwrap(), root),
+            ["other.txt", "untracked.txt"]
+        );
+    }
+
+    #[test]
+    fn changed_paths_are_relative_to_a_subdirectory_of_the_repo() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("inner.txt"), "inner").unwrap();
+
+        let mut config = Config::builder().directory(root.join("sub")).build();
+        config.unstaged = true;
+
+        assert_eq!(
+            names(changed_paths(&config).unwrap(), &root.join("sub")),
+            ["inner.txt"]
+        );
+    }
+
+    #[test]
+    fn an_unknown_ref_is_a_git_error() {
+        let repo = feature_branch_repo();
+        let mut config = Config::builder().directory(repo.path()).build();
+        config.changed_since = Some("no-such-branch".to_string());
+
+        let err = changed_paths(&config).unwrap_err();
+        assert!(
+            matches!(err, FyaiError::Git(msg) if msg == "unknown git revision \"no-such-branch\"")
+        );
+    }
+
+    // ---- diff ----
+
+    #[test]
+    fn diff_of_a_range_keeps_only_the_accepted_paths() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+
+        let diff = diff(root, "main..feature", |path| path != Path::new("new.txt")).unwrap();
+
+        assert!(diff.contains("+++ b/base.txt"));
+        assert!(diff.contains("+edited on feature"));
+        assert!(!diff.contains("new.txt"));
+    }
+
+    #[test]
+    fn diff_of_a_three_dot_range_starts_at_the_merge_base() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        git(root, &["checkout", "-q", "main"]);
+        fs::write(root.join("other.txt"), "edited on main").unwrap();
+        git(root, &["commit", "-qam", "main work"]);
+
+        let two_dot = diff(root, "main..feature", |_| true).unwrap();
+        assert!(two_dot.contains("-edited on main"));
+        assert!(two_dot.contains("+edited on feature"));
+
+        let three_dot = diff(root, "main...feature", |_| true).unwrap();
+        assert!(!three_dot.contains("other.txt"));
+        assert!(three_dot.contains("+edited on feature"));
+        assert!(three_dot.contains("+++ b/new.txt"));
+    }
+
+    #[test]
+    fn diff_of_a_single_ref_compares_with_the_working_tree() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::write(root.join("other.txt"), "uncommitted").unwrap();
+        fs::remove_file(root.join("new.txt")).unwrap();
+
+        let diff = diff(root, "feature", |_| true).unwrap();
+
+        assert!(diff.contains("+uncommitted"));
+        assert!(diff.contains("deleted file mode"));
+        assert!(!diff.contains("base.txt"));
+    }
+
+    #[test]
+    fn diff_is_empty_when_every_path_is_rejected() {
+        let repo = feature_branch_repo();
+        assert_eq!(diff(repo.path(), "main..feature", |_| false).unwrap(), "");
+    }
+
+    #[test]
+    fn diff_treats_an_empty_side_of_a_range_as_head() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+
+        assert_eq!(
+            diff(root, "main..", |_| true).unwrap(),
+            diff(root, "main..feature", |_| true).unwrap()
+        );
+        assert_eq!(
+            diff(root, "..main", |_| true).unwrap(),
+            diff(root, "feature..main", |_| true).unwrap()
+        );
+        assert_eq!(
+            diff(root, "main...", |_| true).unwrap(),
+            diff(root, "main...feature", |_| true).unwrap()
+        );
+        assert!(
+            diff(root, "main..", |_| true)
+                .unwrap()
+                .contains("+edited on feature")
+        );
+    }
+
+    #[test]
+    fn diff_of_more_paths_than_one_command_line_holds_covers_them_all() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        let dir = root.join("x".repeat(200));
+        fs::create_dir(&dir).unwrap();
+        let count = 2 * MAX_PATHSPEC_BYTES / 200;
+        for i in 0..count {
+            fs::write(dir.join(format!("{i:05}.txt")), format!("file {i}\n")).unwrap();
+        }
+        git(root, &["add", "."]);
+        git(root, &["commit", "-qm", "many files"]);
+
+        let diff = diff(root, "HEAD~1..", |_| true).unwrap();
+
+        assert_eq!(diff.matches("diff --git ").count(), count);
+        let first = diff.find("00000.txt").unwrap();
+        let last = diff.find(&format!("{:05}.txt", count - 1)).unwrap();
+        assert!(first < last);
+    }
+
+    #[test]
+    fn diff_names_the_unknown_side_of_a_range() {
+        let repo = feature_branch_repo();
+        let err = diff(repo.path(), "main..nope", |_| true).unwrap_err();
+        assert_eq!(err.to_string(), "unknown git revision \"nope\"");
+    }
+
+    // ---- recent_commits ----
+
+    #[test]
+    fn recent_commits_lists_the_latest_commits_touching_the_directory() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("inner.txt"), "inner").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "add sub\n\nWith a body."]);
+
+        let commits = recent_commits(root, 2).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].subject, "add sub");
+        assert_eq!(commits[0].paths, ["sub/inner.txt"]);
+        assert_eq!(commits[1].subject, "feature work");
+        assert_eq!(commits[1].paths, ["base.txt", "new.txt"]);
+        assert_eq!(commits[0].date.len(), "YYYY-MM-DD".len());
+        assert!(!commits[0].hash.is_empty());
+
+        // Only the commit that touched `sub` shows up there, with paths
+        // relative to it.
+        let commits = recent_commits(&root.join("sub"), 10).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].paths, ["inner.txt"]);
+    }
+
+    // ---- submodule_roots ----
+
+    #[test]
+    fn submodule_roots_lists_gitlinks_under_the_directory() {
+        let dep = feature_branch_repo();
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        let url = dep.path().to_str().unwrap();
+        git(
+            root,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                url,
+                "libs/dep",
+            ],
+        );
+        git(root, &["commit", "-q", "-m", "add dep"]);
+
+        assert_eq!(submodule_roots(root).unwrap(), [root.join("libs/dep")]);
+        assert_eq!(
+            submodule_roots(&root.join("libs")).unwrap(),
+            [root.join("libs").join("dep")]
+        );
+        assert!(submodule_roots(&root.join("libs/dep")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn submodule_roots_is_empty_outside_a_work_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(submodule_roots(dir.path()).unwrap().is_empty());
+    }
+
+    // ---- Worktree ----
+
+    #[test]
+    fn worktree_checks_out_the_revision_and_is_removed_on_drop() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::write(root.join("base.txt"), "uncommitted").unwrap();
+
+        let (worktree, directory) = Worktree::at_revision(root, "main").unwrap();
+        assert_eq!(directory.file_name(), root.file_name());
+        assert_eq!(
+            fs::read_to_string(directory.join("base.txt")).unwrap(),
+            "base"
+        );
+        assert!(!directory.join("new.txt").exists());
+
+        drop(worktree);
+        assert!(!directory.exists());
+        let list = git_output(root, ["worktree", "list", "--porcelain"], "test git").unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&list).matches("worktree ").count(),
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("base.txt")).unwrap(),
+            "uncommitted"
+        );
+    }
+
+    #[test]
+    fn worktree_maps_a_subdirectory_into_the_checkout() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("inner.txt"), "v1").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "add sub"]);
+        git(root, &["tag", "v1"]);
+        fs::write(root.join("sub").join("inner.txt"), "v2").unwrap();
+        git(root, &["commit", "-q", "-am", "edit sub"]);
+
+        let (_worktree, directory) = Worktree::at_revision(&root.join("sub"), "v1").unwrap();
+        assert_eq!(directory.file_name().unwrap(), "sub");
+        assert_eq!(
+            fs::read_to_string(directory.join("inner.txt")).unwrap(),
+            "v1"
+        );
+    }
+
+    #[test]
+    fn worktree_fails_when_the_directory_is_missing_at_the_revision() {
+        let repo = feature_branch_repo();
+        let root = repo.path();
+        fs::create_dir(root.join("later")).unwrap();
+        fs::write(root.join("later").join("file.txt"), "later").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "add later"]);
+
+        let err = Worktree::at_revision(&root.join("later"), "main").unwrap_err();
+        assert!(
+            matches!(&err, FyaiError::Git(msg) if msg.ends_with("does not exist at revision \"main\"")),
+            "unexpected error: {err:?}"
+        );
+    }
+
+    #[test]
+    fn worktree_of_an_unknown_revision_is_a_git_error() {
+        let repo = feature_branch_repo();
+        let err = Worktree::at_revision(repo.path(), "v9").unwrap_err();
+        assert!(matches!(err, FyaiError::Git(msg) if msg == "unknown git revision \"v9\""));
+    }
+
+    // ---- command_error_details ----
+
+    /// Returns a real `ExitStatus` representing a failed process, so tests
+    /// can build a custom `Output` around it (`ExitStatus` has no public
+    /// constructor).
+    fn failing_status() -> process::ExitStatus {
+        Command::new("false")
+            .output()
+            .expect("failed to run `false`")
+            .status
+    }
+
+    #[test]
+    fn command_error_details_prefers_stderr_over_stdout() {
+        let output = process::Output {
+            status: failing_status(),
+            stdout: b"stdout message".to_vec(),
+            stderr: b"stderr message".to_vec(),
+        };
+        assert_eq!(command_error_details(&output), "stderr message");
+    }
+
+    #[test]
+    fn command_error_details_falls_back_to_stdout_when_stderr_empty() {
+        let output = process::Output {
+            status: failing_status(),
+            stdout: b"stdout message".to_vec(),
+            stderr: Vec::new(),
+        };
+        assert_eq!(command_error_details(&output), "stdout message");
+    }
+
+    #[test]
+    fn command_error_details_falls_back_to_stdout_when_stderr_whitespace_only() {
+        let output = process::Output {
+            status: failing_status(),
+            stdout: b"stdout message".to_vec(),
+            stderr: b"   \n\t  ".to_vec(),
+        };
+        assert_eq!(command_error_details(&output), "stdout message");
+    }
+
+    #[test]
+    fn command_error_details_returns_unknown_error_when_both_empty() {
+        let output = process::Output {
+            status: failing_status(),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        };
+        assert_eq!(command_error_details(&output), "unknown error");
+    }
+
+    #[test]
+    fn command_error_details_returns_unknown_error_when_both_whitespace_only() {
+        let output = process::Output {
+            status: failing_status(),
+            stdout: b"  \n  ".to_vec(),
+            stderr: b"\t\t".to_vec(),
+        };
+        assert_eq!(command_error_details(&output), "unknown error");
+    }
+}
@@ -3,22 +3,36 @@
 //! and combines the matching files into a single text file for feeding into
 //! an LLM.
 //!
+//! The pipeline is usable a step at a time: [`collect_entries`] runs just
+//! the walk and filters, returning every matching [`Entry`] without reading
+//! or writing anything; [`scan_to_writer`] combines into any
+//! [`std::io::Write`]; and [`run_local`]/[`run_git`] write to the configured
+//! output file (or stdout, for an output path of `-`).
+//!
 //! This crate is intentionally silent and side-effect-free beyond writing
-//! the requested output file: it never prints to stdout/stderr and never
-//! touches the clipboard. Those concerns live in the `feedyourai`/`fyai`
-//! binaries, which are thin CLI wrappers around [`run_local`] and
-//! [`run_git`].
+//! the requested output (and, when asked, keeping `--repo` clones in a
+//! [`cache`](cache::CloneCache)): it never prints anything else and never
+//! touches the clipboard. Those concerns live in the `feedyourai`/`fyai` binaries,
+//! which are thin CLI wrappers around the functions above.
 
 #![warn(missing_docs)]
 
+/// The opt-in cache of `--repo` clones, reused across runs.
+pub mod cache;
 /// Configuration types (CLI-agnostic) and config-file discovery/merging.
 pub mod config;
 /// The crate's error type.
 pub mod error;
+mod git;
+#[cfg(feature = "native-git")]
+mod native_git;
 /// Orchestrates a single combine run against a local directory or a
 /// temporary clone of a git repository.
 pub mod runner;
 /// Directory walking, filtering, and file-combining logic.
 pub mod scanner;
+/// Token counting, to size output against a model's context window.
+pub mod tokens;
 
-pub use runner::{run_git, run_local};
+pub use runner::{run_git, run_git_to_writer, run_local, run_local_to_writer};
+pub use scanner::{Entry, ScanStats, collect_entries, scan_to_writer};
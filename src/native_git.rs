@@ -0,0 +1,375 @@
+//! In-process git (libgit2, behind the `native-git` feature) for cloning
+//! `--repo` sources on the local filesystem, so a run doesn't need a `git`
+//! binary. [`crate::runner`] falls back to the `git` command line for every
+//! other URL.
+
+use std::path::Path;
+
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{AutotagOption, BranchType, Commit, FetchOptions, Repository};
+
+use crate::error::{FyaiError, Result};
+
+/// Returns whether this backend clones `repo_url`: a `file://` URL or a
+/// path to an existing local directory.
+pub(crate) fn supports(repo_url: &str) -> bool {
+    repo_url.starts_with("file://") || Path::new(repo_url).is_dir()
+}
+
+/// Clones `repo_url` into `clone_path` and checks out `branch` (a branch or
+/// tag) and/or `commit`, like `git clone [--branch <branch>]` followed by
+/// `git checkout <commit>`. With a `subdir`, only the files under it are
+/// written to disk. With `submodules`, the submodules (under `subdir`, if
+/// set) are cloned and checked out too, recursively.
+///
+/// The clone always has the full history: libgit2's local transport can't
+/// fetch shallow, and copying local objects is cheap anyway. Failures are
+/// [`FyaiError::NativeGit`], naming the step that failed.
+pub(crate) fn clone_repository(
+    repo_url: &str,
+    branch: Option<&str>,
+    commit: Option<&str>,
+    subdir: Option<&Path>,
+    submodules: bool,
+    clone_path: &Path,
+) -> Result<()> {
+    let mut fetch = FetchOptions::new();
+    fetch.download_tags(AutotagOption::All);
+    let repo = RepoBuilder::new()
+        .fetch_options(fetch)
+        .with_checkout(checkout_options(subdir))
+        .clone(repo_url, clone_path)
+        .map_err(failed("clone"))?;
+
+    if let Some(branch) = branch {
+        check_out_branch_or_tag(&repo, branch, subdir).map_err(failed("checkout"))?;
+    }
+    if let Some(commit) = commit {
+        let target = repo
+            .revparse_single(commit)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(failed("checkout"))?;
+        check_out_detached(&repo, &target, subdir).map_err(failed("checkout"))?;
+    }
+    if submodules {
+        update_submodules(&repo, subdir).map_err(failed("submodule update"))?;
+    }
+    Ok(())
+}
+
+/// Initializes and checks out every submodule of `repo` under `subdir` (or
+/// all of them), then theirs in turn, like `git submodule update --init
+/// --recursive`.
+fn update_submodules(
+    repo: &Repository,
+    subdir: Option<&Path>,
+) -> std::result::Result<(), git2::Error> {
+    for mut submodule in repo.submodules()? {
+        if subdir.is_some_and(|subdir| !submodule.path().starts_with(subdir)) {
+            continue;
+        }
+        submodule.update(true, None)?;
+        update_submodules(&submodule.open()?, None)?;
+    }
+    Ok(())
+}
+
+/// Forced checkout options, limited to `subdir` when there is one.
+fn checkout_options(subdir: Option<&Path>) -> CheckoutBuilder<'static> {
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+    if let Some(subdir) = subdir {
+        checkout.path(subdir);
+    }
+    checkout
+}
+
+/// Checks out `name`: a local branch tracking `origin/<name>` if the remote
+/// has that branch, otherwise the tag `name` with a detached `HEAD`.
+fn check_out_branch_or_tag(
+    repo: &Repository,
+    name: &str,
+    subdir: Option<&Path>,
+) -> std::result::Result<(), git2::Error> {
+    let upstream = format!("origin/{name}");
+    if let Ok(remote) = repo.find_branch(&upstream, BranchType::Remote) {
+        let head = format!("refs/heads/{name}");
+        if repo.head()?.name() == Some(head.as_str()) {
+            return Ok(());
+        }
+        let target = remote.get().peel_to_commit()?;
+        let mut local = repo.branch(name, &target, false)?;
+        local.set_upstream(Some(&upstream))?;
+        repo.checkout_tree(target.as_object(), Some(&mut checkout_options(subdir)))?;
+        return repo.set_head(&head);
+    }
+
+    let tag = repo
+        .find_reference(&format!("refs/tags/{name}"))
+        .map_err(|_| git2::Error::from_str(&format!("remote branch or tag {name:?} not found")))?;
+    check_out_detached(repo, &tag.peel_to_commit()?, subdir)
+}
+
+/// Checks out `target`'s tree and points a detached `HEAD` at it.
+fn check_out_detached(
+    repo: &Repository,
+    target: &Commit<'_>,
+    subdir: Option<&Path>,
+) -> std::result::Result<(), git2::Error> {
+    repo.checkout_tree(target.as_object(), Some(&mut checkout_options(subdir)))?;
+    repo.set_head_detached(target.id())
+}
+
+/// Maps a libgit2 error from `operation` to [`FyaiError::NativeGit`].
+fn failed(operation: &'static str) -> impl FnOnce(git2::Error) -> FyaiError {
+    move |source| FyaiError::NativeGit { operation, source }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::TempDir;
+
+    /// Runs `git <args>` in `dir`, asserting it succeeds.
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.email=test@test.com", "-c", "user.name=Test"])
+            .args(args)
+            .status()
+            .expect("git should run");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    /// A repo on `main` with `a.txt` at "v1", tagged `v1`, then "v2", plus a
+    /// `feature` branch off "v2" that adds `feature.txt`.
+    fn source_repo() -> TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path();
+        git(path, &["init", "-q", "-b", "main"]);
+        fs::write(path.join("a.txt"), "v1").expect("write");
+        git(path, &["add", "."]);
+        git(path, &["commit", "-q", "-m", "v1"]);
+        git(path, &["tag", "v1"]);
+        fs::write(path.join("a.txt"), "v2").expect("write");
+        git(path, &["commit", "-q", "-am", "v2"]);
+        git(path, &["checkout", "-q", "-b", "feature"]);
+        fs::write(path.join("feature.txt"), "feature").expect("write");
+        git(path, &["add", "."]);
+        git(path, &["commit", "-q", "-m", "feature"]);
+        git(path, &["checkout", "-q", "main"]);
+        dir
+    }
+
+    fn clone(source: &Path, branch: Option<&str>, commit: Option<&str>) -> (TempDir, Result<()>) {
+        let target = tempfile::tempdir().expect("tempdir");
+        let url = format!("file://{}", source.display());
+        let result = clone_repository(
+            &url,
+            branch,
+            commit,
+            None,
+            false,
+            &target.path().join("repo"),
+        );
+        (target, result)
+    }
+
+    fn read(clone: &TempDir, name: &str) -> Option<String> {
+        fs::read_to_string(clone.path().join("repo").join(name)).ok()
+    }
+
+    #[test]
+    fn supports_file_urls_and_existing_directories_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(supports("file:///srv/repo.git"));
+        assert!(supports(dir.path().to_str().unwrap()));
+        assert!(!supports("https://github.com/owner/repo"));
+        assert!(!supports("git@github.com:owner/repo.git"));
+        assert!(!supports("/nonexistent/path/that/does/not/exist"));
+    }
+
+    #[test]
+    fn clones_the_default_branch_with_its_history() {
+        let source = source_repo();
+        let (clone, result) = clone(source.path(), None, None);
+        result.expect("clone should succeed");
+
+        assert_eq!(read(&clone, "a.txt").as_deref(), Some("v2"));
+        assert_eq!(read(&clone, "feature.txt"), None);
+        let repo = Repository::open(clone.path().join("repo")).unwrap();
+        assert!(!repo.is_shallow());
+        assert!(repo.revparse_single("HEAD~1").is_ok());
+    }
+
+    #[test]
+    fn clones_a_local_path_too() {
+        let source = source_repo();
+        let target = tempfile::tempdir().expect("tempdir");
+        clone_repository(
+            source.path().to_str().unwrap(),
+            None,
+            None,
+            None,
+            false,
+            &target.path().join("repo"),
+        )
+        .expect("clone should succeed");
+        assert_eq!(read(&target, "a.txt").as_deref(), Some("v2"));
+    }
+
+    #[test]
+    fn a_subdir_limits_what_every_checkout_writes() {
+        let source = source_repo();
+        fs::create_dir(source.path().join("sub")).expect("mkdir");
+        fs::write(source.path().join("sub").join("inner.txt"), "inner").expect("write");
+        git(source.path(), &["checkout", "-q", "feature"]);
+        git(source.path(), &["add", "."]);
+        git(source.path(), &["commit", "-q", "-m", "add sub"]);
+        git(source.path(), &["checkout", "-q", "main"]);
+
+        let target = tempfile::tempdir().expect("tempdir");
+        let url = format!("file://{}", source.path().display());
+        clone_repository(
+            &url,
+            Some("feature"),
+            None,
+            Some(Path::new("sub")),
+            false,
+            &target.path().join("repo"),
+        )
+        .expect("clone should succeed");
+
+        assert_eq!(read(&target, "sub/inner.txt").as_deref(), Some("inner"));
+        assert_eq!(read(&target, "a.txt"), None);
+        assert_eq!(read(&target, "feature.txt"), None);
+    }
+
+    #[test]
+    fn submodules_are_cloned_recursively_when_asked() {
+        let dep = source_repo();
+        let source = source_repo();
+        let dep_url = dep.path().to_str().unwrap();
+        git(
+            source.path(),
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                dep_url,
+                "dep",
+            ],
+        );
+        git(source.path(), &["commit", "-q", "-m", "add dep"]);
+
+        let (clone_without, result) = clone(source.path(), None, None);
+        result.expect("clone should succeed");
+        assert_eq!(read(&clone_without, "dep/a.txt"), None);
+
+        let target = tempfile::tempdir().expect("tempdir");
+        let url = format!("file://{}", source.path().display());
+        clone_repository(&url, None, None, None, true, &target.path().join("repo"))
+            .expect("clone should succeed");
+        assert_eq!(read(&target, "dep/a.txt").as_deref(), Some("v2"));
+    }
+
+    #[test]
+    fn checks_out_a_branch_as_a_tracking_local_branch() {
+        let source = source_repo();
+        let (clone, result) = clone(source.path(), Some("feature"), None);
+        result.expect("clone should succeed");
+
+        assert_eq!(read(&clone, "feature.txt").as_deref(), Some("feature"));
+        let repo = Repository::open(clone.path().join("repo")).unwrap();
+        assert_eq!(repo.head().unwrap().name(), Some("refs/heads/feature"));
+        let local = repo.find_branch("feature", BranchType::Local).unwrap();
+        assert_eq!(
+            local.upstream().unwrap().name().unwrap(),
+            Some("origin/feature")
+        );
+    }
+
+    #[test]
+    fn checking_out_the_default_branch_by_name_is_a_no_op() {
+        let source = source_repo();
+        let (clone, result) = clone(source.path(), Some("main"), None);
+        result.expect("clone should succeed");
+        assert_eq!(read(&clone, "a.txt").as_deref(), Some("v2"));
+    }
+
+    #[test]
+    fn checks_out_a_tag_detached() {
+        let source = source_repo();
+        let (clone, result) = clone(source.path(), Some("v1"), None);
+        result.expect("clone should succeed");
+
+        assert_eq!(read(&clone, "a.txt").as_deref(), Some("v1"));
+        let repo = Repository::open(clone.path().join("repo")).unwrap();
+        assert!(repo.head_detached().unwrap());
+    }
+
+    #[test]
+    fn checks_out_a_commit() {
+        let source = source_repo();
+        let sha = Repository::open(source.path())
+            .unwrap()
+            .revparse_single("v1")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+
+        let (clone, result) = clone(source.path(), None, Some(&sha));
+        result.expect("clone should succeed");
+        assert_eq!(read(&clone, "a.txt").as_deref(), Some("v1"));
+    }
+
+    #[test]
+    fn unknown_branch_is_a_checkout_error() {
+        let source = source_repo();
+        let (_clone, result) = clone(source.path(), Some("nope"), None);
+
+        match result {
+            Err(FyaiError::NativeGit { operation, source }) => {
+                assert_eq!(operation, "checkout");
+                assert!(source.message().contains("\"nope\" not found"));
+            }
+            other => panic!("expected Err(FyaiError::NativeGit {{ .. }}), got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_commit_is_a_checkout_error() {
+        let source = source_repo();
+        let bogus_sha = "0".repeat(40);
+        let (_clone, result) = clone(source.path(), None, Some(&bogus_sha));
+
+        match result {
+            Err(error @ FyaiError::NativeGit { .. }) => {
+                assert!(error.to_string().starts_with("git checkout failed: "));
+            }
+            other => panic!("expected Err(FyaiError::NativeGit {{ .. }}), got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn missing_repository_is_a_clone_error() {
+        let missing = tempfile::tempdir().expect("tempdir");
+        let (_clone, result) = clone(&missing.path().join("gone"), None, None);
+
+        assert!(matches!(
+            result,
+            Err(FyaiError::NativeGit {
+                operation: "clone",
+                ..
+            })
+        ));
+    }
+}
@@ -3,30 +3,65 @@
 //! contents. Also handles cloning a remote git repository into a temporary
 //! directory before running the same combine logic against it.
 
-use std::path::PathBuf;
-use std::process::{self, Command};
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
 
 use tempfile::TempDir;
 
+use crate::cache::{CloneCache, DEFAULT_MAX_SIZE};
 use crate::config::Config;
 use crate::error::{FyaiError, Result};
-use crate::scanner::{ScanStats, scan};
+use crate::git::{Worktree, command_error_details, git_output};
+use crate::scanner::{ScanStats, scan, scan_to_writer};
 
 /// Combines files from a local directory as described by `config`.
 ///
 /// Writes the result to `config.output` (either the directory tree only, or
-/// the tree plus file contents, depending on `config.tree_only`), and
-/// returns a byte breakdown of every file the walk collected.
+/// the tree plus file contents, depending on `config.tree_only`; stdout when
+/// `config.output` is `-`), and returns a byte breakdown of every file the
+/// walk collected.
+///
+/// With `config.rev`, the directory is scanned as it was at that revision
+/// instead, from a temporary git worktree. That needs a single directory:
+/// `config.rev` with `config.extra_directories` fails with an
+/// [`io::ErrorKind::InvalidInput`] error.
 pub fn run_local(config: Config) -> Result<ScanStats> {
-    let stats = scan(&config)?;
-    Ok(stats)
+    with_revision(config, |config| scan(&config))
+}
+
+/// Like [`run_local`], but writes the result to `output` instead of
+/// `config.output`. See [`scan_to_writer`] for how `output` is used.
+pub fn run_local_to_writer<W: Write>(config: Config, output: &mut W) -> Result<ScanStats> {
+    with_revision(config, |config| scan_to_writer(&config, output))
+}
+
+/// Checks `config.rev` (if set) out into a temporary git worktree, points
+/// `config.directory` at the same directory inside it, and calls `run`
+/// with it, removing the worktree afterwards. The working copy, its index,
+/// and its uncommitted changes are left alone.
+fn with_revision<T>(mut config: Config, run: impl FnOnce(Config) -> Result<T>) -> Result<T> {
+    let Some(rev) = config.rev.take() else {
+        return run(config);
+    };
+    single_directory(&config, "rev")?;
+    let (worktree, directory) = Worktree::at_revision(&config.directory, &rev)?;
+    config.directory = directory;
+
+    let result = run(config);
+    drop(worktree);
+    result
 }
 
 /// Clones `repo_url` into a temporary directory, then runs the same combine
 /// logic as [`run_local`] against the clone.
 ///
 /// The temporary clone is removed before this function returns, regardless
-/// of the run's outcome.
+/// of the run's outcome. With `config.cache`, the clone is kept in the
+/// system [`CloneCache`] instead and reused by later runs of the same URL,
+/// and once the run succeeds, the least recently used clones are evicted
+/// down to `config.cache_max_size`.
 ///
 /// # Arguments
 ///
@@ -35,28 +70,164 @@ pub fn run_local(config: Config) -> Result<ScanStats> {
 ///   the clone is not shallow, since the target commit may not be reachable
 ///   from a depth-1 clone.
 /// * `config` - the combine configuration; `config.directory` is overwritten
-///   with the path to the cloned repository.
+///   with the path to the cloned repository, or to `config.repo_path`
+///   inside it. When `config.changed_since` or `config.diff` is set, the
+///   clone is not shallow either, so the refs they name (and their merge
+///   base with the checked-out branch) are available;
+///   likewise for `config.git_log`, whose commits touching the scanned
+///   directory can be arbitrarily far back, and `config.rev`, which is
+///   checked out from the clone. `config.extra_directories` must be empty,
+///   or the run fails with an [`io::ErrorKind::InvalidInput`] error.
 pub fn run_git(
     repo_url: &str,
     branch: Option<&str>,
     commit: Option<&str>,
     config: Config,
 ) -> Result<ScanStats> {
-    let (temp_dir, clone_path) = clone_repository(repo_url, branch, commit)?;
+    with_clone(repo_url, branch, commit, config, run_local)
+}
+
+/// Like [`run_git`], but writes the result to `output` instead of
+/// `config.output`, as [`run_local_to_writer`] does.
+pub fn run_git_to_writer<W: Write>(
+    repo_url: &str,
+    branch: Option<&str>,
+    commit: Option<&str>,
+    config: Config,
+    output: &mut W,
+) -> Result<ScanStats> {
+    with_clone(repo_url, branch, commit, config, |config| {
+        run_local_to_writer(config, output)
+    })
+}
+
+/// Clones `repo_url` (see [`clone_repository`]), or checks it out from the
+/// clone cache with `config.cache`, points `config.directory` at the clone
+/// (or at `config.repo_path` inside it), and calls `run` with it, removing
+/// a temporary clone afterwards.
+fn with_clone<T>(
+    repo_url: &str,
+    branch: Option<&str>,
+    commit: Option<&str>,
+    config: Config,
+    run: impl FnOnce(Config) -> Result<T>,
+) -> Result<T> {
+    single_directory(&config, "repo")?;
+    let subdir = config.repo_path.as_deref();
+    if let Some(subdir) = subdir {
+        check_repo_path(subdir)?;
+    }
+    let cache = if config.cache {
+        Some(CloneCache::system().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not determine the system cache directory",
+            )
+        })?)
+    } else {
+        None
+    };
+    let (temp_dir, cache_lock, clone_path) = match &cache {
+        Some(cache) => {
+            let (lock, clone_path) =
+                cache.check_out(repo_url, branch, commit, subdir, config.submodules)?;
+            (None, Some(lock), clone_path)
+        }
+        None => {
+            let full_history = commit.is_some()
+                || config.changed_since.is_some()
+                || config.diff.is_some()
+                || config.git_log.is_some()
+                || config.rev.is_some();
+            let (temp_dir, clone_path) = clone_repository(
+                repo_url,
+                branch,
+                commit,
+                full_history,
+                subdir,
+                config.submodules,
+            )?;
+            (Some(temp_dir), None, clone_path)
+        }
+    };
 
+    let directory = match subdir {
+        Some(subdir) => {
+            let directory = clone_path.join(subdir);
+            if !directory.is_dir() {
+                return Err(FyaiError::Git(format!(
+                    "{} does not exist in the repository",
+                    subdir.display()
+                )));
+            }
+            directory
+        }
+        None => clone_path,
+    };
+    let max_size = config.cache_max_size.unwrap_or(DEFAULT_MAX_SIZE);
     let mut config = config;
-    config.directory = clone_path;
+    config.directory = directory;
 
-    let result = run_local(config);
+    let result = run(config);
     drop(temp_dir);
+    drop(cache_lock);
+    // Eviction only tidies up after a run that already succeeded, so a
+    // failure to evict (say, a clone another process is removing) doesn't
+    // fail the run.
+    if let (Some(cache), Ok(_)) = (&cache, &result)
+        && let Err(err) = cache.evict(max_size, repo_url)
+    {
+        eprintln!("Warning: failed to evict old clones from the cache: {err}");
+    }
     result
 }
 
+/// Rejects `config.extra_directories` for `key`, an option that replaces
+/// `config.directory` with a single checkout.
+fn single_directory(config: &Config, key: &str) -> Result<()> {
+    if config.extra_directories.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{key} needs a single input directory, not several"),
+    )
+    .into())
+}
+
+/// Rejects a `repo_path` that could point outside the clone: it must be
+/// relative, without `..` components.
+fn check_repo_path(subdir: &Path) -> Result<()> {
+    let inside = subdir
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if inside && subdir.components().next().is_some() {
+        return Ok(());
+    }
+    Err(FyaiError::InvalidValue {
+        key: "repo_path",
+        value: subdir.display().to_string(),
+    })
+}
+
 /// Clones `repo_url` into a fresh temporary directory, optionally checking
 /// out `branch` and/or `commit`.
 ///
-/// The clone is shallow (`--depth 1`) unless `commit` is set, since the
-/// target commit may not be reachable from a depth-1 history.
+/// The clone is shallow (`--depth 1`) unless `full_history` is set, which
+/// callers do when they need more than the tip commit (a pinned `commit`
+/// may not be reachable from a depth-1 history).
+///
+/// With a `subdir`, only that subtree (plus the files at the repository
+/// root) is checked out, via a cone-mode sparse checkout, and file contents
+/// outside it aren't downloaded where the server supports partial clones.
+///
+/// With `submodules`, the submodules (under `subdir`, if set) are checked
+/// out recursively at the commits the checkout pins, shallowly unless
+/// `full_history` is set.
+///
+/// With the `native-git` feature, local paths and `file://` URLs are cloned
+/// in-process (always with full history); everything else goes through the
+/// `git` command line.
 ///
 /// Returns the [`TempDir`] guard (drop it to delete the clone) alongside the
 /// path to the checked-out repository.
@@ -64,15 +235,34 @@ fn clone_repository(
     repo_url: &str,
     branch: Option<&str>,
     commit: Option<&str>,
+    full_history: bool,
+    subdir: Option<&Path>,
+    submodules: bool,
 ) -> Result<(TempDir, PathBuf)> {
     let temp_dir = tempfile::tempdir()?;
     let clone_path = temp_dir.path().join("repo");
 
+    #[cfg(feature = "native-git")]
+    if crate::native_git::supports(repo_url) {
+        crate::native_git::clone_repository(
+            repo_url,
+            branch,
+            commit,
+            subdir,
+            submodules,
+            &clone_path,
+        )?;
+        return Ok((temp_dir, clone_path));
+    }
+
     let mut cmd = Command::new("git");
     cmd.arg("clone");
-    if commit.is_none() {
+    if !full_history {
         cmd.arg("--depth").arg("1");
     }
+    if subdir.is_some() {
+        cmd.args(["--filter=blob:none", "--sparse"]);
+    }
     if let Some(branch) = branch {
         cmd.args(["--branch", branch]);
     }
@@ -88,74 +278,52 @@ fn clone_repository(
         )));
     }
 
+    if let Some(subdir) = subdir {
+        git_output(
+            &clone_path,
+            [
+                OsStr::new("sparse-checkout"),
+                OsStr::new("set"),
+                OsStr::new("--cone"),
+                subdir.as_os_str(),
+            ],
+            "git sparse-checkout",
+        )?;
+    }
     if let Some(commit) = commit {
-        let output = Command::new("git")
-            .arg("-C")
-            .arg(&clone_path)
-            .args(["checkout", commit])
-            .output()
-            .map_err(|e| FyaiError::Git(format!("failed to run git checkout: {e}")))?;
-        if !output.status.success() {
-            return Err(FyaiError::Git(format!(
-                "git checkout failed: {}",
-                command_error_details(&output)
-            )));
+        git_output(&clone_path, ["checkout", commit], "git checkout")?;
+    }
+    if submodules {
+        let mut args: Vec<&OsStr> = ["submodule", "update", "--init", "--recursive"]
+            .into_iter()
+            .map(OsStr::new)
+            .collect();
+        if !full_history {
+            args.extend([OsStr::new("--depth"), OsStr::new("1")]);
         }
+        if let Some(subdir) = subdir {
+            args.extend([OsStr::new("--"), subdir.as_os_str()]);
+        }
+        git_output(&clone_path, args, "git submodule update")?;
     }
 
     Ok((temp_dir, clone_path))
 }
 
-/// Extracts a human-readable error message from a failed command's output,
-/// preferring stderr over stdout, and falling back to a generic message if
-/// both are empty.
-fn command_error_details(output: &process::Output) -> String {
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    let details = if !stderr.trim().is_empty() {
-        stderr.trim()
-    } else {
-        stdout.trim()
-    };
-
-    if details.is_empty() {
-        "unknown error".to_string()
-    } else {
-        details.to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
-    use std::path::Path;
 
     // ---- test helpers ----
 
     /// Builds a minimal [`Config`] pointing at `directory`/`output`, with
     /// every optional filter unset and all booleans at their usual default.
     fn test_config(directory: PathBuf, output: PathBuf) -> Config {
-        Config {
-            directory,
-            output,
-            include_dirs: None,
-            exclude_dirs: None,
-            include_ext: None,
-            exclude_ext: None,
-            include_files: None,
-            exclude_files: None,
-            min_size: None,
-            max_size: None,
-            hidden: true,
-            gitignore: true,
-            ignore_files: true,
-            git_global: true,
-            follow_links: false,
-            tree_only: false,
-            human: false,
-        }
+        Config::builder()
+            .directory(directory)
+            .output(output)
+            .build()
     }
 
     /// Runs `git <args>` with `-C repo_path`, asserting it succeeds, and
@@ -266,8 +434,105 @@ mod tests {
         assert!(matches!(result, Err(FyaiError::Io(_))));
     }
 
+    #[test]
+    fn run_local_and_run_git_reject_several_roots_for_one_checkout() {
+        let first = tempfile::tempdir().expect("tempdir");
+        let second = tempfile::tempdir().expect("tempdir");
+        let config = Config::builder()
+            .directories([first.path(), second.path()])
+            .rev("HEAD")
+            .build();
+
+        let err = run_local(config.clone()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "rev needs a single input directory, not several"
+        );
+        let err = run_git("https://example.com/x.git", None, None, config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "repo needs a single input directory, not several"
+        );
+    }
+
+    #[test]
+    fn run_local_with_rev_scans_the_revision_with_its_own_ignore_files() {
+        let repo_dir = init_git_repo();
+        let repo_path = repo_dir.path();
+        fs::write(repo_path.join(".fyaiignore"), "secret.txt\n").expect("write .fyaiignore");
+        fs::write(repo_path.join("secret.txt"), "old secret").expect("write secret.txt");
+        run_git_cmd(repo_path, &["add", "."]);
+        run_git_cmd(
+            repo_path,
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "-m",
+                "ignore secret",
+            ],
+        );
+        run_git_cmd(repo_path, &["tag", "v1"]);
+        fs::remove_file(repo_path.join(".fyaiignore")).expect("remove .fyaiignore");
+        fs::write(repo_path.join("file1.txt"), "uncommitted edit").expect("write file1");
+
+        let mut config = test_config(repo_path.to_path_buf(), PathBuf::from("combined.txt"));
+        config.rev = Some("v1".to_string());
+        let mut buffer = Vec::new();
+        run_local_to_writer(config, &mut buffer).expect("run should succeed");
+
+        let contents = String::from_utf8(buffer).unwrap();
+        assert!(contents.contains("hello from file1"));
+        assert!(!contents.contains("uncommitted edit"));
+        assert!(!contents.contains("old secret"));
+        assert_eq!(
+            fs::read_to_string(repo_path.join("file1.txt")).unwrap(),
+            "uncommitted edit"
+        );
+    }
+
+    #[test]
+    fn run_local_to_writer_writes_into_the_writer_only() {
+        let source_dir = tempfile::tempdir().expect("tempdir");
+        fs::write(source_dir.path().join("a.txt"), "content of a").expect("write a.txt");
+
+        let out_dir = tempfile::tempdir().expect("tempdir");
+        let output_path = out_dir.path().join("combined.txt");
+
+        let config = test_config(source_dir.path().to_path_buf(), output_path.clone());
+
+        let mut buffer = Vec::new();
+        let stats = run_local_to_writer(config, &mut buffer).expect("run should succeed");
+
+        assert_eq!(stats.written_size, 12);
+        assert!(!output_path.exists());
+        assert!(String::from_utf8(buffer).unwrap().contains("content of a"));
+    }
+
     // ---- run_git: success paths ----
 
+    #[test]
+    fn run_git_to_writer_writes_the_clone_into_the_writer() {
+        let repo_dir = init_git_repo();
+        let repo_path = repo_dir.path().to_str().expect("utf8 path").to_string();
+        let placeholder_dir = tempfile::tempdir().expect("tempdir");
+
+        let config = test_config(
+            placeholder_dir.path().to_path_buf(),
+            PathBuf::from("combined.txt"),
+        );
+
+        let mut buffer = Vec::new();
+        run_git_to_writer(&repo_path, None, None, config, &mut buffer).expect("run should succeed");
+
+        let contents = String::from_utf8(buffer).unwrap();
+        assert!(contents.contains("file1.txt"));
+        assert!(contents.contains("hello from file1"));
+    }
+
     #[test]
     fn run_git_clones_and_scans_default_branch() {
         let repo_dir = init_git_repo();
@@ -375,8 +640,172 @@ mod tests {
         );
     }
 
+    #[test]
+    fn run_git_with_changed_since_keeps_only_the_branch_changes() {
+        let repo_dir = init_git_repo();
+        let repo_path = repo_dir.path();
+        let default_branch = run_git_cmd(repo_path, &["rev-parse", "--abbrev-ref", "HEAD"]);
+
+        run_git_cmd(repo_path, &["checkout", "-q", "-b", "feature"]);
+        fs::write(repo_path.join("feature.txt"), "feature work").expect("write feature.txt");
+        run_git_cmd(repo_path, &["add", "."]);
+        run_git_cmd(
+            repo_path,
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "-m",
+                "feature commit",
+            ],
+        );
+
+        let repo_url = repo_path.to_str().expect("utf8 path").to_string();
+        let placeholder_dir = tempfile::tempdir().expect("tempdir");
+        let mut config = test_config(
+            placeholder_dir.path().to_path_buf(),
+            PathBuf::from("combined.txt"),
+        );
+        // Only `feature` is checked out in the clone, so this resolves
+        // through `origin/<default branch>`.
+        config.changed_since = Some(default_branch);
+
+        let mut buffer = Vec::new();
+        run_git_to_writer(&repo_url, Some("feature"), None, config, &mut buffer)
+            .expect("run should succeed");
+
+        let contents = String::from_utf8(buffer).unwrap();
+        assert!(contents.contains("feature work"));
+        assert!(!contents.contains("file1.txt"));
+    }
+
+    #[test]
+    fn run_git_with_git_log_clones_enough_history() {
+        let repo_dir = init_git_repo();
+        let repo_path = repo_dir.path();
+        fs::write(repo_path.join("file2.txt"), "hello from file2").expect("write file2");
+        run_git_cmd(repo_path, &["add", "."]);
+        run_git_cmd(
+            repo_path,
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "-m",
+                "second commit",
+            ],
+        );
+
+        // A `file://` URL, since git ignores `--depth` for plain local paths.
+        let repo_url = format!("file://{}", repo_path.display());
+        let placeholder_dir = tempfile::tempdir().expect("tempdir");
+        let mut config = test_config(
+            placeholder_dir.path().to_path_buf(),
+            PathBuf::from("combined.txt"),
+        );
+        config.git_log = Some(5);
+
+        let mut buffer = Vec::new();
+        run_git_to_writer(&repo_url, None, None, config, &mut buffer).expect("run should succeed");
+
+        let contents = String::from_utf8(buffer).unwrap();
+        assert!(contents.contains("second commit"));
+        assert!(contents.contains("initial commit"));
+    }
+
+    #[test]
+    fn run_git_with_repo_path_scans_only_that_subtree() {
+        let repo_dir = init_git_repo();
+        let repo_path = repo_dir.path();
+        fs::create_dir_all(repo_path.join("packages/api")).expect("mkdir api");
+        fs::create_dir_all(repo_path.join("packages/web")).expect("mkdir web");
+        fs::write(repo_path.join("packages/api/server.rs"), "fn serve() {}").expect("write");
+        fs::write(repo_path.join("packages/web/app.js"), "render()").expect("write");
+        run_git_cmd(repo_path, &["add", "."]);
+        run_git_cmd(
+            repo_path,
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "-m",
+                "add packages",
+            ],
+        );
+
+        let repo_url = format!("file://{}", repo_path.display());
+        let placeholder_dir = tempfile::tempdir().expect("tempdir");
+        let mut config = test_config(
+            placeholder_dir.path().to_path_buf(),
+            PathBuf::from("combined.txt"),
+        );
+        config.repo_path = Some(PathBuf::from("packages/api"));
+
+        let mut buffer = Vec::new();
+        run_git_to_writer(&repo_url, None, None, config, &mut buffer).expect("run should succeed");
+
+        let contents = String::from_utf8(buffer).unwrap();
+        assert!(contents.contains("api/\n  server.rs\n"));
+        assert!(contents.contains("### server.rs"));
+        assert!(!contents.contains("app.js"));
+        assert!(!contents.contains("file1.txt"));
+    }
+
     // ---- run_git: failure paths ----
 
+    #[test]
+    fn run_git_rejects_a_repo_path_outside_the_clone() {
+        for subdir in ["../elsewhere", "/etc", ""] {
+            let placeholder_dir = tempfile::tempdir().expect("tempdir");
+            let mut config = test_config(
+                placeholder_dir.path().to_path_buf(),
+                PathBuf::from("combined.txt"),
+            );
+            config.repo_path = Some(PathBuf::from(subdir));
+
+            let result = run_git_to_writer("unused", None, None, config, &mut Vec::new());
+            assert!(
+                matches!(
+                    result,
+                    Err(FyaiError::InvalidValue {
+                        key: "repo_path",
+                        ..
+                    })
+                ),
+                "{subdir:?}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn run_git_with_a_missing_repo_path_fails() {
+        let repo_dir = init_git_repo();
+        let repo_url = repo_dir.path().to_str().expect("utf8 path").to_string();
+        let placeholder_dir = tempfile::tempdir().expect("tempdir");
+        let mut config = test_config(
+            placeholder_dir.path().to_path_buf(),
+            PathBuf::from("combined.txt"),
+        );
+        config.repo_path = Some(PathBuf::from("no/such/dir"));
+
+        let result = run_git_to_writer(&repo_url, None, None, config, &mut Vec::new());
+        match result {
+            Err(FyaiError::Git(msg)) => {
+                assert_eq!(msg, "no/such/dir does not exist in the repository");
+            }
+            other => panic!("expected Err(FyaiError::Git(_)), got {other:?}"),
+        }
+    }
+
     #[test]
     fn run_git_invalid_repo_url_returns_git_error() {
         let out_dir = tempfile::tempdir().expect("tempdir");
@@ -397,6 +826,8 @@ mod tests {
         }
     }
 
+    // The native backend reports this as `FyaiError::NativeGit` instead.
+    #[cfg(not(feature = "native-git"))]
     #[test]
     fn run_git_invalid_commit_returns_git_error() {
         let repo_dir = init_git_repo();
@@ -436,66 +867,4 @@ mod tests {
         let result = run_git(&repo_path, None, None, config);
         assert!(matches!(result, Err(FyaiError::Io(_))));
     }
-
-    // ---- command_error_details ----
-
-    /// Returns a real `ExitStatus` representing a failed process, so tests
-    /// can build a custom `Output` around it (`ExitStatus` has no public
-    /// constructor).
-    fn failing_status() -> process::ExitStatus {
-        Command::new("false")
-            .output()
-            .expect("failed to run `false`")
-            .status
-    }
-
-    #[test]
-    fn command_error_details_prefers_stderr_over_stdout() {
-        let output = process::Output {
-            status: failing_status(),
-            stdout: b"stdout message".to_vec(),
-            stderr: b"stderr message".to_vec(),
-        };
-        assert_eq!(command_error_details(&output), "stderr message");
-    }
-
-    #[test]
-    fn command_error_details_falls_back_to_stdout_when_stderr_empty() {
-        let output = process::Output {
-            status: failing_status(),
-            stdout: b"stdout message".to_vec(),
-            stderr: Vec::new(),
-        };
-        assert_eq!(command_error_details(&output), "stdout message");
-    }
-
-    #[test]
-    fn command_error_details_falls_back_to_stdout_when_stderr_whitespace_only() {
-        let output = process::Output {
-            status: failing_status(),
-            stdout: b"stdout message".to_vec(),
-            stderr: b"   \n\t  ".to_vec(),
-        };
-        assert_eq!(command_error_details(&output), "stdout message");
-    }
-
-    #[test]
-    fn command_error_details_returns_unknown_error_when_both_empty() {
-        let output = process::Output {
-            status: failing_status(),
-            stdout: Vec::new(),
-            stderr: Vec::new(),
-        };
-        assert_eq!(command_error_details(&output), "unknown error");
-    }
-
-    #[test]
-    fn command_error_details_returns_unknown_error_when_both_whitespace_only() {
-        let output = process::Output {
-            status: failing_status(),
-            stdout: b"  \n  ".to_vec(),
-            stderr: b"\t\t".to_vec(),
-        };
-        assert_eq!(command_error_details(&output), "unknown error");
-    }
 }
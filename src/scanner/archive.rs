@@ -0,0 +1,518 @@
+//! Reads a `.zip` or `.tar` archive (optionally gzip-, xz-, or
+//! zstd-compressed) in place of a directory, without extracting it.
+//!
+//! Every member becomes an [`Entry`] at a virtual path, the archive's own
+//! path (`config.directory`) joined with the member's path, so the tree,
+//! the headings, and [`PathFilter`] all see member paths exactly as they
+//! would see a walked directory's. Directories the archive doesn't list
+//! itself are inferred from its members' paths.
+//!
+//! Members are read in one sequential pass, since a compressed tarball can't
+//! be read any other way, keeping in memory only the contents of files the
+//! filters and size bounds let through. Symlinks, hard links, and members
+//! whose path would leave the archive (absolute, or with `..`) are skipped.
+//! Ignore files inside the archive aren't applied, but `config.hidden`
+//! still skips dot-files.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::{FyaiError, Result};
+
+use super::collect::{Contents, Entry};
+use super::filter::PathFilter;
+use super::process::size_allowed;
+
+/// The members of an archive that passed the filters.
+pub(crate) struct Members {
+    /// Files and directories, sorted into the preorder a walk produces.
+    pub entries: Vec<Entry>,
+    /// Contents of the member files within `config.min_size` and
+    /// `config.max_size`; empty unless [`read_members`] was asked for them.
+    pub contents: Contents,
+}
+
+/// Archive layouts, recognized from their leading bytes rather than the
+/// file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+/// One archive member, as [`read_members`] is handed it.
+struct Member<'r> {
+    /// The member's path as stored in the archive.
+    path: PathBuf,
+    is_dir: bool,
+    /// Uncompressed size in bytes.
+    size: u64,
+    /// The member's contents.
+    data: &'r mut dyn Read,
+}
+
+/// Reads the archive at `config.directory`, returning every member the
+/// filters allow and, with `read_contents`, the contents of those files
+/// within the size bounds.
+///
+/// # Errors
+///
+/// Fails with [`FyaiError::Archive`] if the file isn't a supported archive
+/// or can't be decoded.
+pub(crate) fn read_members(config: &Config, read_contents: bool) -> Result<Members> {
+    let archive = &config.directory;
+    let failed = |source: io::Error| FyaiError::Archive {
+        path: archive.clone(),
+        source,
+    };
+
+    let mut file = File::open(archive).map_err(failed)?;
+    let format = detect(&mut file).map_err(failed)?;
+    let filter = PathFilter::new(config)?;
+    let mut members = Collector {
+        config,
+        filter,
+        read_contents,
+        allowed_dirs: HashMap::new(),
+        dirs: HashSet::new(),
+        files: HashMap::new(),
+        contents: Contents::new(),
+    };
+    read_archive(file, format, &mut |member| members.add(member)).map_err(failed)?;
+    Ok(members.finish())
+}
+
+/// Returns `file`'s [`Format`], leaving it positioned at its start.
+fn detect(file: &mut File) -> io::Result<Format> {
+    const XZ: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+    const ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+    let mut header = Vec::with_capacity(512);
+    file.by_ref().take(512).read_to_end(&mut header)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let format = if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        Format::Zip
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Format::TarGz
+    } else if header.starts_with(XZ) {
+        Format::TarXz
+    } else if header.starts_with(ZSTD) {
+        Format::TarZst
+    } else if header.get(257..262) == Some(b"ustar") {
+        Format::Tar
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a directory, or a .zip or .tar(.gz/.xz/.zst) archive",
+        ));
+    };
+    Ok(format)
+}
+
+/// Calls `visit` with every file and directory member of `file`, in
+/// archive order.
+fn read_archive(
+    file: File,
+    format: Format,
+    visit: &mut dyn FnMut(Member<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    let reader = BufReader::new(file);
+    match format {
+        Format::Zip => read_zip(reader, visit),
+        Format::Tar => read_tar(reader, visit),
+        Format::TarGz => read_tar(flate2::read::MultiGzDecoder::new(reader), visit),
+        Format::TarXz => read_tar(lzma_rust2::XzReader::new(reader, true), visit),
+        Format::TarZst => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(reader)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            read_tar(decoder, visit)
+        }
+    }
+}
+
+fn read_tar<R: Read>(
+    reader: R,
+    visit: &mut dyn FnMut(Member<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let size = entry.size();
+        visit(Member {
+            path,
+            is_dir: entry_type.is_dir(),
+            size,
+            data: &mut entry,
+        })?;
+    }
+    Ok(())
+}
+
+fn read_zip<R: Read + Seek>(
+    reader: R,
+    visit: &mut dyn FnMut(Member<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_symlink() {
+            continue;
+        }
+        // `enclosed_name` is `None` for paths that would leave the archive;
+        // `member_path` would reject them anyway.
+        let Some(path) = file.enclosed_name() else {
+            continue;
+        };
+        let is_dir = file.is_dir();
+        let size = file.size();
+        visit(Member {
+            path,
+            is_dir,
+            size,
+            data: &mut file,
+        })?;
+    }
+    Ok(())
+}
+
+/// Accumulates the members [`read_members`] keeps.
+struct Collector<'a> {
+    config: &'a Config,
+    filter: PathFilter<'a>,
+    read_contents: bool,
+    /// Whether each directory seen so far, by virtual path, may be walked
+    /// into: the filters allow it and every directory above it.
+    allowed_dirs: HashMap<PathBuf, bool>,
+    /// Every kept directory, listed or inferred.
+    dirs: HashSet<PathBuf>,
+    /// Every kept file and its size. A path listed twice (which tar allows)
+    /// keeps its last member, as extracting would.
+    files: HashMap<PathBuf, u64>,
+    contents: Contents,
+}
+
+impl Collector<'_> {
+    fn add(&mut self, member: Member<'_>) -> io::Result<()> {
+        let Some(relative) = member_path(&member.path) else {
+            return Ok(());
+        };
+        let path = self.config.directory.join(&relative);
+
+        if member.is_dir {
+            if self.dir_allowed(&path) {
+                self.dirs.insert(path);
+            }
+            return Ok(());
+        }
+
+        let parent_allowed = path
+            .parent()
+            .is_none_or(|parent| parent == self.config.directory || self.dir_allowed(parent));
+        if !parent_allowed || !self.visible(&path) || !self.filter.allows_entry(&path, false) {
+            return Ok(());
+        }
+
+        if self.read_contents
+            && size_allowed(member.size, self.config.min_size, self.config.max_size)
+        {
+            let mut contents = Vec::with_capacity(member.size.min(1 << 24) as usize);
+            member.data.read_to_end(&mut contents)?;
+            self.contents.insert(path.clone(), contents);
+        } else {
+            // Drop whatever an earlier member of the same path left.
+            self.contents.remove(&path);
+        }
+        self.files.insert(path, member.size);
+        Ok(())
+    }
+
+    /// Returns whether the directory at virtual `path` may be walked into,
+    /// as a directory walk would decide it: it and every directory above it
+    /// within the archive must pass the filters.
+    fn dir_allowed(&mut self, path: &Path) -> bool {
+        if path == self.config.directory {
+            return true;
+        }
+        if let Some(&allowed) = self.allowed_dirs.get(path) {
+            return allowed;
+        }
+        let parent_allowed = match path.parent() {
+            Some(parent) => self.dir_allowed(parent),
+            None => true,
+        };
+        let allowed = parent_allowed && self.visible(path) && self.filter.allows_entry(path, true);
+        self.allowed_dirs.insert(path.to_path_buf(), allowed);
+        allowed
+    }
+
+    /// Returns false for a dot-file or dot-directory under `config.hidden`.
+    fn visible(&self, path: &Path) -> bool {
+        !self.config.hidden
+            || !path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'))
+    }
+
+    /// Adds every kept file's directories, then sorts everything into
+    /// preorder.
+    fn finish(mut self) -> Members {
+        let root = &self.config.directory;
+        for path in self.files.keys() {
+            let ancestors = path
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| *ancestor != root.as_path());
+            self.dirs.extend(ancestors.map(Path::to_path_buf));
+        }
+
+        let depth = |path: &Path| {
+            path.strip_prefix(root)
+                .map_or(0, |relative| relative.components().count())
+        };
+        let mut entries: Vec<Entry> = self
+            .dirs
+            .into_iter()
+            .map(|path| Entry {
+                depth: depth(&path),
+                path,
+                is_dir: true,
+                size: None,
+            })
+            .chain(self.files.into_iter().map(|(path, size)| Entry {
+                depth: depth(&path),
+                path,
+                is_dir: false,
+                size: Some(size),
+            }))
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Members {
+            entries,
+            contents: self.contents,
+        }
+    }
+}
+
+/// Returns `path` as a relative path of plain names, dropping `.`
+/// components, or `None` if it's empty, absolute, or climbs out with `..`.
+fn member_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+
+    /// The members every fixture archive holds: a nested file, a dot-file,
+    /// and a file under a dot-directory, with no directory members.
+    const FILES: &[(&str, &[u8])] = &[
+        ("project/src/main.rs", b"fn main() {}\n"),
+        ("project/README.md", b"# Project\n"),
+        ("project/.env", b"SECRET=1\n"),
+        ("project/.cache/blob.txt", b"cached\n"),
+    ];
+
+    fn tar_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, data) in files {
+            writer
+                .start_file(*path, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Writes `bytes` to `name` in `dir`, returning a [`Config`] scanning it.
+    fn archive_config(dir: &Path, name: &str, bytes: &[u8]) -> Config {
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        Config::builder().directory(path).build()
+    }
+
+    fn relative_paths(config: &Config, members: &Members) -> Vec<String> {
+        members
+            .entries
+            .iter()
+            .map(|entry| {
+                let relative = entry.path.strip_prefix(&config.directory).unwrap();
+                let name = relative.to_string_lossy().replace('\\', "/");
+                if entry.is_dir {
+                    format!("{name}/")
+                } else {
+                    name
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_every_format_into_the_same_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let tar = tar_bytes(FILES);
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+
+        let mut xz =
+            lzma_rust2::XzWriter::new(Vec::new(), lzma_rust2::XzOptions::with_preset(1)).unwrap();
+        xz.write_all(&tar).unwrap();
+        let xz = xz.finish().unwrap();
+
+        let zst = ruzstd::encoding::compress_to_vec(
+            tar.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+
+        let fixtures = [
+            ("src.zip", zip_bytes(FILES)),
+            ("src.tar", tar),
+            ("src.tar.gz", gz),
+            ("src.tar.xz", xz),
+            ("src.tar.zst", zst),
+        ];
+        for (name, bytes) in fixtures {
+            let config = archive_config(dir.path(), name, &bytes);
+            let members = read_members(&config, true).unwrap();
+
+            // Hidden files are skipped by default, and `project/` and
+            // `project/src/` are inferred from the files under them.
+            assert_eq!(
+                relative_paths(&config, &members),
+                [
+                    "project/",
+                    "project/README.md",
+                    "project/src/",
+                    "project/src/main.rs"
+                ],
+                "{name}"
+            );
+            let main = config.directory.join("project/src/main.rs");
+            assert_eq!(members.contents[&main], b"fn main() {}\n", "{name}");
+            let depths: Vec<usize> = members.entries.iter().map(|entry| entry.depth).collect();
+            assert_eq!(depths, [1, 2, 2, 3], "{name}");
+        }
+    }
+
+    #[test]
+    fn applies_the_path_filters_and_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let bytes = tar_bytes(FILES);
+
+        let mut config = archive_config(dir.path(), "src.tar", &bytes);
+        config.exclude_dirs = Some(vec!["src".to_string()]);
+        let members = read_members(&config, false).unwrap();
+        assert_eq!(
+            relative_paths(&config, &members),
+            ["project/", "project/README.md"]
+        );
+
+        config.exclude_dirs = None;
+        config.hidden = false;
+        config.include_ext = Some(vec!["txt".to_string()]);
+        let members = read_members(&config, false).unwrap();
+        assert_eq!(
+            relative_paths(&config, &members),
+            ["project/", "project/.cache/", "project/.cache/blob.txt"]
+        );
+    }
+
+    #[test]
+    fn reads_contents_only_when_asked_and_within_the_size_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let bytes = zip_bytes(FILES);
+
+        let mut config = archive_config(dir.path(), "src.zip", &bytes);
+        assert!(read_members(&config, false).unwrap().contents.is_empty());
+
+        // README.md is 10 bytes, main.rs 13: only README.md fits.
+        config.max_size = Some(12);
+        let members = read_members(&config, true).unwrap();
+        let read: Vec<&PathBuf> = members.contents.keys().collect();
+        assert_eq!(read, [&config.directory.join("project/README.md")]);
+        // The file past the bound is still listed, as it would be on disk.
+        assert_eq!(members.entries.len(), 4);
+    }
+
+    #[test]
+    fn a_path_listed_twice_keeps_its_last_member() {
+        let dir = tempfile::tempdir().unwrap();
+        let bytes = tar_bytes(&[("notes.txt", b"old"), ("notes.txt", b"newer")]);
+        let mut config = archive_config(dir.path(), "src.tar", &bytes);
+        let notes = config.directory.join("notes.txt");
+
+        let members = read_members(&config, true).unwrap();
+        assert_eq!(members.contents[&notes], b"newer");
+        assert_eq!(members.entries.len(), 1);
+        assert_eq!(members.entries[0].size, Some(5));
+
+        // The last member is past the bound, so the first one's contents
+        // mustn't stand in for it.
+        config.max_size = Some(4);
+        let members = read_members(&config, true).unwrap();
+        assert!(members.contents.is_empty());
+        assert_eq!(members.entries[0].size, Some(5));
+    }
+
+    #[test]
+    fn rejects_a_file_that_is_not_an_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = archive_config(dir.path(), "notes.txt", b"just some text\n");
+
+        let err = read_members(&config, true).err().unwrap();
+        assert!(matches!(
+            &err,
+            FyaiError::Archive { path, source }
+                if *path == config.directory && source.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn member_path_keeps_members_inside_the_archive() {
+        assert_eq!(
+            member_path(Path::new("./a/./b.txt")),
+            Some(PathBuf::from("a/b.txt"))
+        );
+        assert_eq!(member_path(Path::new("a/../../b.txt")), None);
+        assert_eq!(member_path(Path::new("/etc/passwd")), None);
+        assert_eq!(member_path(Path::new("./")), None);
+    }
+}
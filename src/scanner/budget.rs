@@ -0,0 +1,334 @@
+//! Fits a run under `config.max_tokens`: files are admitted in
+//! `config.budget_order` until the next one would overspend the budget, and
+//! every file after that is left out of the output and marked `[omitted]`
+//! in the tree instead.
+
+use std::cmp::Reverse;
+use std::fs;
+
+use crate::config::{BudgetOrder, Config};
+
+use super::document::FileBlock;
+use super::tree::{RootTree, TreeMarks, render_trees};
+
+/// Tree label for files dropped to stay within the budget.
+const OMITTED: &str = "omitted";
+
+/// The outcome of [`fit_to_budget`].
+pub(crate) struct BudgetFit {
+    /// The rendered trees, with every dropped file marked [`OMITTED`].
+    pub trees: Vec<String>,
+    /// Admitted files, still in walk order.
+    pub files: Vec<FileBlock>,
+    /// Summed size of the dropped files.
+    pub dropped_size: u64,
+}
+
+/// Admits `files` in `config.budget_order` while the trees plus every
+/// admitted file's contents stay within `max_tokens`.
+///
+/// The trees (rendered from `trees`, with `marks` plus the omitted labels)
+/// count against the budget too, and grow by one label per dropped file,
+/// so admission is repeated against the re-rendered trees until they settle.
+/// Each pass can only admit fewer files than the last, so this always
+/// terminates, usually after one or two passes.
+pub(crate) fn fit_to_budget(
+    trees: &[RootTree<'_>],
+    marks: &TreeMarks<'_>,
+    files: Vec<FileBlock>,
+    config: &Config,
+    max_tokens: u64,
+) -> BudgetFit {
+    let priority = priority_order(&files, config.budget_order);
+
+    let mut admitted = files.len();
+    let trees = loop {
+        let mut marks = marks.clone();
+        marks.extend(
+            priority[admitted..]
+                .iter()
+                .map(|&index| (files[index].path.as_path(), OMITTED)),
+        );
+        let rendered = render_trees(trees, config.human, &marks);
+        let tree_tokens = rendered
+            .iter()
+            .map(|tree| config.tokenizer.count(tree))
+            .sum();
+        let remaining = max_tokens.saturating_sub(tree_tokens);
+
+        let mut spent = 0u64;
+        let fit = priority[..admitted]
+            .iter()
+            .take_while(|&&index| {
+                spent += files[index].tokens;
+                spent <= remaining
+            })
+            .count();
+        if fit == admitted {
+            break rendered;
+        }
+        admitted = fit;
+    };
+
+    let mut keep = vec![false; files.len()];
+    for &index in &priority[..admitted] {
+        keep[index] = true;
+    }
+
+    let mut kept = Vec::with_capacity(admitted);
+    let mut dropped_size = 0;
+    for (file, keep) in files.into_iter().zip(keep) {
+        if keep {
+            kept.push(file);
+        } else {
+            dropped_size += file.size;
+        }
+    }
+
+    BudgetFit {
+        trees,
+        files: kept,
+        dropped_size,
+    }
+}
+
+/// Returns `files`' indices in the order they should be admitted. Sorts are
+/// stable, so ties keep walk order.
+fn priority_order(files: &[FileBlock], order: BudgetOrder) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..files.len()).collect();
+    match order {
+        BudgetOrder::Walk => {}
+        BudgetOrder::Smallest => indices.sort_by_key(|&index| files[index].tokens),
+        BudgetOrder::Recent => {
+            // Files whose mtime can't be read sort last.
+            let modified: Vec<_> = files
+                .iter()
+                .map(|file| {
+                    fs::metadata(&file.path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                })
+                .collect();
+            indices.sort_by_key(|&index| Reverse(modified[index]));
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::collect::Entry;
+    use crate::tokens::Tokenizer;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn base_config(directory: &Path) -> Config {
+        Config::builder().directory(directory).build()
+    }
+
+    /// Builds matching `(entries, files)` fixtures for each `(name, tokens)`
+    /// in `files`: a file directly under `root` holding that many chars/4
+    /// tokens.
+    fn fixture(root: &Path, files: &[(&str, u64)]) -> (Vec<Entry>, Vec<FileBlock>) {
+        let entries = files
+            .iter()
+            .map(|&(name, tokens)| Entry {
+                path: root.join(name),
+                depth: 1,
+                is_dir: false,
+                size: Some(tokens * 4),
+            })
+            .collect();
+        let blocks = files
+            .iter()
+            .map(|&(name, tokens)| FileBlock {
+                path: root.join(name),
+                display_path: PathBuf::from(name),
+                size: tokens * 4,
+                text: "x".repeat(tokens as usize * 4),
+                tokens,
+                part: None,
+                matched_lines: Vec::new(),
+                line_numbers: Vec::new(),
+                stripped_size: 0,
+                redactions: 0,
+            })
+            .collect();
+        (entries, blocks)
+    }
+
+    /// `entries` as the one root of a run, labelled `proj`.
+    fn proj(entries: &[Entry]) -> [RootTree<'_>; 1] {
+        [RootTree {
+            label: "proj",
+            entries,
+        }]
+    }
+
+    fn names(files: &[FileBlock]) -> Vec<String> {
+        files
+            .iter()
+            .map(|file| {
+                file.path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn everything_fits_when_the_budget_is_large_enough() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("a", 10), ("b", 10)]);
+
+        let fit = fit_to_budget(
+            &proj(&entries),
+            &TreeMarks::new(),
+            files,
+            &base_config(root),
+            1_000,
+        );
+
+        assert_eq!(names(&fit.files), ["a", "b"]);
+        assert_eq!(fit.dropped_size, 0);
+        assert!(!fit.trees[0].contains("[omitted]"));
+    }
+
+    #[test]
+    fn walk_order_admits_a_prefix_and_marks_the_rest_omitted() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("a", 10), ("b", 10), ("c", 1)]);
+
+        // The tree costs a few tokens itself; 25 leaves room for `a` only,
+        // and admission stops at `b` even though `c` alone would still fit.
+        let fit = fit_to_budget(
+            &proj(&entries),
+            &TreeMarks::new(),
+            files,
+            &base_config(root),
+            25,
+        );
+
+        assert_eq!(names(&fit.files), ["a"]);
+        assert_eq!(fit.dropped_size, 44);
+        assert_eq!(fit.trees[0], "proj/\n  a\n  b [omitted]\n  c [omitted]\n");
+    }
+
+    #[test]
+    fn smallest_order_admits_the_cheapest_files_but_keeps_walk_order() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("big", 50), ("b", 5), ("a", 5)]);
+        let mut config = base_config(root);
+        config.budget_order = BudgetOrder::Smallest;
+
+        let fit = fit_to_budget(&proj(&entries), &TreeMarks::new(), files, &config, 30);
+
+        assert_eq!(names(&fit.files), ["b", "a"]);
+        assert!(fit.trees[0].contains("big [omitted]"));
+    }
+
+    #[test]
+    fn recent_order_admits_the_most_recently_modified_files_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let (entries, files) = fixture(dir.path(), &[("old", 10), ("new", 10)]);
+        let now = SystemTime::now();
+        for (file, age) in files.iter().zip([100, 0]) {
+            std::fs::write(&file.path, &file.text).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&file.path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+        let mut config = base_config(dir.path());
+        config.budget_order = BudgetOrder::Recent;
+
+        let fit = fit_to_budget(&proj(&entries), &TreeMarks::new(), files, &config, 20);
+
+        assert_eq!(names(&fit.files), ["new"]);
+        assert!(fit.trees[0].contains("old [omitted]"));
+    }
+
+    #[test]
+    fn tree_growth_from_omitted_labels_is_paid_for_by_dropping_more_files() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("a", 20), ("b", 20), ("c", 100)]);
+        let config = base_config(root);
+
+        // Unmarked, the tree is 5 tokens, leaving exactly enough for `a` and
+        // `b`; once `c` is marked omitted the tree grows, so `b` goes too.
+        let unmarked = render_trees(&proj(&entries), false, &TreeMarks::new()).concat();
+        assert_eq!(Tokenizer::Chars.count(&unmarked), 5);
+
+        let fit = fit_to_budget(&proj(&entries), &TreeMarks::new(), files, &config, 45);
+
+        assert_eq!(names(&fit.files), ["a"]);
+        assert_eq!(fit.trees[0], "proj/\n  a\n  b [omitted]\n  c [omitted]\n");
+        assert!(Tokenizer::Chars.count(&fit.trees[0]) + 20 <= 45);
+    }
+
+    #[test]
+    fn a_budget_smaller_than_the_tree_drops_every_file() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("a", 1)]);
+
+        let fit = fit_to_budget(
+            &proj(&entries),
+            &TreeMarks::new(),
+            files,
+            &base_config(root),
+            0,
+        );
+
+        assert!(fit.files.is_empty());
+        assert_eq!(fit.dropped_size, 4);
+    }
+
+    #[test]
+    fn existing_marks_stay_unless_the_file_is_omitted() {
+        let root = Path::new("/proj");
+        let (entries, files) = fixture(root, &[("a", 10), ("b", 10)]);
+        let marks: TreeMarks<'_> = entries
+            .iter()
+            .map(|entry| (entry.path.as_path(), "changed"))
+            .collect();
+
+        let fit = fit_to_budget(&proj(&entries), &marks, files, &base_config(root), 25);
+
+        assert_eq!(names(&fit.files), ["a"]);
+        assert_eq!(fit.trees[0], "proj/\n  a [changed]\n  b [omitted]\n");
+    }
+
+    #[test]
+    fn every_root_tree_counts_against_the_budget_and_gets_omitted_marks() {
+        let (api_entries, api_files) = fixture(Path::new("/api"), &[("a", 10)]);
+        let (web_entries, web_files) = fixture(Path::new("/web"), &[("b", 10)]);
+        let trees = [
+            RootTree {
+                label: "api",
+                entries: &api_entries,
+            },
+            RootTree {
+                label: "web",
+                entries: &web_entries,
+            },
+        ];
+        let files = api_files.into_iter().chain(web_files).collect();
+
+        // Each tree costs 2 tokens, so 25 leaves room for `a` but not `b`.
+        let fit = fit_to_budget(
+            &trees,
+            &TreeMarks::new(),
+            files,
+            &base_config(Path::new("/api")),
+            25,
+        );
+
+        assert_eq!(names(&fit.files), ["a"]);
+        assert_eq!(fit.trees, ["api/\n  a\n", "web/\n  b [omitted]\n"]);
+    }
+}
@@ -0,0 +1,603 @@
+//! Splits a run's files into numbered output chunks of at most
+//! `config.chunk_size` each, for when one combined file is too big for a
+//! model to take in at once.
+//!
+//! Chunks are filled in walk order, and a file never straddles two chunks
+//! unless it's bigger than a chunk on its own: then it's cut into pieces
+//! (at line boundaries where possible), each marked with its [`Part`].
+//! Every chunk repeats the full tree, so its cost counts against each
+//! chunk's size too.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::ChunkSize;
+use crate::tokens::Tokenizer;
+
+use super::document::{FileBlock, Part};
+#[cfg(doc)]
+use super::process::numbers_inline;
+
+/// Groups `files` into chunks that each fit `chunk_size` alongside `tree`.
+/// With `numbered` (see [`numbers_inline`]), a file is measured with its
+/// line-number gutters, as it will be written.
+///
+/// The room left for file contents is `chunk_size` minus the tree's own
+/// cost, but never less than half a chunk: a tree that big would otherwise
+/// leave chunks holding almost nothing, so chunks go over `chunk_size`
+/// instead. Always returns at least one (possibly empty) chunk.
+pub(crate) fn split_into_chunks(
+    files: Vec<FileBlock>,
+    tree: &str,
+    chunk_size: ChunkSize,
+    tokenizer: Tokenizer,
+    numbered: bool,
+) -> Vec<Vec<FileBlock>> {
+    let measure = Measure {
+        chunk_size,
+        tokenizer,
+        numbered,
+    };
+    let limit = measure.limit();
+    let capacity = limit
+        .saturating_sub(measure.cost(tree))
+        .max(limit.div_ceil(2));
+
+    let mut packer = Packer {
+        capacity,
+        chunks: Vec::new(),
+        current: Vec::new(),
+        used: 0,
+    };
+    for file in files {
+        let cost = measure.file_cost(&file);
+        if cost > capacity {
+            for piece in measure.split_file(file, capacity) {
+                let cost = measure.file_cost(&piece);
+                packer.push(piece, cost);
+            }
+        } else {
+            packer.push(file, cost);
+        }
+    }
+    packer.finish()
+}
+
+/// Returns the path of chunk `index` (1-based) of `count`: `output` with a
+/// zero-padded number inserted before its extension (`fyai.txt` becomes
+/// `fyai.001.txt`), padded to at least three digits.
+pub(crate) fn chunk_path(output: &Path, index: usize, count: usize) -> PathBuf {
+    let width = count.to_string().len().max(3);
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}.{index:0width$}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{index:0width$}"),
+    };
+    output.with_file_name(name)
+}
+
+/// Returns the path of the manifest listing the chunks a run wrote next to
+/// `output`: a hidden `.<output name>.chunks` beside it, so the next run
+/// knows which files are its own.
+pub(crate) fn manifest_path(output: &Path) -> PathBuf {
+    let name = output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    output.with_file_name(format!(".{name}.chunks"))
+}
+
+/// Returns the chunks the manifest next to `output` lists, as an earlier
+/// run wrote them. A missing manifest lists none, and only plain file
+/// names shaped like [`chunk_path`]'s are trusted, so a stray line can't
+/// point anywhere else.
+pub(crate) fn previous_chunks(output: &Path) -> io::Result<Vec<PathBuf>> {
+    let manifest = match fs::read_to_string(manifest_path(output)) {
+        Ok(manifest) => manifest,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(manifest
+        .lines()
+        .filter(|name| is_chunk_name(output, name))
+        .map(|name| output.with_file_name(name))
+        .collect())
+}
+
+/// Records `chunks`, the files this run wrote next to `output`, in its
+/// manifest.
+pub(crate) fn write_manifest(output: &Path, chunks: &[PathBuf]) -> io::Result<()> {
+    let mut manifest = String::new();
+    for chunk in chunks {
+        if let Some(name) = chunk.file_name() {
+            manifest.push_str(&name.to_string_lossy());
+            manifest.push('\n');
+        }
+    }
+    fs::write(manifest_path(output), manifest)
+}
+
+/// Returns the chunks an earlier run listed in its manifest that aren't
+/// among `written` and still exist: what a run with more chunks, or a
+/// different padding, left behind. Anything else next to `output` is left
+/// alone, however it's named.
+pub(crate) fn stale_chunks(previous: &[PathBuf], written: &[PathBuf]) -> Vec<PathBuf> {
+    previous
+        .iter()
+        .filter(|path| !written.contains(path) && path.is_file())
+        .cloned()
+        .collect()
+}
+
+/// Returns whether `name` is a plain file name [`chunk_path`] could give
+/// for `output`: its stem, a number of at least three digits, and its
+/// extension.
+fn is_chunk_name(output: &Path, name: &str) -> bool {
+    if Path::new(name).file_name().and_then(|name| name.to_str()) != Some(name) {
+        return false;
+    }
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let number = name
+        .strip_prefix(stem.as_str())
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| match output.extension() {
+            Some(ext) => rest
+                .strip_suffix(ext.to_string_lossy().as_ref())
+                .and_then(|rest| rest.strip_suffix('.')),
+            None => Some(rest),
+        });
+    number.is_some_and(|number| number.len() >= 3 && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Fills chunks in order, starting a new one whenever the next file
+/// wouldn't fit in what's left of the current one.
+struct Packer {
+    capacity: u64,
+    chunks: Vec<Vec<FileBlock>>,
+    current: Vec<FileBlock>,
+    used: u64,
+}
+
+impl Packer {
+    fn push(&mut self, file: FileBlock, cost: u64) {
+        if self.used + cost > self.capacity && !self.current.is_empty() {
+            self.chunks.push(std::mem::take(&mut self.current));
+            self.used = 0;
+        }
+        self.current.push(file);
+        self.used += cost;
+    }
+
+    fn finish(mut self) -> Vec<Vec<FileBlock>> {
+        if !self.current.is_empty() || self.chunks.is_empty() {
+            self.chunks.push(self.current);
+        }
+        self.chunks
+    }
+}
+
+/// Measures text in `chunk_size`'s unit.
+struct Measure {
+    chunk_size: ChunkSize,
+    tokenizer: Tokenizer,
+    /// Whether files are written with their line-number gutters.
+    numbered: bool,
+}
+
+impl Measure {
+    fn limit(&self) -> u64 {
+        match self.chunk_size {
+            ChunkSize::Bytes(limit) | ChunkSize::Tokens(limit) => limit,
+        }
+    }
+
+    fn cost(&self, text: &str) -> u64 {
+        match self.chunk_size {
+            ChunkSize::Bytes(_) => text.len() as u64,
+            ChunkSize::Tokens(_) => self.tokenizer.count(text),
+        }
+    }
+
+    /// Like [`Measure::cost`] on `file`'s text as written, gutters
+    /// included, but reuses the token count already taken while reading the
+    /// file.
+    fn file_cost(&self, file: &FileBlock) -> u64 {
+        match self.chunk_size {
+            ChunkSize::Bytes(_) if self.numbers(file) => file.numbered_text().len() as u64,
+            ChunkSize::Bytes(_) => file.text.len() as u64,
+            ChunkSize::Tokens(_) => file.tokens,
+        }
+    }
+
+    /// Whether `file` is written with line-number gutters.
+    fn numbers(&self, file: &FileBlock) -> bool {
+        self.numbered && !file.line_numbers.is_empty()
+    }
+
+    /// The cost of one line's gutter in `file`, at its widest: `0` when it
+    /// has none.
+    fn gutter_cost(&self, file: &FileBlock) -> u64 {
+        match file.line_numbers.iter().max() {
+            Some(max) if self.numbered => self.cost(&format!("{max} | ")),
+            _ => 0,
+        }
+    }
+
+    /// Cuts `file` into pieces of at most `capacity` each, numbered with
+    /// their [`Part`].
+    ///
+    /// Pieces end at line boundaries, except that a single line longer than
+    /// `capacity` is itself cut at character boundaries: every `capacity`
+    /// bytes, or every `capacity * 4` characters when measuring tokens (the
+    /// usual characters-per-token ratio, so a BPE-counted piece can come out
+    /// slightly over). Every segment is charged a gutter when lines are
+    /// numbered, since any of them may start a line of its piece.
+    fn split_file(&self, file: FileBlock, capacity: u64) -> Vec<FileBlock> {
+        let gutter = self.gutter_cost(&file);
+        let room = capacity.saturating_sub(gutter).max(1);
+        // Each piece's text and, with `file.line_numbers`, its own share of
+        // them: a line cut across pieces is numbered in each.
+        let mut pieces = Vec::new();
+        let mut current = (String::new(), Vec::new());
+        let mut used = 0u64;
+        for (index, line) in file.text.split_inclusive('\n').enumerate() {
+            let number = file.line_numbers.get(index).copied();
+            for segment in self.split_line(line, room) {
+                let cost = self.cost(segment) + gutter;
+                if used + cost > capacity && !current.0.is_empty() {
+                    pieces.push(std::mem::take(&mut current));
+                    used = 0;
+                }
+                current.0.push_str(segment);
+                if let Some(number) = number
+                    && current.1.last() != Some(&number)
+                {
+                    current.1.push(number);
+                }
+                used += cost;
+            }
+        }
+        if !current.0.is_empty() {
+            pieces.push(current);
+        }
+
+        let count = pieces.len();
+        pieces
+            .into_iter()
+            .enumerate()
+            .map(|(index, (text, line_numbers))| {
+                let mut piece = FileBlock {
+                    path: file.path.clone(),
+                    display_path: file.display_path.clone(),
+                    size: file.size,
+                    tokens: 0,
+                    text,
+                    part: Some(Part {
+                        index: index + 1,
+                        count,
+                    }),
+                    matched_lines: file.matched_lines.clone(),
+                    line_numbers,
+                    stripped_size: file.stripped_size,
+                    redactions: file.redactions,
+                };
+                piece.tokens = piece.count_tokens(self.tokenizer, self.numbered);
+                piece
+            })
+            .collect()
+    }
+
+    /// Returns `line` whole if it fits in `capacity`, or else cut into
+    /// segments as described on [`Measure::split_file`].
+    fn split_line<'a>(&self, line: &'a str, capacity: u64) -> Vec<&'a str> {
+        if self.cost(line) <= capacity {
+            return vec![line];
+        }
+
+        let (max, in_bytes) = match self.chunk_size {
+            ChunkSize::Bytes(_) => (capacity, true),
+            ChunkSize::Tokens(_) => (capacity.saturating_mul(4), false),
+        };
+
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut taken = 0u64;
+        for (offset, ch) in line.char_indices() {
+            let step = if in_bytes { ch.len_utf8() as u64 } else { 1 };
+            if taken + step > max && offset > start {
+                segments.push(&line[start..offset]);
+                start = offset;
+                taken = 0;
+            }
+            taken += step;
+        }
+        segments.push(&line[start..]);
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(name: &str, text: &str) -> FileBlock {
+        FileBlock {
+            path: PathBuf::from(name),
+            display_path: PathBuf::from(name),
+            size: text.len() as u64,
+            tokens: Tokenizer::Chars.count(text),
+            text: text.to_string(),
+            part: None,
+            matched_lines: Vec::new(),
+            line_numbers: Vec::new(),
+            stripped_size: 0,
+            redactions: 0,
+        }
+    }
+
+    fn layout(chunks: &[Vec<FileBlock>]) -> Vec<Vec<String>> {
+        chunks
+            .iter()
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|file| match file.part {
+                        Some(part) => format!("{} ({part})", file.path.display()),
+                        None => file.path.display().to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn everything_fits_in_one_chunk() {
+        let files = vec![block("a", "aaaa"), block("b", "bbbb")];
+        let chunks = split_into_chunks(files, "", ChunkSize::Bytes(100), Tokenizer::Chars, false);
+        assert_eq!(layout(&chunks), [["a", "b"]]);
+    }
+
+    #[test]
+    fn files_are_packed_in_order_without_splitting_any() {
+        let files = vec![
+            block("a", &"a".repeat(40)),
+            block("b", &"b".repeat(40)),
+            block("c", &"c".repeat(40)),
+        ];
+        let chunks = split_into_chunks(files, "", ChunkSize::Bytes(100), Tokenizer::Chars, false);
+        assert_eq!(layout(&chunks), vec![vec!["a", "b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn the_tree_counts_against_every_chunk() {
+        let files = vec![block("a", &"a".repeat(40)), block("b", &"b".repeat(40))];
+        let tree = "t".repeat(30);
+        let chunks =
+            split_into_chunks(files, &tree, ChunkSize::Bytes(100), Tokenizer::Chars, false);
+        assert_eq!(layout(&chunks), [["a"], ["b"]]);
+    }
+
+    #[test]
+    fn a_huge_tree_still_leaves_half_a_chunk_for_files() {
+        let files = vec![block("a", &"a".repeat(50)), block("b", &"b".repeat(50))];
+        let tree = "t".repeat(500);
+        let chunks =
+            split_into_chunks(files, &tree, ChunkSize::Bytes(100), Tokenizer::Chars, false);
+        assert_eq!(layout(&chunks), [["a"], ["b"]]);
+    }
+
+    #[test]
+    fn token_chunks_measure_with_the_tokenizer() {
+        // 10 tokens each under chars/4.
+        let files = vec![
+            block("a", &"a".repeat(40)),
+            block("b", &"b".repeat(40)),
+            block("c", &"c".repeat(40)),
+        ];
+        let chunks = split_into_chunks(files, "", ChunkSize::Tokens(25), Tokenizer::Chars, false);
+        assert_eq!(layout(&chunks), vec![vec!["a", "b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn a_file_bigger_than_a_chunk_is_split_at_line_boundaries() {
+        let text = "line one\n".repeat(5); // 45 bytes
+        let files = vec![
+            block("small", "x"),
+            block("big", &text),
+            block("after", "y"),
+        ];
+        let chunks = split_into_chunks(files, "", ChunkSize::Bytes(20), Tokenizer::Chars, false);
+
+        assert_eq!(
+            layout(&chunks),
+            vec![
+                vec!["small", "big (1 of 3)"],
+                vec!["big (2 of 3)"],
+                vec!["big (3 of 3)", "after"],
+            ]
+        );
+        let rejoined: String = chunks
+            .iter()
+            .flatten()
+            .filter(|file| file.path == Path::new("big"))
+            .map(|file| file.text.as_str())
+            .collect();
+        assert_eq!(rejoined, text);
+        assert!(
+            chunks
+                .iter()
+                .flatten()
+                .filter(|file| file.part.is_some())
+                .all(|file| file.text.len() <= 20 && file.text.ends_with('\n'))
+        );
+    }
+
+    #[test]
+    fn a_single_overlong_line_is_cut_at_character_boundaries() {
+        let text = "é".repeat(15); // 30 bytes, one line
+        let files = vec![block("min.js", &text)];
+        let chunks = split_into_chunks(files, "", ChunkSize::Bytes(8), Tokenizer::Chars, false);
+
+        let pieces: Vec<&str> = chunks.iter().flatten().map(|f| f.text.as_str()).collect();
+        assert_eq!(pieces.len(), 4);
+        assert!(pieces.iter().all(|piece| piece.len() <= 8));
+        assert_eq!(pieces.concat(), text);
+    }
+
+    #[test]
+    fn split_pieces_keep_the_line_numbers_of_their_own_lines() {
+        let mut big = block("big", "one\ntwo\nthree\n");
+        big.line_numbers = vec![2, 5, 9];
+        let chunks =
+            split_into_chunks(vec![big], "", ChunkSize::Bytes(10), Tokenizer::Chars, false);
+
+        let numbers: Vec<&[usize]> = chunks
+            .iter()
+            .flatten()
+            .map(|file| file.line_numbers.as_slice())
+            .collect();
+        assert_eq!(numbers, [&[2, 5][..], &[9]]);
+    }
+
+    #[test]
+    fn numbered_files_keep_every_chunk_within_the_limit() {
+        let numbered = |name: &str, lines: usize, width: usize| {
+            let text: String = (0..lines)
+                .map(|line| format!("{}\n", "x".repeat(width + line % 3)))
+                .collect();
+            let mut file = block(name, &text);
+            file.line_numbers = (1..=lines).map(|line| line * 7).collect();
+            file.tokens = file.count_tokens(Tokenizer::Chars, true);
+            file
+        };
+        let files = || {
+            let mut files: Vec<FileBlock> = (0..6)
+                .map(|index| numbered(&format!("small{index}"), 6, 10))
+                .collect();
+            files.push(numbered("big", 40, 20));
+            files.push(numbered("long", 1, 700));
+            files
+        };
+        let tree = "tree\n";
+
+        let chunks =
+            split_into_chunks(files(), tree, ChunkSize::Bytes(300), Tokenizer::Chars, true);
+        assert!(chunks.len() > 3);
+        for chunk in &chunks {
+            let size: usize = chunk.iter().map(|file| file.numbered_text().len()).sum();
+            assert!(tree.len() + size <= 300, "{} bytes", tree.len() + size);
+        }
+
+        let tree_tokens = Tokenizer::Chars.count(tree);
+        let chunks =
+            split_into_chunks(files(), tree, ChunkSize::Tokens(80), Tokenizer::Chars, true);
+        for chunk in &chunks {
+            for file in chunk {
+                assert_eq!(file.tokens, Tokenizer::Chars.count(&file.numbered_text()));
+            }
+            let tokens: u64 = chunk.iter().map(|file| file.tokens).sum();
+            assert!(
+                tree_tokens + tokens <= 80,
+                "{} tokens",
+                tree_tokens + tokens
+            );
+        }
+    }
+
+    #[test]
+    fn split_pieces_carry_their_own_token_counts() {
+        let text = format!("{}\n{}\n", "a".repeat(39), "b".repeat(39)); // 20 tokens
+        let chunks = split_into_chunks(
+            vec![block("f", &text)],
+            "",
+            ChunkSize::Tokens(10),
+            Tokenizer::Chars,
+            false,
+        );
+        let tokens: Vec<u64> = chunks.iter().flatten().map(|file| file.tokens).collect();
+        assert_eq!(tokens, [10, 10]);
+    }
+
+    #[test]
+    fn no_files_still_yields_one_empty_chunk() {
+        let chunks = split_into_chunks(
+            Vec::new(),
+            "tree",
+            ChunkSize::Bytes(10),
+            Tokenizer::Chars,
+            false,
+        );
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_empty());
+    }
+
+    #[test]
+    fn chunk_path_inserts_a_padded_number_before_the_extension() {
+        assert_eq!(
+            chunk_path(Path::new("fyai.txt"), 1, 3),
+            PathBuf::from("fyai.001.txt")
+        );
+        assert_eq!(
+            chunk_path(Path::new("out/context.md"), 12, 12),
+            PathBuf::from("out/context.012.md")
+        );
+        assert_eq!(
+            chunk_path(Path::new("dump"), 7, 1200),
+            PathBuf::from("dump.0007")
+        );
+    }
+
+    #[test]
+    fn only_chunks_the_manifest_lists_are_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("fyai.txt");
+        for name in [
+            "fyai.001.txt",
+            "fyai.002.txt",
+            "fyai.003.txt",
+            "fyai.2024.txt",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert!(previous_chunks(&output).unwrap().is_empty());
+
+        let earlier: Vec<PathBuf> = (1..=4).map(|index| chunk_path(&output, index, 4)).collect();
+        write_manifest(&output, &earlier).unwrap();
+        let previous = previous_chunks(&output).unwrap();
+        assert_eq!(previous, earlier);
+
+        // fyai.004.txt is already gone, and fyai.2024.txt was never ours.
+        let written = [dir.path().join("fyai.001.txt")];
+        assert_eq!(
+            stale_chunks(&previous, &written),
+            [
+                dir.path().join("fyai.002.txt"),
+                dir.path().join("fyai.003.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn previous_chunks_ignores_manifest_lines_that_are_not_chunk_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("fyai.txt");
+        fs::write(
+            manifest_path(&output),
+            "fyai.001.txt\n../fyai.002.txt\nnotes.txt\nfyai.01.txt\nfyai.abc.txt\nfyai.003.md\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            previous_chunks(&output).unwrap(),
+            [dir.path().join("fyai.001.txt")]
+        );
+        assert_eq!(manifest_path(&output), dir.path().join(".fyai.txt.chunks"));
+    }
+}
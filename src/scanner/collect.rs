@@ -3,20 +3,26 @@
 //! the directory tree exactly one time, no matter how many outputs it
 //! produces from it.
 
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 
 use ignore::WalkState;
 
 use crate::config::Config;
+use crate::error::{FyaiError, Result};
+use crate::git::changed_paths;
 
+use super::file_list::listed_entries;
 use super::filter::PathFilter;
+use super::roots::roots;
 use super::walker::build_walker;
 
 /// One filtered walk entry: enough for both tree rendering and, for files,
 /// reading contents.
-pub(crate) struct Entry {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
     /// Absolute path of the entry.
     pub path: PathBuf,
     /// Number of path components between this entry and `config.directory`
@@ -32,14 +38,191 @@ pub(crate) struct Entry {
 /// Walks `config.directory` in parallel, keeping only entries [`PathFilter`]
 /// allows, then sorts the result back into a deterministic preorder.
 ///
-/// [`Path`](std::path::Path)'s `Ord` compares path components rather than
-/// raw bytes, so sorting by path exactly reconstructs the preorder a
-/// sequential depth-first walk would have produced (a directory's own path
-/// is always ordered immediately before all of its descendants' paths, and
-/// before any sibling's), which is what the tree renderer's depth-based
-/// nesting logic assumes.
-pub(crate) fn collect_entries(config: &Config) -> io::Result<Vec<Entry>> {
-    let filter = PathFilter::new(config);
+/// This is the walk and filter every run starts with, with nothing read or
+/// written: every include/exclude, ignore-file, and size-independent rule
+/// in `config` applies (`min_size`/`max_size` only decide which files get
+/// read, so sized-out files are still returned), and a file at
+/// `config.output` is left out as always. Entries the walk can't read are
+/// skipped rather than failing the whole call.
+///
+/// With `config.changed_since`, `config.staged`, or `config.unstaged`, only
+/// the files git reports as changed are returned, along with the
+/// directories that lead to them. `config.full_tree` doesn't apply here;
+/// it only changes the rendered tree.
+///
+/// With `config.files_from`, nothing is walked: the listed files and the
+/// directories leading to them are returned instead, whatever the filters
+/// say, with any change filter still applied.
+///
+/// When `config.directory` is a `.zip` or `.tar(.gz/.xz/.zst)` archive
+/// rather than a directory, its members are returned instead, at paths
+/// under the archive's own path (`src.tar.gz/src/main.rs`), without
+/// extracting anything. Ignore files inside it aren't applied.
+///
+/// With `config.extra_directories`, each root is collected on its own, and
+/// its entries follow the previous root's.
+///
+/// # Errors
+///
+/// Fails with [`FyaiError::Git`] if a change filter is set and git can't
+/// answer it (not a repository, unknown ref), and with
+/// [`FyaiError::Archive`] if an archive can't be read, or is combined with
+/// a change filter or `config.files_from`. A `config.files_from` path
+/// outside `config.directory` fails with [`FyaiError::InvalidValue`], and a
+/// missing one with an I/O error. Several roots fail with an
+/// [`io::ErrorKind::InvalidInput`] error if one of them is listed twice, or
+/// with `config.files_from`.
+pub fn collect_entries(config: &Config) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for root in roots(config)? {
+        entries.extend(collect(&root.config, false)?.entries);
+    }
+    Ok(entries)
+}
+
+/// Contents of an archive's member files, keyed by their paths, read in
+/// the same pass as the members themselves.
+pub(crate) type Contents = HashMap<PathBuf, Vec<u8>>;
+
+/// The entries a run works from: see [`collect`].
+pub(crate) struct Collected {
+    /// Entries whose files are read, as [`collect_entries`] returns them.
+    pub entries: Vec<Entry>,
+    /// With `config.full_tree` and a change filter, every entry the
+    /// filters allow, for the tree, and the changed files to mark in it.
+    pub full_tree: Option<(Vec<Entry>, HashSet<PathBuf>)>,
+    /// For an archive, the contents of its member files within the size
+    /// bounds, if they were asked for; `None` for a directory, whose files
+    /// are read from disk.
+    pub archive: Option<Contents>,
+}
+
+/// Walks `config.directory` (or reads `config.files_from`) and applies any
+/// change filter, keeping the
+/// unrestricted walk too when `config.full_tree` asks for it. An archive's
+/// members are read instead, along with their contents when
+/// `read_contents` is set.
+pub(crate) fn collect(config: &Config, read_contents: bool) -> Result<Collected> {
+    if is_archive(config)? {
+        let (entries, contents) = read_archive(config, read_contents)?;
+        return Ok(Collected {
+            entries,
+            full_tree: None,
+            archive: Some(contents),
+        });
+    }
+
+    let entries = match &config.files_from {
+        Some(list) => listed_entries(config, list)?,
+        None => walk_entries(config)?,
+    };
+    let Some(changed) = changed_paths(config)? else {
+        return Ok(Collected {
+            entries,
+            full_tree: None,
+            archive: None,
+        });
+    };
+
+    let only_changed = only_files(&entries, &changed);
+    Ok(Collected {
+        entries: only_changed,
+        full_tree: config.full_tree.then_some((entries, changed)),
+        archive: None,
+    })
+}
+
+/// Returns whether `config.directory` is a file, to be read as an archive
+/// in place of a directory.
+///
+/// Fails for an archive combined with an option that asks git about the
+/// scanned directory, since an archive has no working copy to ask about,
+/// or with `config.files_from`, whose paths name files on disk.
+pub(crate) fn is_archive(config: &Config) -> Result<bool> {
+    if !config.directory.is_file() {
+        return Ok(false);
+    }
+    let git_options = [
+        ("changed_since", config.changed_since.is_some()),
+        ("staged", config.staged),
+        ("unstaged", config.unstaged),
+        ("diff", config.diff.is_some()),
+        ("git_log", config.git_log.is_some()),
+        ("submodules", config.submodules),
+    ];
+    if let Some((key, _)) = git_options.into_iter().find(|(_, set)| *set) {
+        return Err(FyaiError::Archive {
+            path: config.directory.clone(),
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{key} needs a git working copy, not an archive"),
+            ),
+        });
+    }
+    if config.files_from.is_some() {
+        return Err(FyaiError::Archive {
+            path: config.directory.clone(),
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "files_from lists files on disk, not archive members",
+            ),
+        });
+    }
+    Ok(true)
+}
+
+/// Reads the archive at `config.directory`: its filtered members, and with
+/// `read_contents`, their contents.
+#[cfg(feature = "archive")]
+fn read_archive(config: &Config, read_contents: bool) -> Result<(Vec<Entry>, Contents)> {
+    let members = super::archive::read_members(config, read_contents)?;
+    Ok((members.entries, members.contents))
+}
+
+/// Without the `archive` feature, archives can't be read.
+#[cfg(not(feature = "archive"))]
+fn read_archive(config: &Config, _read_contents: bool) -> Result<(Vec<Entry>, Contents)> {
+    Err(FyaiError::Archive {
+        path: config.directory.clone(),
+        source: io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading archives needs the `archive` feature",
+        ),
+    })
+}
+
+/// Returns the files in `entries` that are in `files`, plus their ancestor
+/// directories, still in preorder.
+pub(crate) fn only_files(entries: &[Entry], files: &HashSet<PathBuf>) -> Vec<Entry> {
+    let ancestors: HashSet<&Path> = entries
+        .iter()
+        .filter(|entry| !entry.is_dir && files.contains(&entry.path))
+        .flat_map(|entry| entry.path.ancestors().skip(1))
+        .collect();
+
+    entries
+        .iter()
+        .filter(|entry| {
+            if entry.is_dir {
+                ancestors.contains(entry.path.as_path())
+            } else {
+                files.contains(&entry.path)
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// The walk behind [`collect_entries`], before any change filter.
+///
+/// [`Path`]'s `Ord` compares path components rather than raw bytes, so
+/// sorting by path exactly reconstructs the preorder a sequential
+/// depth-first walk would have produced (a directory's own path is always
+/// ordered immediately before all of its descendants' paths, and before any
+/// sibling's), which is what the tree renderer's depth-based nesting logic
+/// assumes.
+fn walk_entries(config: &Config) -> Result<Vec<Entry>> {
+    let filter = PathFilter::new(config)?;
     let walker = build_walker(config)?;
     let (tx, rx) = mpsc::channel::<Entry>();
 
@@ -90,25 +273,7 @@ mod tests {
     /// A baseline [`Config`] with every filter at its "do nothing special"
     /// default. Individual tests override only the fields they care about.
     fn base_config(directory: &std::path::Path) -> Config {
-        Config {
-            directory: directory.to_path_buf(),
-            output: PathBuf::from("fyai.txt"),
-            include_dirs: None,
-            exclude_dirs: None,
-            include_ext: None,
-            exclude_ext: None,
-            include_files: None,
-            exclude_files: None,
-            min_size: None,
-            max_size: None,
-            hidden: true,
-            gitignore: true,
-            ignore_files: true,
-            git_global: true,
-            follow_links: false,
-            tree_only: false,
-            human: false,
-        }
+        Config::builder().directory(directory).build()
     }
 
     #[test]
@@ -202,4 +367,155 @@ mod tests {
         assert!(!paths.contains(&output_path));
         assert!(paths.contains(&dir.path().join("kept.txt")));
     }
+
+    // ---- change filters ----
+
+    /// Runs `git <args>` in `dir`, asserting it succeeds.
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    /// A committed repo holding `keep/a.txt` and `skip/b.txt`, with
+    /// `keep/a.txt` then edited but not staged.
+    fn repo_with_one_unstaged_edit() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir(root.join("keep")).unwrap();
+        fs::create_dir(root.join("skip")).unwrap();
+        fs::write(root.join("keep").join("a.txt"), "a").unwrap();
+        fs::write(root.join("skip").join("b.txt"), "b").unwrap();
+        git(root, &["init", "-q"]);
+        git(root, &["add", "."]);
+        git(
+            root,
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "-m",
+                "initial",
+            ],
+        );
+        fs::write(root.join("keep").join("a.txt"), "edited").unwrap();
+        dir
+    }
+
+    #[test]
+    fn collect_entries_keeps_only_changed_files_and_their_directories() {
+        let repo = repo_with_one_unstaged_edit();
+        let root = repo.path();
+        let config = Config::builder().directory(root).unstaged(true).build();
+
+        let paths: Vec<PathBuf> = collect_entries(&config)
+            .expect("collect_entries")
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+
+        assert_eq!(paths, [root.join("keep"), root.join("keep").join("a.txt")]);
+    }
+
+    #[test]
+    fn collect_keeps_the_full_walk_for_full_tree() {
+        let repo = repo_with_one_unstaged_edit();
+        let root = repo.path();
+        let config = Config::builder()
+            .directory(root)
+            .unstaged(true)
+            .full_tree(true)
+            .build();
+
+        let collected = collect(&config, false).expect("collect");
+        assert_eq!(collected.entries.len(), 2);
+        let (all, changed) = collected.full_tree.expect("full tree");
+        assert_eq!(all.len(), 4);
+        assert_eq!(changed, HashSet::from([root.join("keep").join("a.txt")]));
+    }
+
+    #[test]
+    fn collect_entries_fails_outside_a_git_repository_with_a_change_filter() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let config = Config::builder().directory(dir.path()).staged(true).build();
+
+        let err = collect_entries(&config).unwrap_err();
+        assert!(matches!(err, crate::error::FyaiError::Git(_)));
+    }
+
+    #[test]
+    fn is_archive_is_true_for_a_file_and_rejects_git_options() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let archive = dir.path().join("src.zip");
+        fs::write(&archive, b"PK").expect("write");
+
+        assert!(!is_archive(&base_config(dir.path())).expect("directory"));
+        assert!(is_archive(&base_config(&archive)).expect("archive"));
+
+        let config = Config::builder().directory(&archive).git_log(3).build();
+        let err = is_archive(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "failed to read archive {}: git_log needs a git working copy, not an archive",
+                archive.display()
+            )
+        );
+    }
+
+    #[test]
+    fn collect_entries_lists_each_root_in_turn() {
+        let first = tempfile::tempdir().expect("tempdir");
+        let second = tempfile::tempdir().expect("tempdir");
+        fs::write(first.path().join("b.txt"), b"b").expect("write");
+        fs::write(second.path().join("a.txt"), b"a").expect("write");
+
+        let config = Config::builder()
+            .directories([first.path(), second.path()])
+            .build();
+        let paths: Vec<PathBuf> = collect_entries(&config)
+            .expect("collect_entries")
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [first.path().join("b.txt"), second.path().join("a.txt")]
+        );
+    }
+
+    #[test]
+    fn collect_entries_returns_files_from_entries_without_walking_or_filtering() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::create_dir_all(dir.path().join("docs")).expect("create_dir_all");
+        fs::write(dir.path().join("docs").join("guide.md"), b"guide").expect("write");
+        fs::write(dir.path().join("main.rs"), b"main").expect("write");
+        let list = dir.path().join("files.txt");
+        fs::write(&list, "docs/guide.md\n").expect("write");
+
+        let config = Config::builder()
+            .directory(dir.path())
+            .files_from(&list)
+            .include_ext(["rs"])
+            .build();
+        let paths: Vec<PathBuf> = collect_entries(&config)
+            .expect("collect_entries")
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                dir.path().join("docs"),
+                dir.path().join("docs").join("guide.md")
+            ]
+        );
+    }
 }
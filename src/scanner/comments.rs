@@ -0,0 +1,784 @@
+//! The `config.strip_comments` transform: removes line and block comments
+//! from a file's text, by the language [`fence_language`] maps its
+//! extension to, so they don't spend the token budget.
+//!
+//! This is a small lexer per language family rather than a parser: just
+//! enough to tell comments from string and character literals (and, for
+//! JavaScript and TypeScript, regex literals), so the `//` in
+//! `"http://…"` survives. Everything else is kept byte for byte:
+//!
+//! - A line left blank by a removed comment is dropped, as are the blank
+//!   lines a leading license header leaves behind, and whitespace before a
+//!   trailing comment is trimmed.
+//! - A block comment that spanned lines becomes a line break, which keeps
+//!   Go's and JavaScript's semicolon insertion intact. One within a line
+//!   becomes a space if removing it would join two tokens.
+//! - Doc comments are comments, but docstrings are string literals and
+//!   stay, as do a leading `#!` line and Haskell's `{-# … #-}` pragmas.
+//!
+//! Languages without a syntax here (Markdown, Makefiles, Perl, …) are left
+//! untouched. Constructs that need a real parser, like Ruby regex literals
+//! or shell here-documents, aren't recognized, so a comment marker inside
+//! one is taken for a comment.
+//!
+//! [`fence_language`]: super::lang::fence_language
+
+/// A string literal's delimiters.
+struct Quote {
+    open: &'static str,
+    close: &'static str,
+    /// Whether a backslash escapes the character after it.
+    escapes: bool,
+    /// Whether the literal ends at the end of its line even if unclosed,
+    /// for languages where an apostrophe in plain text (YAML's `it's`)
+    /// isn't always a quote.
+    single_line: bool,
+}
+
+impl Quote {
+    const fn new(open: &'static str, close: &'static str, escapes: bool) -> Self {
+        Self {
+            open,
+            close,
+            escapes,
+            single_line: false,
+        }
+    }
+
+    const fn single_line(self) -> Self {
+        Self {
+            single_line: true,
+            ..self
+        }
+    }
+}
+
+const DOUBLE: Quote = Quote::new("\"", "\"", true);
+const SINGLE: Quote = Quote::new("'", "'", true);
+const RAW_DOUBLE: Quote = Quote::new("\"", "\"", false);
+const RAW_SINGLE: Quote = Quote::new("'", "'", false);
+const TRIPLE_DOUBLE: Quote = Quote::new("\"\"\"", "\"\"\"", true);
+const TRIPLE_SINGLE: Quote = Quote::new("'''", "'''", true);
+
+/// How one language family writes comments and literals.
+struct Syntax {
+    /// Markers that comment out the rest of the line.
+    line: &'static [&'static str],
+    /// Opening and closing delimiters of block comments, checked before
+    /// `line` (so Lua's `--[[` wins over `--`).
+    block: &'static [(&'static str, &'static str)],
+    /// Whether block comments nest (Rust, Swift, Kotlin, Scala, Haskell).
+    nested: bool,
+    /// String literals, longest opener first.
+    quotes: &'static [Quote],
+    /// Whether a comment marker at the start of `rest`, right after the
+    /// byte `prev`, really starts a comment.
+    starts_comment: fn(prev: Option<u8>, rest: &str) -> bool,
+    /// Whether a quote at the start of `rest`, right after the byte
+    /// `prev`, really opens a string literal.
+    opens_quote: fn(prev: Option<u8>, rest: &str) -> bool,
+    /// Whether `'` only quotes a single (possibly escaped) character, and
+    /// is otherwise a Rust lifetime or a Haskell prime.
+    char_literals: bool,
+    /// Whether Rust's raw strings (`r"…"`, `r#"…"#`, `br"…"`) exist.
+    raw_strings: bool,
+    /// Whether a `/` where an operand is expected starts a regex literal.
+    regex_literals: bool,
+}
+
+fn always(_: Option<u8>, _: &str) -> bool {
+    true
+}
+
+/// Shell and YAML comments only start a word: `$#` and `a#b` aren't.
+fn at_word_start(prev: Option<u8>, _: &str) -> bool {
+    prev.is_none_or(|prev| prev.is_ascii_whitespace() || b";|&(".contains(&prev))
+}
+
+/// YAML quotes only open a scalar, so the apostrophe in `title: it's`
+/// is plain text.
+fn at_scalar_start(prev: Option<u8>, _: &str) -> bool {
+    prev.is_none_or(|prev| prev.is_ascii_whitespace() || b"[{,".contains(&prev))
+}
+
+/// PHP 8 attributes (`#[Route]`) aren't comments.
+fn not_php_attribute(_: Option<u8>, rest: &str) -> bool {
+    !rest.starts_with("#[")
+}
+
+/// SCSS's `//` inside an unquoted `url(//cdn…)` or after a `:` isn't a
+/// comment.
+fn not_scss_url(prev: Option<u8>, rest: &str) -> bool {
+    !(rest.starts_with("//") && matches!(prev, Some(b':' | b'(')))
+}
+
+/// Haskell's `{-# … #-}` pragmas aren't comments to drop, and a run of
+/// dashes followed by a symbol (`-->`) is an operator.
+fn haskell_comment(_: Option<u8>, rest: &str) -> bool {
+    if rest.starts_with("{-#") {
+        return false;
+    }
+    if rest.starts_with("--") {
+        let dashes = rest.bytes().take_while(|&byte| byte == b'-').count();
+        return !rest
+            .as_bytes()
+            .get(dashes)
+            .is_some_and(|next| b"!#$%&*+./<=>?@\\^|~:".contains(next));
+    }
+    true
+}
+
+const C: Syntax = Syntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+    nested: false,
+    quotes: &[DOUBLE, SINGLE],
+    starts_comment: always,
+    opens_quote: always,
+    char_literals: false,
+    raw_strings: false,
+    regex_literals: false,
+};
+const RUST: Syntax = Syntax {
+    nested: true,
+    quotes: &[DOUBLE],
+    char_literals: true,
+    raw_strings: true,
+    ..C
+};
+const CSHARP: Syntax = Syntax {
+    quotes: &[Quote::new("@\"", "\"", false), DOUBLE, SINGLE],
+    ..C
+};
+const JAVA: Syntax = Syntax {
+    quotes: &[TRIPLE_DOUBLE, DOUBLE, SINGLE],
+    ..C
+};
+const KOTLIN: Syntax = Syntax {
+    nested: true,
+    quotes: &[Quote::new("\"\"\"", "\"\"\"", false), DOUBLE, SINGLE],
+    ..C
+};
+const SWIFT: Syntax = Syntax {
+    nested: true,
+    quotes: &[TRIPLE_DOUBLE, DOUBLE],
+    ..C
+};
+const DART: Syntax = Syntax {
+    quotes: &[TRIPLE_DOUBLE, TRIPLE_SINGLE, DOUBLE, SINGLE],
+    ..C
+};
+const GO: Syntax = Syntax {
+    quotes: &[Quote::new("`", "`", false), DOUBLE, SINGLE],
+    ..C
+};
+const JAVASCRIPT: Syntax = Syntax {
+    quotes: &[Quote::new("`", "`", true), DOUBLE, SINGLE],
+    regex_literals: true,
+    ..C
+};
+const PHP: Syntax = Syntax {
+    line: &["//", "#"],
+    starts_comment: not_php_attribute,
+    ..C
+};
+const ZIG: Syntax = Syntax {
+    block: &[],
+    quotes: &[
+        Quote::new("\\\\", "\n", false).single_line(),
+        DOUBLE,
+        SINGLE,
+    ],
+    ..C
+};
+const CSS: Syntax = Syntax { line: &[], ..C };
+const SCSS: Syntax = Syntax {
+    starts_comment: not_scss_url,
+    ..C
+};
+const JSON: Syntax = Syntax {
+    quotes: &[DOUBLE],
+    ..C
+};
+const PYTHON: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    quotes: &[TRIPLE_DOUBLE, TRIPLE_SINGLE, DOUBLE, SINGLE],
+    ..C
+};
+const HASH: Syntax = Syntax {
+    quotes: &[DOUBLE, SINGLE],
+    ..PYTHON
+};
+const ELIXIR: Syntax = Syntax {
+    quotes: &[TRIPLE_DOUBLE, DOUBLE, SINGLE],
+    ..PYTHON
+};
+const GRAPHQL: Syntax = Syntax {
+    quotes: &[TRIPLE_DOUBLE, DOUBLE],
+    ..PYTHON
+};
+const BASH: Syntax = Syntax {
+    quotes: &[DOUBLE, RAW_SINGLE],
+    starts_comment: at_word_start,
+    ..PYTHON
+};
+const POWERSHELL: Syntax = Syntax {
+    block: &[("<#", "#>")],
+    quotes: &[RAW_DOUBLE, RAW_SINGLE],
+    ..PYTHON
+};
+const YAML: Syntax = Syntax {
+    quotes: &[DOUBLE.single_line(), RAW_SINGLE.single_line()],
+    starts_comment: at_word_start,
+    opens_quote: at_scalar_start,
+    ..PYTHON
+};
+const TOML: Syntax = Syntax {
+    quotes: &[
+        TRIPLE_DOUBLE,
+        Quote::new("'''", "'''", false),
+        DOUBLE.single_line(),
+        RAW_SINGLE.single_line(),
+    ],
+    ..PYTHON
+};
+const SQL: Syntax = Syntax {
+    line: &["--"],
+    quotes: &[RAW_SINGLE, RAW_DOUBLE],
+    ..C
+};
+const LUA: Syntax = Syntax {
+    line: &["--"],
+    block: &[("--[[", "]]")],
+    quotes: &[Quote::new("[[", "]]", false), DOUBLE, SINGLE],
+    ..C
+};
+const HASKELL: Syntax = Syntax {
+    line: &["--"],
+    block: &[("{-", "-}")],
+    nested: true,
+    quotes: &[DOUBLE],
+    starts_comment: haskell_comment,
+    char_literals: true,
+    ..C
+};
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+    quotes: &[],
+    ..C
+};
+
+/// Returns the comment syntax for a [`fence_language`] tag, or `None` for
+/// languages whose comments are left alone.
+///
+/// [`fence_language`]: super::lang::fence_language
+fn syntax(language: &str) -> Option<&'static Syntax> {
+    Some(match language {
+        "rust" => &RUST,
+        "c" | "cpp" | "protobuf" => &C,
+        "java" => &JAVA,
+        "csharp" => &CSHARP,
+        "kotlin" | "scala" => &KOTLIN,
+        "swift" => &SWIFT,
+        "dart" => &DART,
+        "go" => &GO,
+        "javascript" | "jsx" | "typescript" | "tsx" => &JAVASCRIPT,
+        "php" => &PHP,
+        "zig" => &ZIG,
+        "css" => &CSS,
+        "scss" | "sass" => &SCSS,
+        "json" => &JSON,
+        "python" => &PYTHON,
+        "ruby" | "r" => &HASH,
+        "elixir" => &ELIXIR,
+        "graphql" => &GRAPHQL,
+        "bash" => &BASH,
+        "powershell" => &POWERSHELL,
+        "yaml" => &YAML,
+        "toml" => &TOML,
+        "sql" => &SQL,
+        "lua" => &LUA,
+        "haskell" => &HASKELL,
+        "html" | "xml" | "vue" | "svelte" => &MARKUP,
+        _ => return None,
+    })
+}
+
+/// Returns `text` without its comments, along with the 1-based line of
+/// `text` each of its lines starts on, or `None` if `language` (a
+/// [`fence_language`] tag) has no comment syntax here.
+///
+/// [`fence_language`]: super::lang::fence_language
+pub(crate) fn strip_comments(text: &str, language: &str) -> Option<(String, Vec<usize>)> {
+    let syntax = syntax(language)?;
+    let mut stripper = Stripper {
+        text,
+        bytes: text.as_bytes(),
+        syntax,
+        out: Vec::with_capacity(text.len()),
+        lines: Vec::new(),
+        pos: 0,
+        line: 1,
+        origin: 1,
+        line_start: 0,
+        line_stripped: false,
+        dropped_leading: false,
+    };
+    Some(stripper.run())
+}
+
+/// State of one [`strip_comments`] pass. Comments and literals are only
+/// ever cut at ASCII delimiters, so `out` stays valid UTF-8.
+struct Stripper<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    syntax: &'static Syntax,
+    out: Vec<u8>,
+    /// The line of `text` each finished line of `out` starts on.
+    lines: Vec<usize>,
+    /// Position in `text`.
+    pos: usize,
+    /// The line of `text` at `pos`.
+    line: usize,
+    /// The line of `text` the line being written starts on.
+    origin: usize,
+    /// Where the line being written starts in `out`.
+    line_start: usize,
+    /// Whether a comment was removed from the line being written.
+    line_stripped: bool,
+    /// Whether a line was dropped before anything was written, so blank
+    /// lines after a leading header should go too.
+    dropped_leading: bool,
+}
+
+impl Stripper<'_> {
+    fn run(&mut self) -> (String, Vec<usize>) {
+        if self.text.starts_with("#!") {
+            let end = self.text.find('\n').unwrap_or(self.text.len());
+            self.copy(end);
+        }
+
+        while self.pos < self.bytes.len() {
+            let rest = &self.text[self.pos..];
+            let prev = self.pos.checked_sub(1).map(|index| self.bytes[index]);
+            if rest.starts_with('\n') || rest.starts_with("\r\n") {
+                let newline = if rest.starts_with('\n') { 1 } else { 2 };
+                self.pos += newline;
+                self.line += 1;
+                self.end_line(&rest[..newline]);
+            } else if let Some(end) = self.raw_string(prev, rest) {
+                self.copy_literal(end);
+            } else if let Some(&(open, close)) = self.syntax.block.iter().find(|(open, _)| {
+                rest.starts_with(open) && (self.syntax.starts_comment)(prev, rest)
+            }) {
+                self.block_comment(open, close);
+            } else if self
+                .syntax
+                .line
+                .iter()
+                .any(|marker| rest.starts_with(marker) && (self.syntax.starts_comment)(prev, rest))
+            {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                let end = if rest[..end].ends_with('\r') {
+                    end - 1
+                } else {
+                    end
+                };
+                self.pos += end;
+                self.line_stripped = true;
+            } else if let Some(end) = self.char_literal(rest) {
+                self.copy(end);
+            } else if let Some(end) = self.regex_literal(rest) {
+                self.copy(end);
+            } else if let Some(quote) = self
+                .syntax
+                .quotes
+                .iter()
+                .find(|quote| rest.starts_with(quote.open))
+                .filter(|_| (self.syntax.opens_quote)(prev, rest))
+            {
+                let end = self.quoted_len(quote, rest);
+                self.copy_literal(end);
+            } else {
+                self.copy(rest.chars().next().map_or(1, char::len_utf8));
+            }
+        }
+        self.finish_line();
+        if self.out.len() > self.line_start {
+            self.lines.push(self.origin);
+        }
+
+        if self.dropped_leading {
+            let blank = self
+                .out
+                .iter()
+                .take_while(|&&byte| byte == b'\n' || byte == b'\r')
+                .count();
+            let blank = self.out[..blank]
+                .iter()
+                .rposition(|&byte| byte == b'\n')
+                .map_or(0, |newline| newline + 1);
+            let blank_lines = self.out[..blank].iter().filter(|&&byte| byte == b'\n');
+            self.lines.drain(..blank_lines.count());
+            self.out.drain(..blank);
+        }
+        let text = String::from_utf8(std::mem::take(&mut self.out))
+            .expect("comments are only cut at ASCII delimiters");
+        (text, std::mem::take(&mut self.lines))
+    }
+
+    /// Copies the next `len` bytes of `text` as they are.
+    fn copy(&mut self, len: usize) {
+        let end = (self.pos + len).min(self.bytes.len());
+        self.out.extend_from_slice(&self.bytes[self.pos..end]);
+        self.pos = end;
+    }
+
+    /// Copies a literal `len` bytes long, which may span lines.
+    fn copy_literal(&mut self, len: usize) {
+        let start = self.out.len();
+        self.copy(len);
+        let newlines = self.out[start..].iter().filter(|&&byte| byte == b'\n');
+        for _ in 0..newlines.count() {
+            self.lines.push(self.origin);
+            self.line += 1;
+            self.origin = self.line;
+        }
+        if let Some(newline) = self.out[start..].iter().rposition(|&byte| byte == b'\n') {
+            self.line_start = start + newline + 1;
+            self.line_stripped = false;
+        }
+    }
+
+    /// Ends the line being written with `newline`, or drops it if removing
+    /// a comment left it blank. `line` must already be past the newline.
+    fn end_line(&mut self, newline: &str) {
+        if self.finish_line() {
+            if self.out.is_empty() {
+                self.dropped_leading = true;
+            }
+        } else {
+            self.out.extend_from_slice(newline.as_bytes());
+            self.lines.push(self.origin);
+        }
+        self.origin = self.line;
+        self.line_start = self.out.len();
+        self.line_stripped = false;
+    }
+
+    /// Trims the whitespace a removed comment left at the end of the line
+    /// being written, returning whether that left the line blank.
+    fn finish_line(&mut self) -> bool {
+        if !self.line_stripped {
+            return false;
+        }
+        while self.out.len() > self.line_start
+            && matches!(self.out.last(), Some(b' ' | b'\t' | b'\r'))
+        {
+            self.out.pop();
+        }
+        self.out.len() == self.line_start
+    }
+
+    /// Skips a block comment opened by `open` at `pos`.
+    fn block_comment(&mut self, open: &str, close: &str) {
+        let rest = &self.text[self.pos..];
+        let mut depth = 0;
+        let mut index = 0;
+        let len = loop {
+            if index >= rest.len() {
+                break rest.len();
+            }
+            let tail = &rest.as_bytes()[index..];
+            if tail.starts_with(open.as_bytes()) && (depth == 0 || self.syntax.nested) {
+                depth += 1;
+                index += open.len();
+            } else if tail.starts_with(close.as_bytes()) {
+                depth -= 1;
+                index += close.len();
+                if depth == 0 {
+                    break index;
+                }
+            } else {
+                index += 1;
+            }
+        };
+
+        let comment = &rest[..len];
+        self.pos += len;
+        self.line += comment.matches('\n').count();
+        self.line_stripped = true;
+        if comment.contains('\n') {
+            let newline = if comment.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            self.end_line(newline);
+            // The comment's last line is part of the line it removed.
+            self.line_stripped = true;
+        } else {
+            let joins_tokens = self
+                .out
+                .last()
+                .is_some_and(|byte| !byte.is_ascii_whitespace())
+                && self
+                    .bytes
+                    .get(self.pos)
+                    .is_some_and(|byte| !byte.is_ascii_whitespace());
+            if joins_tokens {
+                self.out.push(b' ');
+            }
+        }
+    }
+
+    /// Returns the length of the quoted literal at the start of `rest`,
+    /// through its closing delimiter (or the end of its line or text, if
+    /// unclosed).
+    fn quoted_len(&self, quote: &Quote, rest: &str) -> usize {
+        let bytes = rest.as_bytes();
+        let mut index = quote.open.len();
+        while index < bytes.len() {
+            if quote.escapes && bytes[index] == b'\\' {
+                index += 2;
+            } else if quote.single_line && bytes[index] == b'\n' {
+                return index;
+            } else if bytes[index..].starts_with(quote.close.as_bytes()) {
+                return index + quote.close.len();
+            } else {
+                index += 1;
+            }
+        }
+        bytes.len()
+    }
+
+    /// Returns the length of a Rust raw string at the start of `rest`.
+    fn raw_string(&self, prev: Option<u8>, rest: &str) -> Option<usize> {
+        if !self.syntax.raw_strings || prev.is_some_and(is_identifier_byte) {
+            return None;
+        }
+        let prefix = if rest.starts_with("br") { 2 } else { 1 };
+        if !rest.starts_with('r') && prefix == 1 {
+            return None;
+        }
+        let hashes = rest[prefix..]
+            .bytes()
+            .take_while(|&byte| byte == b'#')
+            .count();
+        if !rest[prefix + hashes..].starts_with('"') {
+            return None;
+        }
+        let body = prefix + hashes + 1;
+        let close = format!("\"{}", "#".repeat(hashes));
+        Some(
+            rest[body..]
+                .find(&close)
+                .map_or(rest.len(), |end| body + end + close.len()),
+        )
+    }
+
+    /// Returns the length of a character literal (`'a'`, `'\n'`) at the
+    /// start of `rest`, in languages where a lone `'` means something else.
+    fn char_literal(&self, rest: &str) -> Option<usize> {
+        if !self.syntax.char_literals || !rest.starts_with('\'') {
+            return None;
+        }
+        let after = &rest[1..];
+        if after.starts_with('\\') {
+            // Escapes are short: `'\u{10FFFF}'` is the longest.
+            return after
+                .bytes()
+                .skip(2)
+                .take(10)
+                .position(|byte| byte == b'\'')
+                .map(|end| end + 4);
+        }
+        let ch = after.chars().next().filter(|&ch| ch != '\'')?;
+        after[ch.len_utf8()..]
+            .starts_with('\'')
+            .then_some(ch.len_utf8() + 2)
+    }
+
+    /// Returns the length of a JavaScript regex literal at the start of
+    /// `rest`, if a `/` there can't be division.
+    fn regex_literal(&self, rest: &str) -> Option<usize> {
+        if !self.syntax.regex_literals || !rest.starts_with('/') {
+            return None;
+        }
+        let operand_expected = self
+            .out
+            .iter()
+            .rev()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_none_or(|byte| b"(,=:[!&|?{};+-*%<>~^".contains(byte));
+        if !operand_expected {
+            return None;
+        }
+
+        let bytes = rest.as_bytes();
+        let mut in_class = false;
+        let mut index = 1;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'\\' => index += 1,
+                b'\n' => return None,
+                b'[' => in_class = true,
+                b']' => in_class = false,
+                b'/' if !in_class => return Some(index + 1),
+                _ => {}
+            }
+            index += 1;
+        }
+        None
+    }
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(language: &str, text: &str) -> String {
+        let (stripped, lines) =
+            strip_comments(text, language).expect("language has a comment syntax");
+        assert_eq!(lines.len(), stripped.split_inclusive('\n').count());
+        stripped
+    }
+
+    #[test]
+    fn languages_without_a_syntax_are_left_alone() {
+        assert_eq!(strip_comments("# Title\n", "markdown"), None);
+        assert_eq!(strip_comments("x", ""), None);
+    }
+
+    #[test]
+    fn rust_keeps_strings_chars_and_lifetimes_and_drops_comment_lines() {
+        let text = "\
+// Copyright 2026 Example
+// SPDX-License-Identifier: MIT
+
+//! Crate docs.
+use std::fmt; // trailing
+
+/// Doc comment.
+fn f<'a>(s: &'a str) -> char {
+    let url = \"http://example.com/*x*/\"; /* block */
+    let raw = r#\"// \"still\" a string\"#;
+    /* outer /* nested */ still comment */
+    let quote = '\"';
+    let slash = '/';
+    if s.is_empty() { '\\'' } else { 'x' }
+}
+";
+        assert_eq!(
+            strip("rust", text),
+            "\
+use std::fmt;
+
+fn f<'a>(s: &'a str) -> char {
+    let url = \"http://example.com/*x*/\";
+    let raw = r#\"// \"still\" a string\"#;
+    let quote = '\"';
+    let slash = '/';
+    if s.is_empty() { '\\'' } else { 'x' }
+}
+"
+        );
+    }
+
+    #[test]
+    fn block_comments_keep_tokens_and_lines_apart() {
+        assert_eq!(strip("c", "int/**/x;\n"), "int x;\n");
+        assert_eq!(
+            strip("go", "x := 1 /* a\nb */ y := 2\n"),
+            "x := 1\n y := 2\n"
+        );
+        assert_eq!(strip("go", "s := `// raw`\n"), "s := `// raw`\n");
+    }
+
+    #[test]
+    fn javascript_tells_regex_literals_from_comments() {
+        let text =
+            "const re = /\"\\/\\//g; // strip\nconst t = `// ${a}`;\nconst half = a / 2; // div\n";
+        assert_eq!(
+            strip("javascript", text),
+            "const re = /\"\\/\\//g;\nconst t = `// ${a}`;\nconst half = a / 2;\n"
+        );
+    }
+
+    #[test]
+    fn python_keeps_docstrings_and_hashes_in_strings() {
+        let text = "#!/usr/bin/env python3\n# comment\ndef f():\n    \"\"\"Docstring # not a comment.\"\"\"\n    return '#' + \"#\"  # trailing\n";
+        assert_eq!(
+            strip("python", text),
+            "#!/usr/bin/env python3\ndef f():\n    \"\"\"Docstring # not a comment.\"\"\"\n    return '#' + \"#\"\n"
+        );
+    }
+
+    #[test]
+    fn bash_and_yaml_comments_start_a_word() {
+        assert_eq!(
+            strip("bash", "echo $# ${#x} a#b 'c # d' # note\n"),
+            "echo $# ${#x} a#b 'c # d'\n"
+        );
+        assert_eq!(
+            strip("yaml", "title: it's a#b # note\nurl: \"x # y\"\n"),
+            "title: it's a#b\nurl: \"x # y\"\n"
+        );
+    }
+
+    #[test]
+    fn haskell_keeps_pragmas_operators_and_primes() {
+        let text = "{-# LANGUAGE GADTs #-}\n{- block {- nested -} -}\nf x' = x' --> 'a' -- note\n";
+        assert_eq!(
+            strip("haskell", text),
+            "{-# LANGUAGE GADTs #-}\nf x' = x' --> 'a'\n"
+        );
+    }
+
+    #[test]
+    fn other_families_strip_their_own_markers() {
+        assert_eq!(
+            strip("sql", "SELECT '--' -- c\n/* b */ FROM t;\n"),
+            "SELECT '--'\n FROM t;\n"
+        );
+        assert_eq!(
+            strip("lua", "--[[ block\n]] x = \"--\" -- c\n"),
+            " x = \"--\"\n"
+        );
+        assert_eq!(
+            strip("html", "<p>a<!-- c -->b</p>\n<!--\n-->\n"),
+            "<p>a b</p>\n"
+        );
+        assert_eq!(
+            strip("php", "#[Route('/')]\n# c\n$a = 1; // c\n"),
+            "#[Route('/')]\n$a = 1;\n"
+        );
+    }
+
+    #[test]
+    fn non_ascii_text_is_kept_whole() {
+        assert_eq!(
+            strip("rust", "let s = \"é\\é\"; /* ünï */ // ß\nlet c = 'é';\n"),
+            "let s = \"é\\é\";\nlet c = 'é';\n"
+        );
+    }
+
+    #[test]
+    fn lines_map_back_to_the_lines_they_came_from() {
+        let text = "// header\n\nfn a() { /* x\n y */ b(\"1\n2\") }\n// c\nfn z() {}";
+        let (stripped, lines) = strip_comments(text, "rust").unwrap();
+        assert_eq!(stripped, "fn a() {\n b(\"1\n2\") }\nfn z() {}");
+        assert_eq!(lines, [3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn crlf_line_endings_are_kept() {
+        assert_eq!(strip("c", "// c\r\nint x; // c\r\n"), "int x;\r\n");
+    }
+}
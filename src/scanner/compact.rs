@@ -0,0 +1,140 @@
+//! The `config.compact` transform: collapses runs of blank lines into one,
+//! drops blank lines at the start and end of a file, trims trailing
+//! whitespace, and, with `config.compact_tabs`, turns leading spaces into
+//! tabs, so spacing spends fewer tokens.
+//!
+//! Languages whose whitespace is syntax (Python, YAML, Makefiles, Haskell,
+//! Sass, and Markdown, where two trailing spaces break a line, by the tag
+//! [`fence_language`] gives them) are left untouched. Elsewhere, the
+//! lines of a multi-line string literal are compacted like any other: the
+//! code still reads the same, but the literal may not hold exactly the same
+//! text.
+//!
+//! [`fence_language`]: super::lang::fence_language
+
+/// Languages whose indentation or trailing whitespace is significant.
+const INDENTATION_SENSITIVE: &[&str] =
+    &["python", "yaml", "makefile", "haskell", "sass", "markdown"];
+
+/// Returns `text` compacted, along with the 1-based line of `text` each of
+/// its lines came from, or `None` if `language` (a [`fence_language`]
+/// tag) is indentation-sensitive. With `tab_width`, each run of that many
+/// spaces at the start of a line becomes a tab; spaces left over, and lines
+/// already indented with tabs, are kept as they are.
+///
+/// Line endings (`\n` or `\r\n`) are kept, and a final newline is kept only
+/// if `text` had one.
+///
+/// [`fence_language`]: super::lang::fence_language
+pub(crate) fn compact(
+    text: &str,
+    language: &str,
+    tab_width: Option<u64>,
+) -> Option<(String, Vec<usize>)> {
+    if INDENTATION_SENSITIVE.contains(&language) {
+        return None;
+    }
+    let tab_width = tab_width
+        .filter(|&width| width > 0)
+        .map(|width| width as usize);
+
+    let mut out = String::with_capacity(text.len());
+    let mut lines = Vec::new();
+    // The line ending and number of the blank line to write before the next
+    // non-blank one, if a run of blank lines is pending.
+    let mut blank = None;
+    for (number, line) in (1..).zip(text.split_inclusive('\n')) {
+        let (content, newline) = match line.strip_suffix("\r\n") {
+            Some(content) => (content, "\r\n"),
+            None => line
+                .strip_suffix('\n')
+                .map_or((line, ""), |content| (content, "\n")),
+        };
+        let content = content.trim_end();
+        if content.is_empty() {
+            if !out.is_empty() && blank.is_none() {
+                blank = Some((newline, number));
+            }
+            continue;
+        }
+
+        if let Some((newline, number)) = blank.take() {
+            out.push_str(newline);
+            lines.push(number);
+        }
+        lines.push(number);
+        let indent = content.len() - content.trim_start_matches(' ').len();
+        match tab_width {
+            Some(width) if indent >= width => {
+                out.extend(std::iter::repeat_n('\t', indent / width));
+                out.extend(std::iter::repeat_n(' ', indent % width));
+                out.push_str(&content[indent..]);
+            }
+            _ => out.push_str(content),
+        }
+        out.push_str(newline);
+    }
+    Some((out, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_blank_runs_and_trims_trailing_whitespace() {
+        let text = "\n \nfn a() {  \n    1\n}\n\n\t\n\nfn b() {}\n\n\n";
+        let (compacted, lines) = compact(text, "rust", None).unwrap();
+        assert_eq!(compacted, "fn a() {\n    1\n}\n\nfn b() {}\n");
+        assert_eq!(lines, [3, 4, 5, 6, 9]);
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings_and_a_missing_final_newline() {
+        assert_eq!(
+            compact("a \r\n\r\n\r\nb", "c", None).unwrap().0,
+            "a\r\n\r\nb"
+        );
+    }
+
+    #[test]
+    fn tab_width_turns_leading_space_runs_into_tabs() {
+        let text = "a\n    b\n      c\n\td\n  e  f\n";
+        assert_eq!(
+            compact(text, "go", Some(4)).unwrap().0,
+            "a\n\tb\n\t  c\n\td\n  e  f\n"
+        );
+        assert_eq!(compact(text, "go", Some(0)), compact(text, "go", None));
+    }
+
+    #[test]
+    fn indentation_sensitive_languages_are_left_alone() {
+        for language in ["python", "yaml", "makefile"] {
+            assert_eq!(compact("a:\n\n\n    b  \n", language, Some(4)), None);
+        }
+        assert!(compact("x", "", None).is_some());
+    }
+
+    #[test]
+    fn haskell_layout_keeps_its_spaces() {
+        // GHC takes a tab to the next multiple of 8 columns, so at width 4
+        // the two tabs before `y` would put it at column 17, out of the
+        // `let` block.
+        let text = "f = let x = 1\n        y = 2\n    in x + y\n";
+        assert_eq!(compact(text, "haskell", Some(4)), None);
+    }
+
+    #[test]
+    fn sass_indentation_keeps_its_spaces() {
+        let text = "nav\n  ul\n    margin: 0\n\n\n  li\n    display: inline\n";
+        assert_eq!(compact(text, "sass", Some(2)), None);
+    }
+
+    #[test]
+    fn markdown_hard_line_breaks_keep_their_trailing_spaces() {
+        assert_eq!(
+            compact("first line  \nsecond line\n", "markdown", None),
+            None
+        );
+    }
+}
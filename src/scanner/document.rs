@@ -0,0 +1,182 @@
+//! The format-independent result of a scan: the rendered trees, every file
+//! that made it into the output, and the run's [`ScanStats`], handed as one
+//! unit to whichever writer `config.output_format` selects.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::git::Commit;
+use crate::tokens::Tokenizer;
+
+use super::lang::path_language;
+use super::scan::ScanStats;
+
+/// Tree text written in place of a real tree when the scanned directory has
+/// no entries at all.
+pub(crate) const EMPTY_TREE: &str = "The directory is empty.\n";
+
+/// One file's decoded contents, ready to be written in any output format.
+pub(crate) struct FileBlock {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Path shown in the file's heading: relative to the root it was found
+    /// under, behind that root's label when a run has several roots.
+    pub display_path: PathBuf,
+    /// File size in bytes, as `stat`'d during the walk.
+    pub size: u64,
+    /// The file's full contents, already validated as UTF-8 (and without
+    /// comments, with `config.strip_comments`).
+    pub text: String,
+    /// Tokens in `text` as written, line-number gutters included (see
+    /// [`FileBlock::count_tokens`]), as counted by `config.tokenizer`.
+    pub tokens: u64,
+    /// Which piece of the file `text` is, when a file bigger than one
+    /// output chunk had to be split across several; `None` for a whole file.
+    pub part: Option<Part>,
+    /// 1-based numbers of the lines `config.grep` matched on, with
+    /// `config.grep_lines`; empty otherwise.
+    pub matched_lines: Vec<usize>,
+    /// With `config.line_numbers`, the 1-based line of the file each line
+    /// of `text` is (lines `config.strip_comments` or `config.compact`
+    /// dropped leave gaps); empty otherwise.
+    pub line_numbers: Vec<usize>,
+    /// Bytes `config.strip_comments` removed from the whole file; `0`
+    /// without it. A file split across chunks keeps its count on every piece.
+    pub stripped_size: u64,
+    /// Secrets `config.redact` replaced in the whole file. A file split
+    /// across chunks keeps its count on every piece.
+    pub redactions: u64,
+}
+
+/// Position of one piece in a numbered sequence: a file split across
+/// chunks, or a chunk among a run's chunks. Both numbers are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct Part {
+    /// This piece's number, from 1.
+    pub index: usize,
+    /// How many pieces there are in total.
+    pub count: usize,
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {}", self.index, self.count)
+    }
+}
+
+impl FileBlock {
+    /// Returns the language tag inferred from `path`'s extension via
+    /// [`path_language`], or `""` when unrecognized.
+    pub(crate) fn language(&self) -> &'static str {
+        path_language(&self.path)
+    }
+
+    /// Returns `line_numbers` as comma-separated ranges (`1-12, 15-40`).
+    pub(crate) fn line_ranges(&self) -> String {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &line in &self.line_numbers {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == line => *last = line,
+                _ => ranges.push((line, line)),
+            }
+        }
+        ranges
+            .into_iter()
+            .map(|(first, last)| {
+                if first == last {
+                    first.to_string()
+                } else {
+                    format!("{first}-{last}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns `text` with each line prefixed by its number from
+    /// `line_numbers`, right-aligned to the widest one (`  9 | `), as
+    /// Markdown output shows it. Every line of the text is kept: one
+    /// without a number gets a blank gutter.
+    pub(crate) fn numbered_text(&self) -> String {
+        let width = self
+            .line_numbers
+            .iter()
+            .max()
+            .map_or(1, |max| max.to_string().len());
+        let mut text =
+            String::with_capacity(self.text.len() + self.line_numbers.len() * (width + 3));
+        for (index, line) in self.text.split_inclusive('\n').enumerate() {
+            let number = self
+                .line_numbers
+                .get(index)
+                .map_or_else(String::new, ToString::to_string);
+            if line.trim_end_matches(['\r', '\n']).is_empty() {
+                text.push_str(&format!("{number:>width$} |{line}"));
+            } else {
+                text.push_str(&format!("{number:>width$} | {line}"));
+            }
+        }
+        text
+    }
+
+    /// Counts [`FileBlock::tokens`] for the text as it will be written: with
+    /// `numbered` (see [`numbers_inline`](super::process::numbers_inline))
+    /// and `line_numbers`, that's [`FileBlock::numbered_text`].
+    pub(crate) fn count_tokens(&self, tokenizer: Tokenizer, numbered: bool) -> u64 {
+        if numbered && !self.line_numbers.is_empty() {
+            tokenizer.count(&self.numbered_text())
+        } else {
+            tokenizer.count(&self.text)
+        }
+    }
+
+    /// Returns `matched_lines` as a comma-separated list (`3, 17, 42`).
+    pub(crate) fn matched_lines_list(&self) -> String {
+        self.matched_lines
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Everything a single output file is rendered from.
+pub(crate) struct Document {
+    /// One rendered tree per input root (see [`super::tree::render_tree`]),
+    /// or just [`EMPTY_TREE`] for a single, empty directory.
+    pub trees: Vec<String>,
+    /// Files to write, in walk order. Always empty for `config.tree_only`.
+    pub files: Vec<FileBlock>,
+    /// Byte and token breakdown of the run.
+    pub stats: ScanStats,
+    /// Whether Markdown headings show each file's token count
+    /// (`config.heading_tokens`).
+    pub heading_tokens: bool,
+    /// This document's position among the run's chunks when
+    /// `config.chunk_size` split the output; `None` for unchunked output.
+    pub chunk: Option<Part>,
+    /// The unified diff for `config.diff`, written after the files; `None`
+    /// without `config.diff`, and in every chunk but the last.
+    pub diff: Option<String>,
+    /// The `config.git_log` commits, newest first, written last; `None`
+    /// without `config.git_log`, and in every chunk but the last.
+    pub commits: Option<Vec<Commit>>,
+}
+
+/// Renders `commits` as the plain-text `- Recent Commits` listing: one
+/// `hash date subject` line per commit, then its changed paths, indented.
+pub(crate) fn render_commits(commits: &[Commit]) -> String {
+    let mut text = String::new();
+    for commit in commits {
+        text.push_str(&format!(
+            "{} {} {}\n",
+            commit.hash, commit.date, commit.subject
+        ));
+        for path in &commit.paths {
+            text.push_str(&format!("  {path}\n"));
+        }
+    }
+    text
+}
@@ -0,0 +1,248 @@
+//! Reads `config.files_from`, an explicit list of files, in place of the
+//! walk.
+//!
+//! The list is newline- or NUL-separated (NUL as soon as the list holds
+//! one, so `rg -l0` and `git ls-files -z` output works as is), read from a
+//! file or, for `-`, from stdin. Each path is relative to
+//! `config.directory`, or absolute and inside it, and becomes an [`Entry`]
+//! at the same path a walk would have given it, along with the directories
+//! leading to it, so the tree shows just the listed files. Only the output
+//! file itself is left out: the walk's name, extension, glob, and ignore
+//! rules don't apply to a list the caller chose file by file.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::{FyaiError, Result};
+
+use super::collect::Entry;
+use super::filter::PathFilter;
+
+/// Returns an [`Entry`] for every file `list` names, plus their ancestor
+/// directories, in the preorder a walk produces. Repeated paths are listed
+/// once.
+///
+/// # Errors
+///
+/// Fails with [`FyaiError::InvalidValue`] for a path outside
+/// `config.directory`, and with an I/O error naming the path when the list
+/// can't be read or a listed path isn't a readable file.
+pub(crate) fn listed_entries(config: &Config, list: &Path) -> Result<Vec<Entry>> {
+    let list = read_list(list)?;
+    let filter = PathFilter::new(config)?;
+    let root = fs::canonicalize(&config.directory)?;
+
+    let mut entries = BTreeMap::new();
+    for listed in split_list(&list) {
+        let relative =
+            relative_path(config, &root, listed).ok_or_else(|| FyaiError::InvalidValue {
+                key: "files_from",
+                value: listed.to_string(),
+            })?;
+        let path = config.directory.join(&relative);
+        let metadata = fs::metadata(&path)
+            .map_err(|err| io::Error::new(err.kind(), format!("listed file {listed}: {err}")))?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("listed file {listed} is not a file"),
+            )
+            .into());
+        }
+        if filter.is_output_path(&path) {
+            continue;
+        }
+
+        let mut directory = config.directory.clone();
+        let components: Vec<_> = relative.components().collect();
+        for (depth, component) in components[..components.len() - 1].iter().enumerate() {
+            directory.push(component);
+            entries.entry(directory.clone()).or_insert_with(|| Entry {
+                path: directory.clone(),
+                depth: depth + 1,
+                is_dir: true,
+                size: None,
+            });
+        }
+        entries.insert(
+            path.clone(),
+            Entry {
+                path,
+                depth: components.len(),
+                is_dir: false,
+                size: Some(metadata.len()),
+            },
+        );
+    }
+    Ok(entries.into_values().collect())
+}
+
+/// Reads the list at `list`, or stdin for `-`.
+fn read_list(list: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    if list.as_os_str() == "-" {
+        io::stdin().read_to_string(&mut contents)?;
+    } else {
+        contents = fs::read_to_string(list).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to read file list {}: {err}", list.display()),
+            )
+        })?;
+    }
+    Ok(contents)
+}
+
+/// Splits `list` on NULs if it has any, else on line endings, dropping
+/// empty entries.
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    let separator = if list.contains('\0') { '\0' } else { '\n' };
+    list.split(separator)
+        .map(|path| path.strip_suffix('\r').unwrap_or(path))
+        .filter(|path| !path.is_empty())
+}
+
+/// Returns `listed` relative to `config.directory` (whose canonical form is
+/// `root`), or `None` if it isn't a file path inside it.
+fn relative_path(config: &Config, root: &Path, listed: &str) -> Option<PathBuf> {
+    let path = Path::new(listed);
+    let path = if path.is_absolute() {
+        path.strip_prefix(root)
+            .or_else(|_| path.strip_prefix(&config.directory))
+            .ok()?
+    } else {
+        path
+    };
+
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_list(dir: &Path, list: &str) -> PathBuf {
+        let path = dir.join("files.txt");
+        fs::write(&path, list).unwrap();
+        path
+    }
+
+    #[test]
+    fn split_list_uses_nuls_when_there_are_any() {
+        let paths: Vec<&str> = split_list("a b.rs\0src/c.rs\0\0").collect();
+        assert_eq!(paths, ["a b.rs", "src/c.rs"]);
+
+        let paths: Vec<&str> = split_list("a.rs\r\n\nsrc/c.rs\n").collect();
+        assert_eq!(paths, ["a.rs", "src/c.rs"]);
+    }
+
+    #[test]
+    fn relative_path_keeps_paths_inside_the_directory() {
+        let config = Config::builder().directory("/work/project").build();
+        let root = Path::new("/work/project");
+        let relative = |listed| relative_path(&config, root, listed);
+
+        assert_eq!(
+            relative("./src/main.rs"),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(
+            relative("/work/project/src/main.rs"),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(relative("../other/main.rs"), None);
+        assert_eq!(relative("src/../main.rs"), None);
+        assert_eq!(relative("/work/other/main.rs"), None);
+        assert_eq!(relative("."), None);
+    }
+
+    #[test]
+    fn listed_entries_add_ancestor_directories_in_preorder() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("src/bin")).unwrap();
+        fs::write(project.join("src/bin/cli.rs"), "cli").unwrap();
+        fs::write(project.join("src/lib.rs"), "lib").unwrap();
+        fs::write(project.join("README.md"), "readme").unwrap();
+        fs::write(project.join("unlisted.rs"), "unlisted").unwrap();
+        let list = write_list(
+            dir.path(),
+            &format!(
+                "src/lib.rs\n{}\nREADME.md\nsrc/lib.rs\n",
+                project.join("src/bin/cli.rs").display()
+            ),
+        );
+
+        let config = Config::builder().directory(&project).build();
+        let entries = listed_entries(&config, &list).unwrap();
+        let summary: Vec<(PathBuf, usize, Option<u64>)> = entries
+            .into_iter()
+            .map(|entry| (entry.path, entry.depth, entry.size))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (project.join("README.md"), 1, Some(6)),
+                (project.join("src"), 1, None),
+                (project.join("src/bin"), 2, None),
+                (project.join("src/bin/cli.rs"), 3, Some(3)),
+                (project.join("src/lib.rs"), 2, Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn listed_entries_reject_missing_outside_and_directory_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        let config = Config::builder().directory(dir.path()).build();
+
+        let list = write_list(dir.path(), "gone.rs\n");
+        let err = listed_entries(&config, &list).unwrap_err();
+        assert!(err.to_string().starts_with("listed file gone.rs: "));
+
+        let list = write_list(dir.path(), "../outside.rs\n");
+        let err = listed_entries(&config, &list).unwrap_err();
+        assert!(matches!(
+            err,
+            FyaiError::InvalidValue {
+                key: "files_from",
+                ..
+            }
+        ));
+
+        let list = write_list(dir.path(), "src\n");
+        let err = listed_entries(&config, &list).unwrap_err();
+        assert_eq!(err.to_string(), "listed file src is not a file");
+    }
+
+    #[test]
+    fn listed_entries_skip_the_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "main").unwrap();
+        fs::write(dir.path().join("fyai.txt"), "old output").unwrap();
+        let list = write_list(dir.path(), "main.rs\nfyai.txt\n");
+
+        let config = Config::builder()
+            .directory(dir.path())
+            .output(dir.path().join("fyai.txt"))
+            .build();
+        let paths: Vec<PathBuf> = listed_entries(&config, &list)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(paths, [dir.path().join("main.rs")]);
+    }
+}
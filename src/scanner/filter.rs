@@ -3,9 +3,14 @@
 
 use std::collections::HashSet;
 use std::ffi::OsString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 
 use crate::config::Config;
+use crate::error::{FyaiError, Result};
+
+use super::chunk::{manifest_path, previous_chunks};
 
 /// Decides whether a walked path should be included in the scan, based on
 /// the output file's own path and the configured include/exclude filters.
@@ -19,21 +24,50 @@ pub struct PathFilter<'a> {
     /// `config.output`'s file name, checked before falling back to
     /// [`same_file::is_same_file`] in [`PathFilter::is_output_path`].
     output_file_name: Option<OsString>,
+    /// With `config.chunk_size`, the chunks the previous run's manifest
+    /// lists, and the manifest itself.
+    chunk_paths: Vec<PathBuf>,
     /// Lower-cased, set-based view of `config`'s include/exclude lists.
     normalized_filters: NormalizedFilterConfig,
+    /// Compiled `config.include_globs`.
+    include_globs: Option<GlobSet>,
+    /// Compiled `config.exclude_globs`.
+    exclude_globs: Option<GlobSet>,
 }
 
 impl<'a> PathFilter<'a> {
     /// Creates a filter for `config`.
-    pub fn new(config: &'a Config) -> Self {
-        let output_file_name = config.output.file_name().map(|name| name.to_os_string());
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FyaiError::InvalidGlob`] if `config.include_globs` or
+    /// `config.exclude_globs` holds a pattern that isn't a valid glob, or
+    /// [`FyaiError::Io`] if a chunk manifest exists but can't be read.
+    pub fn new(config: &'a Config) -> Result<Self> {
+        let output_file_name = config
+            .output
+            .file_name()
+            .filter(|_| !config.output_is_stdout())
+            .map(|name| name.to_os_string());
+        let chunk_paths = if config.chunk_size.is_some() && !config.output_is_stdout() {
+            let mut paths = previous_chunks(&config.output)?;
+            paths.push(manifest_path(&config.output));
+            paths
+        } else {
+            Vec::new()
+        };
         let normalized_filters = NormalizedFilterConfig::new(config);
+        let include_globs = build_glob_set("include_globs", &config.include_globs)?;
+        let exclude_globs = build_glob_set("exclude_globs", &config.exclude_globs)?;
 
-        Self {
+        Ok(Self {
             config,
             output_file_name,
+            chunk_paths,
             normalized_filters,
-        }
+            include_globs,
+            exclude_globs,
+        })
     }
 
     /// Returns whether `path` should be walked into (if a directory) or
@@ -45,10 +79,14 @@ impl<'a> PathFilter<'a> {
         if !self.is_dir_allowed(path) {
             return false;
         }
+        let relative = path.strip_prefix(&self.config.directory).unwrap_or(path);
+        if self.matches_excluded_glob(relative) {
+            return false;
+        }
         if is_dir {
             return true;
         }
-        self.is_file_allowed(path)
+        self.is_file_allowed(path) && self.matches_included_glob(relative)
     }
 
     /// Returns true if `path` is the run's own output file, which must never
@@ -59,7 +97,10 @@ impl<'a> PathFilter<'a> {
     /// pays for [`same_file::is_same_file`]'s two `stat` calls, which is far
     /// cheaper than canonicalizing (resolving every symlink in) both full
     /// paths on every single entry.
-    fn is_output_path(&self, path: &Path) -> bool {
+    pub fn is_output_path(&self, path: &Path) -> bool {
+        if self.is_chunk_path(path) {
+            return true;
+        }
         if path.file_name() != self.output_file_name.as_deref() {
             return false;
         }
@@ -67,6 +108,16 @@ impl<'a> PathFilter<'a> {
             .unwrap_or_else(|_| path == self.config.output)
     }
 
+    /// Returns true if `path` is one of the chunks (or the chunk manifest)
+    /// an earlier `config.chunk_size` run wrote next to `config.output`.
+    /// Files that are merely named like chunks are scanned as usual.
+    fn is_chunk_path(&self, path: &Path) -> bool {
+        self.chunk_paths.iter().any(|chunk| {
+            path.file_name() == chunk.file_name()
+                && same_file::is_same_file(path, chunk).unwrap_or(false)
+        })
+    }
+
     fn is_dir_allowed(&self, path: &Path) -> bool {
         if !self.matches_included_dir(path) {
             return false;
@@ -91,6 +142,29 @@ impl<'a> PathFilter<'a> {
         }
     }
 
+    /// Returns true if `relative`, or any directory above it, matches
+    /// `config.exclude_globs`, so `generated` excludes everything under a
+    /// top-level `generated/` as well as the directory itself.
+    fn matches_excluded_glob(&self, relative: &Path) -> bool {
+        let Some(excludes) = &self.exclude_globs else {
+            return false;
+        };
+        relative
+            .ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| excludes.is_match(ancestor))
+    }
+
+    /// Returns true if file path `relative` matches `config.include_globs`,
+    /// or no include globs are set. Directories are never checked against
+    /// these, since a file deeper down may still match.
+    fn matches_included_glob(&self, relative: &Path) -> bool {
+        match &self.include_globs {
+            Some(includes) => includes.is_match(relative),
+            None => true,
+        }
+    }
+
     fn is_file_allowed(&self, path: &Path) -> bool {
         if !self.file_name_allowed(path) {
             return false;
@@ -189,6 +263,27 @@ fn normalize_list(list: &Option<Vec<String>>) -> Option<HashSet<String>> {
         .map(|items| items.iter().map(|item| item.to_lowercase()).collect())
 }
 
+/// Compiles `globs` into one case-insensitive [`GlobSet`], or returns `None`
+/// if `globs` is `None`. As in a shell, `*` and `?` stop at `/`; `**`
+/// crosses directories.
+fn build_glob_set(key: &'static str, globs: &Option<Vec<String>>) -> Result<Option<GlobSet>> {
+    let Some(globs) = globs else {
+        return Ok(None);
+    };
+    let invalid = |source| FyaiError::InvalidGlob { key, source };
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = GlobBuilder::new(glob)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(invalid)?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(invalid)
+}
+
 /// Returns true if any path component of `path`, lower-cased, is in `set`.
 fn any_component_in_set(path: &Path, set: &HashSet<String>) -> bool {
     path.components().any(|component| {
@@ -203,6 +298,7 @@ fn any_component_in_set(path: &Path, set: &HashSet<String>) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ChunkSize;
     use std::fs;
     use std::path::PathBuf;
 
@@ -210,25 +306,7 @@ mod tests {
     /// suggested defaults. Individual tests override only the fields they
     /// care about.
     fn base_config() -> Config {
-        Config {
-            directory: PathBuf::from("."),
-            output: PathBuf::from("fyai.txt"),
-            include_dirs: None,
-            exclude_dirs: None,
-            include_ext: None,
-            exclude_ext: None,
-            include_files: None,
-            exclude_files: None,
-            min_size: None,
-            max_size: None,
-            hidden: true,
-            gitignore: true,
-            ignore_files: true,
-            git_global: true,
-            follow_links: false,
-            tree_only: false,
-            human: false,
-        }
+        Config::default()
     }
 
     // ---- is_output_path ------------------------------------------------
@@ -241,12 +319,49 @@ mod tests {
 
         let mut config = base_config();
         config.output = output_path.clone();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.is_output_path(&output_path));
         assert!(!filter.allows_entry(&output_path, false));
     }
 
+    #[test]
+    fn is_output_path_true_for_manifest_chunks_only_when_chunking() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk = dir.path().join("fyai.002.txt");
+        let lookalike = dir.path().join("fyai.2024.txt");
+        let unrelated = dir.path().join("fyai.v2.txt");
+        let manifest = dir.path().join(".fyai.txt.chunks");
+        fs::write(&chunk, b"chunk").unwrap();
+        fs::write(&lookalike, b"other").unwrap();
+        fs::write(&unrelated, b"other").unwrap();
+        fs::write(&manifest, b"fyai.001.txt\nfyai.002.txt\n").unwrap();
+
+        let mut config = base_config();
+        config.output = dir.path().join("fyai.txt");
+        assert!(!PathFilter::new(&config).unwrap().is_output_path(&chunk));
+
+        config.chunk_size = Some(ChunkSize::Bytes(1000));
+        let filter = PathFilter::new(&config).unwrap();
+        assert!(filter.is_output_path(&chunk));
+        assert!(filter.is_output_path(&manifest));
+        assert!(!filter.is_output_path(&lookalike));
+        assert!(!filter.is_output_path(&unrelated));
+    }
+
+    #[test]
+    fn is_output_path_false_for_a_file_named_dash_when_writing_to_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let dash = dir.path().join("-");
+        fs::write(&dash, b"not the output").unwrap();
+
+        let mut config = base_config();
+        config.output = PathBuf::from("-");
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(!filter.is_output_path(&dash));
+    }
+
     #[test]
     fn is_output_path_false_for_a_differently_named_file() {
         let dir = tempfile::tempdir().unwrap();
@@ -257,7 +372,7 @@ mod tests {
 
         let mut config = base_config();
         config.output = output_path;
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.is_output_path(&other_path));
     }
@@ -273,7 +388,7 @@ mod tests {
 
         let mut config = base_config();
         config.output = output_path;
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         // Same file name, but `same_file::is_same_file` should determine
         // they're genuinely different files on disk.
@@ -286,7 +401,7 @@ mod tests {
     fn matches_included_dir_allows_paths_containing_the_component() {
         let mut config = base_config();
         config.include_dirs = Some(vec!["src".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.is_dir_allowed(Path::new("project/src/scanner")));
         assert!(!filter.is_dir_allowed(Path::new("project/tests")));
@@ -296,7 +411,7 @@ mod tests {
     fn matches_included_dir_is_case_insensitive() {
         let mut config = base_config();
         config.include_dirs = Some(vec!["src".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.is_dir_allowed(Path::new("project/SRC/scanner")));
     }
@@ -305,7 +420,7 @@ mod tests {
     fn matches_ignored_dir_excludes_paths_containing_the_component() {
         let mut config = base_config();
         config.exclude_dirs = Some(vec!["node_modules".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.is_dir_allowed(Path::new("project/node_modules/pkg")));
         assert!(filter.is_dir_allowed(Path::new("project/src")));
@@ -316,7 +431,7 @@ mod tests {
         let mut config = base_config();
         config.include_dirs = Some(vec!["src".into()]);
         config.exclude_dirs = Some(vec!["node_modules".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         // Matches the include list (`src`) AND the exclude list
         // (`node_modules`): exclude must win.
@@ -331,7 +446,7 @@ mod tests {
     #[test]
     fn dir_allowed_by_default_when_no_dir_filters_set() {
         let config = base_config();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.is_dir_allowed(Path::new("anything/goes")));
     }
@@ -342,7 +457,7 @@ mod tests {
     fn file_name_allowed_matches_include_list_case_insensitively() {
         let mut config = base_config();
         config.include_files = Some(vec!["readme.md".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.file_name_allowed(Path::new("README.md")));
         assert!(!filter.file_name_allowed(Path::new("other.md")));
@@ -352,7 +467,7 @@ mod tests {
     fn file_name_allowed_matches_exclude_list_case_insensitively() {
         let mut config = base_config();
         config.exclude_files = Some(vec!["secret.txt".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.file_name_allowed(Path::new("SECRET.txt")));
         assert!(filter.file_name_allowed(Path::new("public.txt")));
@@ -363,7 +478,7 @@ mod tests {
         let mut config = base_config();
         config.include_files = Some(vec!["readme.md".into()]);
         config.exclude_files = Some(vec!["readme.md".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.file_name_allowed(Path::new("readme.md")));
     }
@@ -371,7 +486,7 @@ mod tests {
     #[test]
     fn file_name_allowed_by_default_when_neither_list_set() {
         let config = base_config();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.file_name_allowed(Path::new("anything.rs")));
     }
@@ -380,7 +495,7 @@ mod tests {
     fn extension_allowed_matches_include_list_without_the_dot_case_insensitively() {
         let mut config = base_config();
         config.include_ext = Some(vec!["rs".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.extension_allowed(Path::new("main.RS")));
         assert!(!filter.extension_allowed(Path::new("main.py")));
@@ -390,7 +505,7 @@ mod tests {
     fn extension_allowed_matches_exclude_list_case_insensitively() {
         let mut config = base_config();
         config.exclude_ext = Some(vec!["log".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.extension_allowed(Path::new("run.LOG")));
         assert!(filter.extension_allowed(Path::new("run.rs")));
@@ -401,7 +516,7 @@ mod tests {
         let mut config = base_config();
         config.include_ext = Some(vec!["rs".into()]);
         config.exclude_ext = Some(vec!["rs".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.extension_allowed(Path::new("main.rs")));
     }
@@ -409,7 +524,7 @@ mod tests {
     #[test]
     fn extension_allowed_by_default_when_neither_list_set() {
         let config = base_config();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.extension_allowed(Path::new("main.rs")));
     }
@@ -418,14 +533,14 @@ mod tests {
     fn extension_allowed_treats_missing_extension_as_empty_string() {
         let mut config = base_config();
         config.include_ext = Some(vec!["rs".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         // No extension at all: doesn't match the include list.
         assert!(!filter.extension_allowed(Path::new("Makefile")));
 
         let mut config_empty_include = base_config();
         config_empty_include.include_ext = Some(vec!["".into()]);
-        let filter_empty = PathFilter::new(&config_empty_include);
+        let filter_empty = PathFilter::new(&config_empty_include).unwrap();
         assert!(filter_empty.extension_allowed(Path::new("Makefile")));
     }
 
@@ -439,7 +554,7 @@ mod tests {
 
         let mut config = base_config();
         config.output = output_path.clone();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.allows_entry(&output_path, false));
     }
@@ -448,7 +563,7 @@ mod tests {
     fn allows_entry_short_circuits_true_for_allowed_directories() {
         let mut config = base_config();
         config.include_dirs = Some(vec!["src".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         // Directories only go through the dir-allowed check, never
         // file_name_allowed/extension_allowed.
@@ -459,7 +574,7 @@ mod tests {
     fn allows_entry_returns_false_for_directories_excluded_by_dir_filters() {
         let mut config = base_config();
         config.exclude_dirs = Some(vec!["node_modules".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(!filter.allows_entry(Path::new("project/node_modules"), true));
     }
@@ -468,7 +583,7 @@ mod tests {
     fn allows_entry_checks_file_filters_for_files() {
         let mut config = base_config();
         config.include_ext = Some(vec!["rs".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.allows_entry(Path::new("project/src/main.rs"), false));
         assert!(!filter.allows_entry(Path::new("project/src/main.py"), false));
@@ -479,7 +594,7 @@ mod tests {
         let mut config = base_config();
         config.include_dirs = Some(vec!["src".into()]);
         config.include_ext = Some(vec!["rs".into()]);
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         // File extension matches, but the directory doesn't contain `src`.
         assert!(!filter.allows_entry(Path::new("project/tests/main.rs"), false));
@@ -490,12 +605,100 @@ mod tests {
     #[test]
     fn allows_entry_allows_everything_by_default() {
         let config = base_config();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.allows_entry(Path::new("anything/at/all.rs"), false));
         assert!(filter.allows_entry(Path::new("anything/at/all"), true));
     }
 
+    // ---- include_globs / exclude_globs -----------------------------------
+
+    fn glob_config(include: &[&str], exclude: &[&str]) -> Config {
+        let mut builder = Config::builder().directory("project");
+        if !include.is_empty() {
+            builder = builder.include_globs(include.iter().copied());
+        }
+        if !exclude.is_empty() {
+            builder = builder.exclude_globs(exclude.iter().copied());
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn include_globs_match_paths_relative_to_the_directory() {
+        let config = glob_config(&["src/**/*.rs"], &[]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(filter.allows_entry(Path::new("project/src/main.rs"), false));
+        assert!(filter.allows_entry(Path::new("project/src/a/b/lib.rs"), false));
+        assert!(!filter.allows_entry(Path::new("project/src/notes.md"), false));
+        assert!(!filter.allows_entry(Path::new("project/tests/main.rs"), false));
+        // Directories are still walked so deeper files can match.
+        assert!(filter.allows_entry(Path::new("project/tests"), true));
+    }
+
+    #[test]
+    fn single_star_stops_at_directory_separators() {
+        let config = glob_config(&["*.rs"], &[]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(filter.allows_entry(Path::new("project/build.rs"), false));
+        assert!(!filter.allows_entry(Path::new("project/src/main.rs"), false));
+    }
+
+    #[test]
+    fn globs_are_case_insensitive_like_the_other_filters() {
+        let config = glob_config(&["SRC/*.RS"], &[]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(filter.allows_entry(Path::new("project/src/Main.rs"), false));
+    }
+
+    #[test]
+    fn exclude_globs_reject_matching_files_and_directories() {
+        let config = glob_config(&[], &["**/generated/**", "*.lock"]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(!filter.allows_entry(Path::new("project/src/generated/api.rs"), false));
+        assert!(!filter.allows_entry(Path::new("project/Cargo.lock"), false));
+        assert!(filter.allows_entry(Path::new("project/src/api.rs"), false));
+    }
+
+    #[test]
+    fn an_excluded_directory_takes_everything_under_it_along() {
+        let config = glob_config(&[], &["vendor"]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(!filter.allows_entry(Path::new("project/vendor"), true));
+        assert!(!filter.allows_entry(Path::new("project/vendor/lib/a.c"), false));
+        assert!(filter.allows_entry(Path::new("project/src/vendor.rs"), false));
+    }
+
+    #[test]
+    fn exclude_globs_win_over_include_globs_and_both_compose_with_lists() {
+        let mut config = glob_config(&["src/**"], &["**/*_test.rs"]);
+        config.exclude_ext = Some(vec!["md".to_string()]);
+        let filter = PathFilter::new(&config).unwrap();
+
+        assert!(filter.allows_entry(Path::new("project/src/lib.rs"), false));
+        assert!(!filter.allows_entry(Path::new("project/src/lib_test.rs"), false));
+        assert!(!filter.allows_entry(Path::new("project/src/README.md"), false));
+    }
+
+    #[test]
+    fn invalid_globs_are_reported_with_their_key() {
+        let config = glob_config(&[], &["src/[oops"]);
+        let err = PathFilter::new(&config).err().expect("invalid glob");
+
+        assert!(matches!(
+            err,
+            FyaiError::InvalidGlob {
+                key: "exclude_globs",
+                ..
+            }
+        ));
+    }
+
     // ---- normalize_list / NormalizedFilterConfig --------------------------
 
     #[test]
@@ -516,7 +719,7 @@ mod tests {
     #[test]
     fn all_filter_lists_none_means_nothing_is_filtered() {
         let config = base_config();
-        let filter = PathFilter::new(&config);
+        let filter = PathFilter::new(&config).unwrap();
 
         assert!(filter.allows_entry(Path::new("literally/anything.xyz"), false));
         assert!(filter.allows_entry(Path::new("literally/anything"), true));
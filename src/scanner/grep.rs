@@ -0,0 +1,140 @@
+//! The `config.grep` content filter, checked on each file's decoded text
+//! during the parallel read, so it costs no extra pass over the files.
+//!
+//! All patterns are compiled into one alternation, so a file is searched
+//! once however many `--grep` flags were given. A match may span lines (a
+//! pattern can use `(?s)` or `\n`); it's reported on the line it starts on.
+
+use regex::Regex;
+
+use crate::config::Config;
+use crate::error::{FyaiError, Result};
+
+/// `config.grep`, compiled, and what to do with its matches.
+pub(crate) struct Grep {
+    /// Every `config.grep` pattern, as one alternation.
+    regex: Regex,
+    /// `config.grep_invert`.
+    invert: bool,
+    /// `config.grep_lines`, unless inverted (a kept file then has no matches
+    /// to list).
+    lines: bool,
+}
+
+impl Grep {
+    /// Compiles `config.grep`, or returns `None` without it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FyaiError::InvalidRegex`] for a pattern that isn't a valid
+    /// regex.
+    pub(crate) fn new(config: &Config) -> Result<Option<Self>> {
+        let Some(patterns) = &config.grep else {
+            return Ok(None);
+        };
+        // Each pattern is compiled on its own first, so an error names just
+        // the pattern at fault rather than the whole alternation.
+        for pattern in patterns {
+            Regex::new(pattern).map_err(|source| FyaiError::InvalidRegex {
+                key: "grep",
+                source,
+            })?;
+        }
+        let alternation = patterns
+            .iter()
+            .map(|pattern| format!("(?:{pattern})"))
+            .collect::<Vec<_>>()
+            .join("|");
+        let regex = Regex::new(&alternation).map_err(|source| FyaiError::InvalidRegex {
+            key: "grep",
+            source,
+        })?;
+
+        Ok(Some(Self {
+            regex,
+            invert: config.grep_invert,
+            lines: config.grep_lines && !config.grep_invert,
+        }))
+    }
+
+    /// Returns `None` if `text` should be left out, else the 1-based
+    /// numbers of the lines a match starts on when headings list them
+    /// (empty otherwise).
+    pub(crate) fn check(&self, text: &str) -> Option<Vec<usize>> {
+        if !self.lines {
+            return (self.regex.is_match(text) != self.invert).then(Vec::new);
+        }
+
+        let mut lines = Vec::new();
+        let (mut line, mut counted_to) = (1, 0);
+        for found in self.regex.find_iter(text) {
+            line += text[counted_to..found.start()].matches('\n').count();
+            counted_to = found.start();
+            if lines.last() != Some(&line) {
+                lines.push(line);
+            }
+        }
+        (!lines.is_empty()).then_some(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grep(patterns: &[&str], invert: bool, lines: bool) -> Grep {
+        let config = Config::builder()
+            .grep(patterns.iter().copied())
+            .grep_invert(invert)
+            .grep_lines(lines)
+            .build();
+        Grep::new(&config).unwrap().unwrap()
+    }
+
+    const TEXT: &str = "use stripe::PaymentIntent;\n\nfn pay() {\n    PaymentIntent::create(); // PaymentIntent\n    refund();\n}\n";
+
+    #[test]
+    fn new_is_none_without_grep() {
+        assert!(Grep::new(&Config::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn new_rejects_an_invalid_pattern_by_name() {
+        let config = Config::builder().grep(["ok", "Payment("]).build();
+        let err = Grep::new(&config).err().unwrap();
+        assert!(matches!(err, FyaiError::InvalidRegex { key: "grep", .. }));
+        assert!(err.to_string().contains("Payment("));
+    }
+
+    #[test]
+    fn check_keeps_files_matching_any_pattern() {
+        assert_eq!(
+            grep(&["PaymentIntent"], false, false).check(TEXT),
+            Some(vec![])
+        );
+        assert_eq!(
+            grep(&["nope", "refund\\("], false, false).check(TEXT),
+            Some(vec![])
+        );
+        assert_eq!(grep(&["nope"], false, false).check(TEXT), None);
+    }
+
+    #[test]
+    fn check_inverted_keeps_files_matching_nothing() {
+        assert_eq!(grep(&["PaymentIntent"], true, true).check(TEXT), None);
+        assert_eq!(grep(&["nope"], true, true).check(TEXT), Some(vec![]));
+    }
+
+    #[test]
+    fn check_lists_each_matching_line_once() {
+        assert_eq!(
+            grep(&["PaymentIntent", "refund"], false, true).check(TEXT),
+            Some(vec![1, 4, 5])
+        );
+        assert_eq!(
+            grep(&["(?s)pay\\(\\).*refund"], false, true).check(TEXT),
+            Some(vec![3])
+        );
+        assert_eq!(grep(&["nope"], false, true).check(TEXT), None);
+    }
+}
@@ -0,0 +1,432 @@
+//! Writes the machine-readable output formats, for tooling that re-parses
+//! the combined output instead of feeding it straight to a model.
+//!
+//! - JSON: a single object, `{"tree": …, "files": [{"path", "size",
+//!   "language", "tokens", "content"}, …], "stats": {…}}`. With several
+//!   input roots, `tree` holds each root's tree in turn, a blank line apart.
+//! - JSONL: one record per line, each tagged with a `"type"`: a `tree`
+//!   record first (one per input root), then one `file` record per file,
+//!   then a closing `stats` record, so a consumer can stream files without
+//!   holding the whole output in memory.
+//!
+//! With `config.diff`, the JSON object gains a `diff` string and JSONL a
+//! `diff` record just before `stats`; with `config.git_log`, a `commits`
+//! array (`{"hash", "date", "subject", "paths"}`) and one `commit` record
+//! per commit, after any diff.
+//!
+//! With `config.grep_lines`, a file record gains a `matched_lines` array of
+//! the line numbers `config.grep` matched on; with `config.line_numbers`, a
+//! `line_numbers` array of the file line each line of `content` came from,
+//! leaving `content` itself unnumbered.
+//!
+//! Chunked output adds a `chunk` object (`{"index", "count"}`) to the JSON
+//! object and the JSONL `tree` records, and a file split across chunks gets
+//! a `part` object of the same shape.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::git::Commit;
+
+use super::document::{Document, FileBlock, Part};
+use super::scan::ScanStats;
+
+/// One file's entry in the `files` array (JSON) or `file` record (JSONL).
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    /// Path relative to the scanned directory, behind its root's label with
+    /// several roots.
+    path: String,
+    /// File size in bytes.
+    size: u64,
+    /// Fence language tag from `lang::fence_language`; `""` if unknown.
+    language: &'static str,
+    /// Tokens in `content`, as counted by the run's tokenizer.
+    tokens: u64,
+    /// Which piece of the file `content` is, if the file was split across
+    /// chunks; omitted for a whole file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part: Option<Part>,
+    /// 1-based numbers of the lines `config.grep` matched on, with
+    /// `config.grep_lines`; omitted otherwise.
+    #[serde(skip_serializing_if = "<[usize]>::is_empty")]
+    matched_lines: &'a [usize],
+    /// 1-based line of the file each line of `content` came from, with
+    /// `config.line_numbers`; omitted otherwise.
+    #[serde(skip_serializing_if = "<[usize]>::is_empty")]
+    line_numbers: &'a [usize],
+    /// The file's full contents.
+    content: &'a str,
+}
+
+impl<'a> FileRecord<'a> {
+    fn new(file: &'a FileBlock) -> Self {
+        Self {
+            path: file.display_path.display().to_string(),
+            size: file.size,
+            language: file.language(),
+            tokens: file.tokens,
+            part: file.part,
+            matched_lines: &file.matched_lines,
+            line_numbers: &file.line_numbers,
+            content: &file.text,
+        }
+    }
+}
+
+/// [`ScanStats`] plus its derived `size_filtered` bucket, so consumers don't
+/// have to recompute it.
+#[derive(Serialize)]
+struct StatsRecord {
+    total_size: u64,
+    written_size: u64,
+    binary_size: u64,
+    grep_filtered_size: u64,
+    size_filtered: u64,
+    budget_dropped_size: u64,
+    stripped_size: u64,
+    redactions: u64,
+    diff_size: u64,
+    tokens: u64,
+    tokenizer: &'static str,
+}
+
+impl From<&ScanStats> for StatsRecord {
+    fn from(stats: &ScanStats) -> Self {
+        Self {
+            total_size: stats.total_size,
+            written_size: stats.written_size,
+            binary_size: stats.binary_size,
+            grep_filtered_size: stats.grep_filtered_size,
+            size_filtered: stats.size_filtered(),
+            budget_dropped_size: stats.budget_dropped_size,
+            stripped_size: stats.stripped_size,
+            redactions: stats.redactions,
+            diff_size: stats.diff_size,
+            tokens: stats.tokens,
+            tokenizer: stats.tokenizer.name(),
+        }
+    }
+}
+
+/// The single JSON object.
+#[derive(Serialize)]
+struct JsonDocument<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunk: Option<Part>,
+    tree: String,
+    files: Vec<FileRecord<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commits: Option<&'a [Commit]>,
+    stats: StatsRecord,
+}
+
+/// One JSONL line, tagged with its `"type"`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonlRecord<'a> {
+    Tree {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        chunk: Option<Part>,
+        tree: &'a str,
+    },
+    File(FileRecord<'a>),
+    Diff {
+        diff: &'a str,
+    },
+    Commit(&'a Commit),
+    Stats(StatsRecord),
+}
+
+/// Writes `document` as a single JSON object, followed by a newline.
+pub(crate) fn write_json<W: Write>(output: &mut W, document: &Document) -> io::Result<()> {
+    let json = JsonDocument {
+        chunk: document.chunk,
+        tree: document.trees.join("\n"),
+        files: document.files.iter().map(FileRecord::new).collect(),
+        diff: document.diff.as_deref(),
+        commits: document.commits.as_deref(),
+        stats: StatsRecord::from(&document.stats),
+    };
+    serde_json::to_writer(&mut *output, &json)?;
+    writeln!(output)
+}
+
+/// Writes `document` as JSON Lines: a `tree` record per input root, one
+/// `file` record per file, any `diff` and `commit` records, then a `stats`
+/// record.
+pub(crate) fn write_jsonl<W: Write>(output: &mut W, document: &Document) -> io::Result<()> {
+    for tree in &document.trees {
+        write_jsonl_record(
+            output,
+            &JsonlRecord::Tree {
+                chunk: document.chunk,
+                tree,
+            },
+        )?;
+    }
+    for file in &document.files {
+        write_jsonl_record(output, &JsonlRecord::File(FileRecord::new(file)))?;
+    }
+    if let Some(diff) = &document.diff {
+        write_jsonl_record(output, &JsonlRecord::Diff { diff })?;
+    }
+    for commit in document.commits.iter().flatten() {
+        write_jsonl_record(output, &JsonlRecord::Commit(commit))?;
+    }
+    write_jsonl_record(
+        output,
+        &JsonlRecord::Stats(StatsRecord::from(&document.stats)),
+    )
+}
+
+fn write_jsonl_record<W: Write>(output: &mut W, record: &JsonlRecord<'_>) -> io::Result<()> {
+    serde_json::to_writer(&mut *output, record)?;
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use serde_json::{Value, json};
+
+    fn fixture() -> Document {
+        Document {
+            trees: vec!["root/\n  src/\n    main (copy).rs\n".to_string()],
+            files: vec![
+                FileBlock {
+                    path: PathBuf::from("/root/src/main (copy).rs"),
+                    display_path: PathBuf::from("src/main (copy).rs"),
+                    size: 13,
+                    text: "fn main() {}\n".to_string(),
+                    tokens: 4,
+                    part: None,
+                    matched_lines: Vec::new(),
+                    line_numbers: Vec::new(),
+                    stripped_size: 0,
+                    redactions: 0,
+                },
+                FileBlock {
+                    path: PathBuf::from("/root/notes"),
+                    display_path: PathBuf::from("notes"),
+                    size: 9,
+                    text: "\"quoted\"\n".to_string(),
+                    tokens: 3,
+                    part: None,
+                    matched_lines: Vec::new(),
+                    line_numbers: Vec::new(),
+                    stripped_size: 0,
+                    redactions: 0,
+                },
+            ],
+            stats: ScanStats {
+                total_size: 30,
+                written_size: 22,
+                binary_size: 2,
+                tokens: 15,
+                ..ScanStats::default()
+            },
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: None,
+        }
+    }
+
+    #[test]
+    fn write_json_writes_one_object_with_tree_files_and_stats() {
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &fixture()).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        assert!(text.ends_with("}\n"));
+        assert_eq!(text.lines().count(), 1);
+
+        let value: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "tree": "root/\n  src/\n    main (copy).rs\n",
+                "files": [
+                    {
+                        "path": "src/main (copy).rs",
+                        "size": 13,
+                        "language": "rust",
+                        "tokens": 4,
+                        "content": "fn main() {}\n",
+                    },
+                    {
+                        "path": "notes",
+                        "size": 9,
+                        "language": "",
+                        "tokens": 3,
+                        "content": "\"quoted\"\n",
+                    },
+                ],
+                "stats": {
+                    "total_size": 30,
+                    "written_size": 22,
+                    "binary_size": 2,
+                    "grep_filtered_size": 0,
+                    "size_filtered": 6,
+                    "budget_dropped_size": 0,
+                    "stripped_size": 0,
+                    "redactions": 0,
+                    "diff_size": 0,
+                    "tokens": 15,
+                    "tokenizer": "chars",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn write_jsonl_writes_tree_then_one_line_per_file_then_stats() {
+        let mut output: Vec<u8> = Vec::new();
+        write_jsonl(&mut output, &fixture()).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        let records: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records[0],
+            json!({"type": "tree", "tree": "root/\n  src/\n    main (copy).rs\n"})
+        );
+        assert_eq!(
+            records[1],
+            json!({
+                "type": "file",
+                "path": "src/main (copy).rs",
+                "size": 13,
+                "language": "rust",
+                "tokens": 4,
+                "content": "fn main() {}\n",
+            })
+        );
+        assert_eq!(records[2]["type"], "file");
+        assert_eq!(records[2]["path"], "notes");
+        assert_eq!(records[3]["type"], "stats");
+        assert_eq!(records[3]["size_filtered"], 6);
+    }
+
+    #[test]
+    fn a_diff_is_a_json_field_and_a_jsonl_record_before_stats() {
+        let document = Document {
+            diff: Some("+added\n".to_string()),
+            commits: None,
+            ..fixture()
+        };
+
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &document).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["diff"], "+added\n");
+
+        let mut output: Vec<u8> = Vec::new();
+        write_jsonl(&mut output, &document).unwrap();
+        let records: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[3], json!({"type": "diff", "diff": "+added\n"}));
+        assert_eq!(records[4]["type"], "stats");
+    }
+
+    #[test]
+    fn commits_are_a_json_array_and_one_jsonl_record_each() {
+        let commit = json!({
+            "hash": "abc1234",
+            "date": "2026-01-02",
+            "subject": "Fix the parser",
+            "paths": ["src/a.rs"],
+        });
+        let document = Document {
+            commits: Some(vec![Commit {
+                hash: "abc1234".to_string(),
+                date: "2026-01-02".to_string(),
+                subject: "Fix the parser".to_string(),
+                paths: vec!["src/a.rs".to_string()],
+            }]),
+            ..fixture()
+        };
+
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &document).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["commits"], json!([commit]));
+
+        let mut output: Vec<u8> = Vec::new();
+        write_jsonl(&mut output, &document).unwrap();
+        let records: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let mut expected = commit;
+        expected["type"] = json!("commit");
+        assert_eq!(records[3], expected);
+        assert_eq!(records[4]["type"], "stats");
+    }
+
+    #[test]
+    fn chunks_and_file_parts_are_numbered_when_present() {
+        let mut document = fixture();
+        document.chunk = Some(Part { index: 2, count: 5 });
+        document.files[0].part = Some(Part { index: 1, count: 2 });
+
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &document).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["chunk"], json!({"index": 2, "count": 5}));
+        assert_eq!(value["files"][0]["part"], json!({"index": 1, "count": 2}));
+        assert!(value["files"][1].get("part").is_none());
+
+        let mut output: Vec<u8> = Vec::new();
+        write_jsonl(&mut output, &document).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let tree: Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(tree["chunk"], json!({"index": 2, "count": 5}));
+    }
+
+    #[test]
+    fn line_numbers_are_an_array_beside_the_unnumbered_content() {
+        let mut document = fixture();
+        document.files[0].line_numbers = vec![4];
+
+        let mut output: Vec<u8> = Vec::new();
+        write_json(&mut output, &document).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["files"][0]["line_numbers"], json!([4]));
+        assert_eq!(value["files"][0]["content"], "fn main() {}\n");
+        assert!(value["files"][1].get("line_numbers").is_none());
+    }
+
+    #[test]
+    fn write_jsonl_writes_only_tree_and_stats_without_files() {
+        let document = Document {
+            files: Vec::new(),
+            ..fixture()
+        };
+        let mut output: Vec<u8> = Vec::new();
+        write_jsonl(&mut output, &document).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        let types: Vec<String> = text
+            .lines()
+            .map(|line| {
+                let value: Value = serde_json::from_str(line).unwrap();
+                value["type"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(types, ["tree", "stats"]);
+    }
+}
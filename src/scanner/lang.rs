@@ -1,5 +1,26 @@
-//! Maps a file extension to the language identifier used on its Markdown
-//! code fence.
+//! Maps a file extension (or, for a few files, a name) to the language
+//! identifier used on its Markdown code fence.
+
+use std::path::Path;
+
+/// Returns the fence language tag for `path`'s extension, or `""` when it
+/// isn't recognized. Files known by name rather than extension
+/// (`Makefile`, `Dockerfile`) are tagged by name.
+pub(crate) fn path_language(path: &Path) -> &'static str {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_lowercase);
+    match name.as_deref() {
+        Some("makefile" | "gnumakefile") => "makefile",
+        Some("dockerfile" | "containerfile") => "dockerfile",
+        _ => path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| fence_language(&ext.to_lowercase()))
+            .unwrap_or_default(),
+    }
+}
 
 /// Returns the fence language tag for `ext` (lower-cased, no leading dot),
 /// or `""` if the extension isn't recognized (a plain, untagged fence is
@@ -29,7 +50,8 @@ pub(crate) fn fence_language(ext: &str) -> &'static str {
         "md" | "markdown" => "markdown",
         "html" | "htm" => "html",
         "css" => "css",
-        "scss" | "sass" => "scss",
+        "scss" => "scss",
+        "sass" => "sass",
         "sql" => "sql",
         "swift" => "swift",
         "dart" => "dart",
@@ -99,7 +121,7 @@ mod tests {
             ("htm", "html"),
             ("css", "css"),
             ("scss", "scss"),
-            ("sass", "scss"),
+            ("sass", "sass"),
             ("sql", "sql"),
             ("swift", "swift"),
             ("dart", "dart"),
@@ -132,6 +154,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn path_language_tags_makefiles_and_dockerfiles_by_name() {
+        assert_eq!(path_language(Path::new("src/main.RS")), "rust");
+        assert_eq!(path_language(Path::new("build/Makefile")), "makefile");
+        assert_eq!(path_language(Path::new("GNUmakefile")), "makefile");
+        assert_eq!(path_language(Path::new("Dockerfile")), "dockerfile");
+        assert_eq!(path_language(Path::new("LICENSE")), "");
+    }
+
     #[test]
     fn returns_empty_string_for_unknown_extensions() {
         assert_eq!(fence_language("xyz"), "");
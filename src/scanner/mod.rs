@@ -7,12 +7,26 @@
 //! for the reasoning behind individual choices (e.g. why the output-file
 //! identity check in `filter` avoids `canonicalize`).
 
+#[cfg(feature = "archive")]
+mod archive;
+mod budget;
+mod chunk;
 mod collect;
+mod comments;
+mod compact;
+mod document;
+mod file_list;
 mod filter;
+mod grep;
+mod json;
 mod lang;
 mod process;
+mod redact;
+mod roots;
 mod scan;
 mod tree;
 mod walker;
+mod xml;
 
-pub use scan::{ScanStats, scan};
+pub use collect::{Entry, collect_entries};
+pub use scan::{ChunkStats, FileStats, ScanStats, scan, scan_to_writer};
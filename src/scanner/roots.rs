@@ -0,0 +1,213 @@
+//! Splits a run over `config.directory` and `config.extra_directories` into
+//! one [`Root`] per input root, each scanned on its own with the same
+//! filters, and labels every root for its tree and headings.
+//!
+//! A single root keeps the label its tree always had (the directory's
+//! name), and its headings stay relative to it. With several roots, each
+//! label is the root's canonical name, lengthened with parent directories
+//! until no two labels collide (`api/src` and `web/src` rather than two
+//! `src`), and headings start with the label, so `README.md` in two roots
+//! stays two distinct headings.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::Result;
+
+use super::tree::root_label;
+
+/// One input root of a run.
+pub(crate) struct Root {
+    /// The run's config, scanning just this root: `directory` is the root
+    /// and `extra_directories` is empty.
+    pub config: Config,
+    /// Label on the first line of the root's tree.
+    pub label: String,
+}
+
+/// Returns `config`'s roots, in order.
+///
+/// # Errors
+///
+/// Fails with an [`io::ErrorKind::InvalidInput`] error for several roots
+/// combined with `config.diff` or `config.git_log`, which describe a single
+/// repository, or `config.files_from`, whose paths are relative to a single
+/// root, or when the same root is listed twice; and with the
+/// underlying I/O error when a root can't be resolved.
+pub(crate) fn roots(config: &Config) -> Result<Vec<Root>> {
+    if config.extra_directories.is_empty() {
+        return Ok(vec![Root {
+            label: root_label(&config.directory).to_string(),
+            config: config.clone(),
+        }]);
+    }
+
+    for (key, set) in [
+        ("diff", config.diff.is_some()),
+        ("git_log", config.git_log.is_some()),
+        ("files_from", config.files_from.is_some()),
+    ] {
+        if set {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{key} needs a single input directory, not several"),
+            )
+            .into());
+        }
+    }
+
+    let directories: Vec<PathBuf> = config.directories().map(Path::to_path_buf).collect();
+    let canonical = directories
+        .iter()
+        .map(fs::canonicalize)
+        .collect::<io::Result<Vec<_>>>()?;
+    let labels = unique_labels(&canonical).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the same input directory is listed more than once",
+        )
+    })?;
+
+    Ok(directories
+        .into_iter()
+        .zip(labels)
+        .map(|(directory, label)| Root {
+            config: Config {
+                directory,
+                extra_directories: Vec::new(),
+                ..config.clone()
+            },
+            label,
+        })
+        .collect())
+}
+
+/// Labels each of `paths` (canonical, so absolute) with its last
+/// component, adding parent components to colliding labels until every
+/// label is unique. Returns `None` if two paths are the same.
+fn unique_labels(paths: &[PathBuf]) -> Option<Vec<String>> {
+    let components: Vec<Vec<Component<'_>>> = paths
+        .iter()
+        .map(|path| path.components().collect())
+        .collect();
+    let mut lengths = vec![1; paths.len()];
+    loop {
+        let labels: Vec<String> = components
+            .iter()
+            .zip(&lengths)
+            .map(|(components, &length)| label(components, length))
+            .collect();
+
+        let mut lengthened = false;
+        let mut collided = false;
+        for (index, own) in labels.iter().enumerate() {
+            if labels.iter().filter(|other| *other == own).count() > 1 {
+                collided = true;
+                if lengths[index] < components[index].len() {
+                    lengths[index] += 1;
+                    lengthened = true;
+                }
+            }
+        }
+        if !collided {
+            return Some(labels);
+        }
+        if !lengthened {
+            return None;
+        }
+    }
+}
+
+/// Joins the last `length` of `components` with `/`.
+fn label(components: &[Component<'_>], length: usize) -> String {
+    let start = components.len().saturating_sub(length);
+    let path: PathBuf = components[start..].iter().collect();
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels_of(paths: &[&str]) -> Option<Vec<String>> {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        unique_labels(&paths)
+    }
+
+    #[test]
+    fn unique_labels_use_the_last_component_when_it_is_unique() {
+        assert_eq!(
+            labels_of(&["/work/backend", "/work/shared-protos"]),
+            Some(vec!["backend".to_string(), "shared-protos".to_string()])
+        );
+    }
+
+    #[test]
+    fn unique_labels_lengthen_only_the_colliding_labels() {
+        assert_eq!(
+            labels_of(&["/work/api/src", "/work/web/src", "/work/docs"]),
+            Some(vec![
+                "api/src".to_string(),
+                "web/src".to_string(),
+                "docs".to_string()
+            ])
+        );
+        assert_eq!(
+            labels_of(&["/a/src", "/x/a/src"]),
+            Some(vec!["/a/src".to_string(), "x/a/src".to_string()])
+        );
+    }
+
+    #[test]
+    fn unique_labels_reject_the_same_path_twice() {
+        assert_eq!(labels_of(&["/work/api", "/work/api"]), None);
+    }
+
+    #[test]
+    fn a_single_root_keeps_its_config_and_plain_label() {
+        let config = Config::builder().directory("/work/backend").build();
+        let roots = roots(&config).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].label, "backend");
+        assert_eq!(roots[0].config, config);
+    }
+
+    #[test]
+    fn several_roots_get_one_config_each() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("api/src")).unwrap();
+        fs::create_dir_all(dir.path().join("web/src")).unwrap();
+        let config = Config::builder()
+            .directories([dir.path().join("api/src"), dir.path().join("web/src")])
+            .build();
+
+        let roots = roots(&config).unwrap();
+        let labels: Vec<&str> = roots.iter().map(|root| root.label.as_str()).collect();
+        assert_eq!(labels, ["api/src", "web/src"]);
+        assert_eq!(roots[1].config.directory, dir.path().join("web/src"));
+        assert!(roots[1].config.extra_directories.is_empty());
+    }
+
+    #[test]
+    fn several_roots_reject_diff_git_log_and_repeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::builder()
+            .directories([dir.path(), dir.path()])
+            .build();
+        let err = roots(&config).err().unwrap();
+        assert!(err.to_string().contains("listed more than once"));
+
+        let other = tempfile::tempdir().unwrap();
+        let config = Config::builder()
+            .directories([dir.path(), other.path()])
+            .git_log(3)
+            .build();
+        let err = roots(&config).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "git_log needs a single input directory, not several"
+        );
+    }
+}
@@ -2,11 +2,17 @@
 //! either as a minimal indented list (the default) or as a `tree`-style
 //! connector diagram when `config.human` is set.
 
+use std::collections::HashMap;
 use std::fmt::Write;
 use std::path::Path;
 
 use super::collect::Entry;
 
+/// Labels shown in brackets after specific entries' names (e.g. `a.rs
+/// [omitted]`), keyed by the entry's absolute path. Entries without a label
+/// render as usual.
+pub(crate) type TreeMarks<'a> = HashMap<&'a Path, &'a str>;
+
 /// A directory tree node, built from a flat [`Entry`] list. Only needed for
 /// the connector-style ([`render_glyph_tree`]) renderer, which has to know
 /// each node's siblings to draw `├──` vs `└──`.
@@ -15,41 +21,73 @@ struct Node<'e> {
     name: &'e str,
     /// Whether this node is a directory rather than a file.
     is_dir: bool,
+    /// Label from [`TreeMarks`], if any.
+    mark: Option<&'e str>,
     /// Direct children, in walk order; always empty for files.
     children: Vec<Node<'e>>,
 }
 
-/// Renders `entries` (already walked and filtered) as a tree, prefixed with
-/// a `- Tree Structure` header.
+/// One input root's tree, before rendering: the label on its first line,
+/// and its walked entries.
+pub(crate) struct RootTree<'a> {
+    pub label: &'a str,
+    pub entries: &'a [Entry],
+}
+
+/// Renders each of `trees` with [`render_tree`], in order.
+pub(crate) fn render_trees(
+    trees: &[RootTree<'_>],
+    human: bool,
+    marks: &TreeMarks<'_>,
+) -> Vec<String> {
+    trees
+        .iter()
+        .map(|tree| render_tree(tree.entries, tree.label, human, marks))
+        .collect()
+}
+
+/// Renders `entries` (already walked and filtered) as a tree: the root's
+/// `label` (see [`root_label`]) on the first line, then one line per entry.
 ///
 /// Uses connector-style glyphs (`├──`, `└──`, `│`) when `human` is set, or a
 /// minimal two-space indent otherwise (the default: fewer bytes, just as
 /// easy for an LLM to parse from depth alone).
-pub(crate) fn render_tree(entries: &[Entry], root: &Path, human: bool) -> String {
+///
+/// Any entry in `marks` gets its label appended in brackets.
+///
+/// Carries no section header of its own, so each output format can wrap it
+/// in its own (a `- Tree Structure` heading, a `<tree>` element, ...).
+pub(crate) fn render_tree(
+    entries: &[Entry],
+    label: &str,
+    human: bool,
+    marks: &TreeMarks<'_>,
+) -> String {
     let mut structure = String::new();
-    structure.push_str("- Tree Structure\n\n");
-
-    let root_label = root
-        .file_name()
-        .and_then(|name| name.to_str())
-        .filter(|name| !name.is_empty())
-        .unwrap_or(".");
 
     // `fmt::Write` on `String` never actually fails (no allocation can
     // realistically be exhausted here), so these are infallible in
     // practice.
     if human {
-        writeln!(structure, "{root_label}").expect("String write is infallible");
-        render_glyph_tree(&build_tree(entries), "", &mut structure);
+        writeln!(structure, "{label}").expect("String write is infallible");
+        render_glyph_tree(&build_tree(entries, marks), "", &mut structure);
     } else {
-        writeln!(structure, "{root_label}/").expect("String write is infallible");
-        render_indent_tree(entries, &mut structure);
+        writeln!(structure, "{label}/").expect("String write is infallible");
+        render_indent_tree(entries, marks, &mut structure);
     }
 
-    structure.push('\n');
     structure
 }
 
+/// Returns the label a single root's tree starts with: `root`'s file name,
+/// or `.` when it has none (`.`, `/`, or an empty path).
+pub(crate) fn root_label(root: &Path) -> &str {
+    root.file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(".")
+}
+
 /// Returns `entry`'s file name, or an empty string if it has none.
 fn entry_name(entry: &Entry) -> &str {
     entry
@@ -59,25 +97,32 @@ fn entry_name(entry: &Entry) -> &str {
         .unwrap_or_default()
 }
 
+/// Formats `mark` as a bracketed suffix (` [label]`), or `""` for `None`.
+fn mark_suffix(mark: Option<&str>) -> String {
+    mark.map(|label| format!(" [{label}]")).unwrap_or_default()
+}
+
 /// Appends `entries` to `output` as a minimal two-space-per-depth indented
 /// list, suffixing directories with `/`.
-fn render_indent_tree(entries: &[Entry], output: &mut String) {
+fn render_indent_tree(entries: &[Entry], marks: &TreeMarks<'_>, output: &mut String) {
     for entry in entries {
         let indent = "  ".repeat(entry.depth);
         let marker = if entry.is_dir { "/" } else { "" };
-        writeln!(output, "{indent}{}{marker}", entry_name(entry))
+        let suffix = mark_suffix(marks.get(entry.path.as_path()).copied());
+        writeln!(output, "{indent}{}{marker}{suffix}", entry_name(entry))
             .expect("String write is infallible");
     }
 }
 
 /// Rebuilds the tree structure implied by `entries`' depths (a preorder
 /// sequence, one depth increase per level) into nested [`Node`]s.
-fn build_tree(entries: &[Entry]) -> Vec<Node<'_>> {
-    build_children(&mut entries.iter().peekable(), 1)
+fn build_tree<'e>(entries: &'e [Entry], marks: &TreeMarks<'e>) -> Vec<Node<'e>> {
+    build_children(&mut entries.iter().peekable(), marks, 1)
 }
 
 fn build_children<'e>(
     entries: &mut std::iter::Peekable<std::slice::Iter<'e, Entry>>,
+    marks: &TreeMarks<'e>,
     depth: usize,
 ) -> Vec<Node<'e>> {
     let mut children = Vec::new();
@@ -90,7 +135,7 @@ fn build_children<'e>(
 
         let node_children = if entry.is_dir {
             match entries.peek() {
-                Some(next) if next.depth > depth => build_children(entries, depth + 1),
+                Some(next) if next.depth > depth => build_children(entries, marks, depth + 1),
                 _ => Vec::new(),
             }
         } else {
@@ -100,6 +145,7 @@ fn build_children<'e>(
         children.push(Node {
             name: entry_name(entry),
             is_dir: entry.is_dir,
+            mark: marks.get(entry.path.as_path()).copied(),
             children: node_children,
         });
     }
@@ -114,7 +160,8 @@ fn render_glyph_tree(nodes: &[Node<'_>], prefix: &str, output: &mut String) {
         let is_last = index == nodes.len() - 1;
         let connector = if is_last { "└── " } else { "├── " };
         let marker = if node.is_dir { "/" } else { "" };
-        writeln!(output, "{prefix}{connector}{}{marker}", node.name)
+        let suffix = mark_suffix(node.mark);
+        writeln!(output, "{prefix}{connector}{}{marker}{suffix}", node.name)
             .expect("String write is infallible");
 
         if !node.children.is_empty() {
@@ -163,19 +210,16 @@ mod tests {
 
     #[test]
     fn renders_indent_tree_for_nested_entries() {
-        let out = render_tree(&deep_entries(), Path::new("myproj"), false);
-        assert_eq!(
-            out,
-            "- Tree Structure\n\nmyproj/\n  a/\n    a1\n    a2/\n      a2a\n  b\n\n"
-        );
+        let out = render_tree(&deep_entries(), "myproj", false, &TreeMarks::new());
+        assert_eq!(out, "myproj/\n  a/\n    a1\n    a2/\n      a2a\n  b\n");
     }
 
     #[test]
     fn renders_glyph_tree_for_nested_entries() {
-        let out = render_tree(&deep_entries(), Path::new("myproj"), true);
+        let out = render_tree(&deep_entries(), "myproj", true, &TreeMarks::new());
         assert_eq!(
             out,
-            "- Tree Structure\n\nmyproj\n├── a/\n│   ├── a1\n│   └── a2/\n│       └── a2a\n└── b\n\n"
+            "myproj\n├── a/\n│   ├── a1\n│   └── a2/\n│       └── a2a\n└── b\n"
         );
     }
 
@@ -185,20 +229,20 @@ mod tests {
         // and `y` is last with nothing after it at all (peek() is None).
         // Both should hit the `_ => Vec::new()` arm in `build_children`.
         let entries = vec![entry("x", 1, true), entry("y", 1, true)];
-        let out = render_tree(&entries, Path::new("myproj"), true);
-        assert_eq!(out, "- Tree Structure\n\nmyproj\n├── x/\n└── y/\n\n");
+        let out = render_tree(&entries, "myproj", true, &TreeMarks::new());
+        assert_eq!(out, "myproj\n├── x/\n└── y/\n");
     }
 
     #[test]
-    fn renders_empty_entries_as_header_and_root_only_indent() {
-        let out = render_tree(&[], Path::new("myproj"), false);
-        assert_eq!(out, "- Tree Structure\n\nmyproj/\n\n");
+    fn renders_empty_entries_as_root_only_indent() {
+        let out = render_tree(&[], "myproj", false, &TreeMarks::new());
+        assert_eq!(out, "myproj/\n");
     }
 
     #[test]
-    fn renders_empty_entries_as_header_and_root_only_glyph() {
-        let out = render_tree(&[], Path::new("myproj"), true);
-        assert_eq!(out, "- Tree Structure\n\nmyproj\n\n");
+    fn renders_empty_entries_as_root_only_glyph() {
+        let out = render_tree(&[], "myproj", true, &TreeMarks::new());
+        assert_eq!(out, "myproj\n");
     }
 
     #[test]
@@ -206,12 +250,27 @@ mod tests {
         // Empirically verified: `Path::new(".")`, `Path::new("/")`, and
         // `Path::new("")` all return `None` from `file_name()`.
         for root in [Path::new("."), Path::new("/"), Path::new("")] {
-            let out = render_tree(&[], root, false);
-            assert_eq!(out, "- Tree Structure\n\n./\n\n", "root = {root:?}");
-
-            let out_human = render_tree(&[], root, true);
-            assert_eq!(out_human, "- Tree Structure\n\n.\n\n", "root = {root:?}");
+            assert_eq!(root_label(root), ".", "root = {root:?}");
         }
+        assert_eq!(root_label(Path::new("/work/myproj")), "myproj");
+    }
+
+    #[test]
+    fn marked_entries_get_a_bracketed_label_in_both_styles() {
+        let entries = deep_entries();
+        let marks = TreeMarks::from([(Path::new("a/a1"), "omitted"), (Path::new("a/a2"), "x")]);
+
+        let out = render_tree(&entries, "myproj", false, &marks);
+        assert_eq!(
+            out,
+            "myproj/\n  a/\n    a1 [omitted]\n    a2/ [x]\n      a2a\n  b\n"
+        );
+
+        let out = render_tree(&entries, "myproj", true, &marks);
+        assert_eq!(
+            out,
+            "myproj\n├── a/\n│   ├── a1 [omitted]\n│   └── a2/ [x]\n│       └── a2a\n└── b\n"
+        );
     }
 
     #[test]
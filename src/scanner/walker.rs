@@ -94,25 +94,7 @@ mod tests {
     /// A baseline [`Config`] with every filter at its "do nothing special"
     /// default. Individual tests override only the fields they care about.
     fn base_config(directory: &std::path::Path) -> Config {
-        Config {
-            directory: directory.to_path_buf(),
-            output: PathBuf::from("fyai.txt"),
-            include_dirs: None,
-            exclude_dirs: None,
-            include_ext: None,
-            exclude_ext: None,
-            include_files: None,
-            exclude_files: None,
-            min_size: None,
-            max_size: None,
-            hidden: true,
-            gitignore: true,
-            ignore_files: true,
-            git_global: true,
-            follow_links: false,
-            tree_only: false,
-            human: false,
-        }
+        Config::builder().directory(directory).build()
     }
 
     /// Runs `config`'s walker to completion and returns every visited path
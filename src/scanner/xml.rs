@@ -0,0 +1,402 @@
+//! Writes the XML output format: one `<documents>` element, shaped the way
+//! Claude-style prompts expect multi-document input.
+//!
+//! ```text
+//! <documents>
+//! <tree>…</tree>
+//! <tree>…</tree>
+//! <document index="1">
+//! <source>src/main.rs</source>
+//! <document_content>…</document_content>
+//! </document>
+//! <diff>…</diff>
+//! <commits>
+//! <commit hash="…" date="…">
+//! <subject>…</subject>
+//! <path>…</path>
+//! </commit>
+//! </commits>
+//! <stats total_size="…" … />
+//! </documents>
+//! ```
+//!
+//! There's one `<tree>` per input root. The `<diff>` element is only there
+//! with `config.diff`, and `<commits>` only with `config.git_log`.
+//!
+//! Chunked output numbers its root (`<documents chunk="2" chunks="5">`),
+//! and a piece of a file split across chunks numbers its `<document>` the
+//! same way (`part="1" parts="3"`). With `config.grep_lines`, a
+//! `<document>` lists the lines `config.grep` matched on
+//! (`matched_lines="3, 17"`), and with `config.line_numbers`, the file
+//! lines its contents came from, as ranges (`line_numbers="1-12, 15-40"`);
+//! the contents themselves stay unnumbered.
+//!
+//! Every piece of text (tree, paths, file contents) is escaped with
+//! [`escape_text`], so a file containing `</document_content>` can't close
+//! its own element early.
+
+use std::io::{self, Write};
+
+use super::document::Document;
+
+/// Writes `document` as a single `<documents>` element.
+pub(crate) fn write_xml<W: Write>(output: &mut W, document: &Document) -> io::Result<()> {
+    match document.chunk {
+        Some(chunk) => writeln!(
+            output,
+            "<documents chunk=\"{}\" chunks=\"{}\">",
+            chunk.index, chunk.count
+        )?,
+        None => writeln!(output, "<documents>")?,
+    }
+    for tree in &document.trees {
+        write!(output, "<tree>\n{}</tree>\n", escape_text(tree))?;
+    }
+
+    for (index, file) in document.files.iter().enumerate() {
+        let source = file.display_path.display().to_string();
+        write!(output, "<document index=\"{}\"", index + 1)?;
+        if let Some(part) = file.part {
+            write!(output, " part=\"{}\" parts=\"{}\"", part.index, part.count)?;
+        }
+        if !file.matched_lines.is_empty() {
+            write!(output, " matched_lines=\"{}\"", file.matched_lines_list())?;
+        }
+        if !file.line_numbers.is_empty() {
+            write!(output, " line_numbers=\"{}\"", file.line_ranges())?;
+        }
+        writeln!(output, ">")?;
+        writeln!(output, "<source>{}</source>", escape_text(&source))?;
+        writeln!(output, "<document_content>")?;
+        write!(output, "{}", escape_text(&file.text))?;
+        if !file.text.ends_with('\n') {
+            writeln!(output)?;
+        }
+        writeln!(output, "</document_content>")?;
+        writeln!(output, "</document>")?;
+    }
+
+    if let Some(diff) = &document.diff {
+        write!(output, "<diff>\n{}</diff>\n", escape_text(diff))?;
+    }
+    if let Some(commits) = &document.commits {
+        writeln!(output, "<commits>")?;
+        for commit in commits {
+            writeln!(
+                output,
+                "<commit hash=\"{}\" date=\"{}\">",
+                escape_text(&commit.hash),
+                escape_text(&commit.date)
+            )?;
+            writeln!(
+                output,
+                "<subject>{}</subject>",
+                escape_text(&commit.subject)
+            )?;
+            for path in &commit.paths {
+                writeln!(output, "<path>{}</path>", escape_text(path))?;
+            }
+            writeln!(output, "</commit>")?;
+        }
+        writeln!(output, "</commits>")?;
+    }
+
+    let stats = &document.stats;
+    writeln!(
+        output,
+        "<stats total_size=\"{}\" written_size=\"{}\" binary_size=\"{}\" grep_filtered_size=\"{}\" size_filtered=\"{}\" budget_dropped_size=\"{}\" stripped_size=\"{}\" redactions=\"{}\" diff_size=\"{}\" tokens=\"{}\" tokenizer=\"{}\"/>",
+        stats.total_size,
+        stats.written_size,
+        stats.binary_size,
+        stats.grep_filtered_size,
+        stats.size_filtered(),
+        stats.budget_dropped_size,
+        stats.stripped_size,
+        stats.redactions,
+        stats.diff_size,
+        stats.tokens,
+        stats.tokenizer
+    )?;
+    writeln!(output, "</documents>")?;
+    Ok(())
+}
+
+/// Escapes `text` for use as XML character data or a double-quoted
+/// attribute value.
+///
+/// Besides the five markup characters, anything XML 1.0 can't represent at
+/// all, not even as a character reference (C0 controls other than tab,
+/// newline and carriage return, plus U+FFFE/U+FFFF), is replaced with
+/// U+FFFD, so the result is always well-formed, whatever the file held.
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            '\0'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.push('\u{FFFD}'),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::git::Commit;
+    use crate::scanner::ScanStats;
+    use crate::scanner::document::{FileBlock, Part};
+
+    fn render(document: &Document) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        write_xml(&mut output, document).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_xml_wraps_tree_files_and_stats_in_documents() {
+        let document = Document {
+            trees: vec!["root/\n  src/\n    main.rs\n".to_string()],
+            files: vec![FileBlock {
+                path: PathBuf::from("/root/src/main.rs"),
+                display_path: PathBuf::from("src/main.rs"),
+                size: 13,
+                text: "fn main() {}\n".to_string(),
+                tokens: 4,
+                part: None,
+                matched_lines: Vec::new(),
+                line_numbers: Vec::new(),
+                stripped_size: 0,
+                redactions: 0,
+            }],
+            stats: ScanStats {
+                total_size: 13,
+                written_size: 13,
+                binary_size: 0,
+                tokens: 10,
+                ..ScanStats::default()
+            },
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: None,
+        };
+
+        assert_eq!(
+            render(&document),
+            "<documents>\n\
+             <tree>\nroot/\n  src/\n    main.rs\n</tree>\n\
+             <document index=\"1\">\n\
+             <source>src/main.rs</source>\n\
+             <document_content>\nfn main() {}\n</document_content>\n\
+             </document>\n\
+             <stats total_size=\"13\" written_size=\"13\" binary_size=\"0\" grep_filtered_size=\"0\" size_filtered=\"0\" budget_dropped_size=\"0\" stripped_size=\"0\" redactions=\"0\" diff_size=\"0\" tokens=\"10\" tokenizer=\"chars\"/>\n\
+             </documents>\n"
+        );
+    }
+
+    #[test]
+    fn write_xml_numbers_documents_from_one_in_order() {
+        let document = Document {
+            trees: vec![String::new()],
+            files: vec![
+                FileBlock {
+                    path: PathBuf::from("/root/a.txt"),
+                    display_path: PathBuf::from("a.txt"),
+                    size: 1,
+                    text: "a".to_string(),
+                    tokens: 1,
+                    part: None,
+                    matched_lines: Vec::new(),
+                    line_numbers: Vec::new(),
+                    stripped_size: 0,
+                    redactions: 0,
+                },
+                FileBlock {
+                    path: PathBuf::from("/root/b.txt"),
+                    display_path: PathBuf::from("b.txt"),
+                    size: 1,
+                    text: "b".to_string(),
+                    tokens: 1,
+                    part: None,
+                    matched_lines: Vec::new(),
+                    line_numbers: Vec::new(),
+                    stripped_size: 0,
+                    redactions: 0,
+                },
+            ],
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: None,
+        };
+
+        let out = render(&document);
+        let first = out.find("<document index=\"1\">\n<source>a.txt</source>");
+        let second = out.find("<document index=\"2\">\n<source>b.txt</source>");
+        assert!(first.is_some() && second.is_some());
+        assert!(first < second);
+        // Text without a trailing newline still gets its closing tag on its
+        // own line.
+        assert!(out.contains("<document_content>\na\n</document_content>"));
+    }
+
+    #[test]
+    fn write_xml_escapes_file_contents_and_paths() {
+        let document = Document {
+            trees: vec!["root/\n  <odd>&name\n".to_string()],
+            files: vec![FileBlock {
+                path: PathBuf::from("/root/<odd>&name"),
+                display_path: PathBuf::from("<odd>&name"),
+                size: 40,
+                text: "if a < b && c > d {}\n</document_content>\n".to_string(),
+                tokens: 10,
+                part: None,
+                matched_lines: Vec::new(),
+                line_numbers: Vec::new(),
+                stripped_size: 0,
+                redactions: 0,
+            }],
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: None,
+        };
+
+        let out = render(&document);
+        assert!(out.contains("<tree>\nroot/\n  &lt;odd&gt;&amp;name\n</tree>"));
+        assert!(out.contains("<source>&lt;odd&gt;&amp;name</source>"));
+        assert!(out.contains("if a &lt; b &amp;&amp; c &gt; d {}\n&lt;/document_content&gt;\n"));
+        assert_eq!(out.matches("</document_content>").count(), 1);
+    }
+
+    #[test]
+    fn write_xml_numbers_chunks_and_file_parts_and_lists_matched_lines() {
+        let document = Document {
+            trees: vec![String::new()],
+            files: vec![FileBlock {
+                path: PathBuf::from("/root/big.txt"),
+                display_path: PathBuf::from("big.txt"),
+                size: 100,
+                text: "b\n".to_string(),
+                tokens: 1,
+                part: Some(Part { index: 2, count: 3 }),
+                matched_lines: vec![4, 9],
+                line_numbers: Vec::new(),
+                stripped_size: 0,
+                redactions: 0,
+            }],
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: Some(Part { index: 3, count: 4 }),
+            diff: None,
+            commits: None,
+        };
+
+        let out = render(&document);
+        assert!(out.starts_with("<documents chunk=\"3\" chunks=\"4\">\n"));
+        assert!(
+            out.contains("<document index=\"1\" part=\"2\" parts=\"3\" matched_lines=\"4, 9\">\n<source>big.txt</source>")
+        );
+        assert!(out.ends_with("</documents>\n"));
+    }
+
+    #[test]
+    fn write_xml_lists_line_numbers_as_ranges_and_leaves_contents_alone() {
+        let document = Document {
+            trees: vec![String::new()],
+            files: vec![FileBlock {
+                path: PathBuf::from("/root/a.rs"),
+                display_path: PathBuf::from("a.rs"),
+                size: 100,
+                text: "a\nb\nc\nd\n".to_string(),
+                tokens: 2,
+                part: None,
+                matched_lines: Vec::new(),
+                line_numbers: vec![1, 2, 3, 7, 9],
+                stripped_size: 0,
+                redactions: 0,
+            }],
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: None,
+        };
+
+        let out = render(&document);
+        assert!(out.contains(
+            "<document index=\"1\" line_numbers=\"1-3, 7, 9\">\n<source>a.rs</source>\n\
+             <document_content>\na\nb\nc\nd\n</document_content>"
+        ));
+    }
+
+    #[test]
+    fn write_xml_puts_an_escaped_diff_before_the_stats() {
+        let document = Document {
+            trees: vec![String::new()],
+            files: Vec::new(),
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: None,
+            diff: Some("-if a < b\n+if a <= b\n".to_string()),
+            commits: None,
+        };
+
+        let out = render(&document);
+        assert!(out.contains("<diff>\n-if a &lt; b\n+if a &lt;= b\n</diff>\n<stats "));
+    }
+
+    #[test]
+    fn write_xml_lists_commits_with_their_paths_before_the_stats() {
+        let document = Document {
+            trees: vec![String::new()],
+            files: Vec::new(),
+            stats: ScanStats::default(),
+            heading_tokens: false,
+            chunk: None,
+            diff: None,
+            commits: Some(vec![Commit {
+                hash: "abc1234".to_string(),
+                date: "2026-01-02".to_string(),
+                subject: "Fix <parser> & lexer".to_string(),
+                paths: vec!["src/a.rs".to_string(), "src/b.rs".to_string()],
+            }]),
+        };
+
+        let out = render(&document);
+        assert!(out.contains(
+            "<commits>\n\
+             <commit hash=\"abc1234\" date=\"2026-01-02\">\n\
+             <subject>Fix &lt;parser&gt; &amp; lexer</subject>\n\
+             <path>src/a.rs</path>\n\
+             <path>src/b.rs</path>\n\
+             </commit>\n\
+             </commits>\n\
+             <stats "
+        ));
+    }
+
+    #[test]
+    fn escape_text_escapes_markup_and_quote_characters() {
+        assert_eq!(
+            escape_text(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn escape_text_keeps_whitespace_and_replaces_other_control_characters() {
+        assert_eq!(escape_text("a\tb\r\nc"), "a\tb\r\nc");
+        assert_eq!(escape_text("nul\0esc\u{1b}"), "nul\u{FFFD}esc\u{FFFD}");
+    }
+}
@@ -0,0 +1,156 @@
+//! Token estimation for the combined output, so runs can be sized against a
+//! model's context window rather than a byte count.
+//!
+//! [`Tokenizer::Chars`] is a cheap, dependency-free estimate (one token per
+//! four characters, rounded up). With the default `tiktoken` feature, the
+//! OpenAI `cl100k_base` and `o200k_base` BPE vocabularies are also
+//! available; both are embedded in the binary, so counting never touches
+//! the network.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{FyaiError, Result};
+
+/// How tokens are counted.
+///
+/// Marked `#[non_exhaustive]`: which variants exist depends on the crate's
+/// features, and more may be added, so matches outside this crate need a
+/// wildcard arm.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Tokenizer {
+    /// One token per four characters, rounded up: no vocabulary to load,
+    /// and usually within ~20% of a real BPE count for source code.
+    #[default]
+    Chars,
+    /// The `cl100k_base` BPE vocabulary (GPT-4, GPT-3.5).
+    #[cfg(feature = "tiktoken")]
+    Cl100k,
+    /// The `o200k_base` BPE vocabulary (GPT-4o and later).
+    #[cfg(feature = "tiktoken")]
+    O200k,
+}
+
+impl Tokenizer {
+    /// Returns the number of tokens in `text`.
+    ///
+    /// The BPE vocabularies are loaded once per process, on first use, and
+    /// shared across threads from then on.
+    pub fn count(self, text: &str) -> u64 {
+        match self {
+            Self::Chars => (text.chars().count() as u64).div_ceil(4),
+            #[cfg(feature = "tiktoken")]
+            Self::Cl100k => tiktoken_rs::cl100k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+            #[cfg(feature = "tiktoken")]
+            Self::O200k => tiktoken_rs::o200k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+        }
+    }
+
+    /// Returns the lower-case name used by the `tokenizer` config key and
+    /// the `--tokenizer` flag.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Chars => "chars",
+            #[cfg(feature = "tiktoken")]
+            Self::Cl100k => "cl100k",
+            #[cfg(feature = "tiktoken")]
+            Self::O200k => "o200k",
+        }
+    }
+}
+
+impl fmt::Display for Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Tokenizer {
+    type Err = FyaiError;
+
+    /// Parses a name as returned by [`Tokenizer::name`].
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "chars" => Ok(Self::Chars),
+            #[cfg(feature = "tiktoken")]
+            "cl100k" => Ok(Self::Cl100k),
+            #[cfg(feature = "tiktoken")]
+            "o200k" => Ok(Self::O200k),
+            _ => Err(FyaiError::InvalidValue {
+                key: "tokenizer",
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chars_counts_one_token_per_four_characters_rounded_up() {
+        assert_eq!(Tokenizer::Chars.count(""), 0);
+        assert_eq!(Tokenizer::Chars.count("abc"), 1);
+        assert_eq!(Tokenizer::Chars.count("abcd"), 1);
+        assert_eq!(Tokenizer::Chars.count("abcde"), 2);
+    }
+
+    #[test]
+    fn chars_counts_characters_not_bytes() {
+        // 4 characters, 8 bytes.
+        assert_eq!(Tokenizer::Chars.count("éééé"), 1);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn bpe_tokenizers_count_real_tokens() {
+        assert_eq!(Tokenizer::Cl100k.count("hello world"), 2);
+        assert_eq!(Tokenizer::O200k.count("hello world"), 2);
+        assert_eq!(Tokenizer::Cl100k.count(""), 0);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn bpe_tokenizers_treat_special_token_text_as_ordinary_text() {
+        // A file that happens to contain `<|endoftext|>` must be counted, not
+        // rejected or collapsed to a single special token.
+        assert!(Tokenizer::Cl100k.count("<|endoftext|>") > 1);
+    }
+
+    #[test]
+    fn name_round_trips_through_from_str() {
+        let tokenizers = [
+            Tokenizer::Chars,
+            #[cfg(feature = "tiktoken")]
+            Tokenizer::Cl100k,
+            #[cfg(feature = "tiktoken")]
+            Tokenizer::O200k,
+        ];
+
+        for tokenizer in tokenizers {
+            assert_eq!(tokenizer.name().parse::<Tokenizer>().unwrap(), tokenizer);
+            assert_eq!(tokenizer.to_string(), tokenizer.name());
+        }
+    }
+
+    #[test]
+    fn from_str_rejects_unknown_names() {
+        let err = "gpt2".parse::<Tokenizer>().unwrap_err();
+        assert!(matches!(
+            err,
+            FyaiError::InvalidValue {
+                key: "tokenizer",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! End-to-end tests for the library API as an embedding application sees
+//! it: only the crate's public items, no binaries involved.
+
+use std::fs;
+use std::path::Path;
+
+use feedyourai::config::Config;
+use feedyourai::{Entry, collect_entries, scan_to_writer};
+
+/// A default [`Config`] for scanning `directory`, with its output file
+/// inside it.
+fn config_for(directory: &Path) -> Config {
+    Config::builder()
+        .directory(directory)
+        .output(directory.join("fyai.txt"))
+        .build()
+}
+
+#[test]
+fn scan_to_writer_combines_into_a_buffer_without_creating_the_output_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn main() {}\n").unwrap();
+
+    let mut buffer = Vec::new();
+    let stats = scan_to_writer(&config_for(dir.path()), &mut buffer).unwrap();
+
+    let contents = String::from_utf8(buffer).unwrap();
+    assert!(contents.contains("### a.rs"));
+    assert!(contents.contains("fn main() {}"));
+    assert_eq!(stats.written_size, 13);
+    assert_eq!(stats.files.len(), 1);
+    assert!(!dir.path().join("fyai.txt").exists());
+}
+
+#[test]
+fn collect_entries_lists_filtered_entries_without_writing_anything() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src").join("lib.rs"), "pub fn f() {}\n").unwrap();
+    fs::write(dir.path().join(".hidden"), "secret").unwrap();
+
+    let entries = collect_entries(&config_for(dir.path())).unwrap();
+
+    assert_eq!(
+        entries,
+        [
+            Entry {
+                path: dir.path().join("src"),
+                depth: 1,
+                is_dir: true,
+                size: None,
+            },
+            Entry {
+                path: dir.path().join("src").join("lib.rs"),
+                depth: 2,
+                is_dir: false,
+                size: Some(14),
+            },
+        ]
+    );
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+}